@@ -0,0 +1,407 @@
+// 첨부 파일 필드: 고른 파일을 워크스페이스 attachments 폴더에 내용 해시(SHA-256) 이름으로 복사하고,
+// 행에는 이름, 크기, 해시 목록을 JSON으로 저장한다. 같은 내용의 파일은 한 번만 저장된다
+use crate::workspace::sanitize_file_name;
+use crate::{CustomCategory, ERPApp, FieldType};
+use eframe::egui;
+use egui::Response;
+use rfd::FileDialog;
+use serde::{Deserialize, Serialize};
+use sha2::{Digest, Sha256};
+use std::collections::HashSet;
+use std::error::Error;
+use std::fs::{self, File};
+use std::io::{self, Read, Write};
+use std::path::{Path, PathBuf};
+use zip::write::FileOptions;
+use zip::{CompressionMethod, ZipArchive, ZipWriter};
+
+// 카테고리 내보내기 zip 안의 첨부 파일 폴더
+const ZIP_ATTACHMENTS_DIR: &str = "첨부 파일";
+
+#[derive(Clone, Default, Serialize, Deserialize, PartialEq, Debug)]
+pub(crate) struct Attachment {
+    pub(crate) name: String, // 원래 파일 이름
+    pub(crate) size: u64,
+    pub(crate) hash: String, // SHA-256 (소문자 16진수), 저장소 안의 파일 이름
+}
+
+pub(crate) fn parse_attachments(value: &str) -> Result<Vec<Attachment>, String> {
+    if value.trim().is_empty() {
+        return Ok(Vec::new());
+    }
+    serde_json::from_str(value).map_err(|e| format!("첨부 파일 목록을 읽을 수 없습니다: {}", e))
+}
+
+fn attachments_to_value(attachments: &[Attachment]) -> String {
+    if attachments.is_empty() {
+        return String::new();
+    }
+    serde_json::to_string(attachments).unwrap_or_default()
+}
+
+// 저장소 안의 경로: 해시 앞 두 글자 폴더로 나눈다
+pub(crate) fn stored_path(store: &Path, hash: &str) -> PathBuf {
+    store.join(hash.get(..2).unwrap_or("__")).join(hash)
+}
+
+fn hash_reader(mut reader: impl Read) -> io::Result<(String, u64)> {
+    let mut hasher = Sha256::new();
+    let size = io::copy(&mut reader, &mut hasher)?;
+    Ok((format!("{:x}", hasher.finalize()), size))
+}
+
+// 파일을 저장소에 복사 (이미 같은 내용이 있으면 그대로 둔다)
+pub(crate) fn store_file(store: &Path, source: &Path) -> io::Result<Attachment> {
+    let (hash, size) = hash_reader(File::open(source)?)?;
+    let target = stored_path(store, &hash);
+    if !target.exists() {
+        fs::create_dir_all(target.parent().unwrap_or(store))?;
+        // 복사 중에 멈춰도 반쯤 쓴 파일이 해시 이름으로 남지 않도록 임시 이름에 쓴 뒤 옮긴다
+        let temp = target.with_extension("tmp");
+        fs::copy(source, &temp)?;
+        fs::rename(&temp, &target)?;
+    }
+    let name = source
+        .file_name()
+        .map(|n| n.to_string_lossy().to_string())
+        .unwrap_or_else(|| hash.clone());
+    Ok(Attachment { name, size, hash })
+}
+
+// 바이트를 저장소에 넣고 해시 반환 (zip에서 되살릴 때)
+fn store_bytes(store: &Path, bytes: &[u8]) -> io::Result<String> {
+    let (hash, _) = hash_reader(bytes)?;
+    let target = stored_path(store, &hash);
+    if !target.exists() {
+        fs::create_dir_all(target.parent().unwrap_or(store))?;
+        let temp = target.with_extension("tmp");
+        fs::write(&temp, bytes)?;
+        fs::rename(&temp, &target)?;
+    }
+    Ok(hash)
+}
+
+// 1,234 B, 12.3 KB, 4.5 MB
+pub(crate) fn format_size(size: u64) -> String {
+    const UNITS: [&str; 4] = ["B", "KB", "MB", "GB"];
+    let mut value = size as f64;
+    let mut unit = 0;
+    while value >= 1024.0 && unit < UNITS.len() - 1 {
+        value /= 1024.0;
+        unit += 1;
+    }
+    if unit == 0 {
+        format!("{} B", size)
+    } else {
+        format!("{:.1} {}", value, UNITS[unit])
+    }
+}
+
+// 기본 프로그램으로 열기. 저장소 파일에는 확장자가 없으므로 원래 이름으로 임시 폴더에 복사해서 연다
+fn open_attachment(store: &Path, attachment: &Attachment) -> Result<(), Box<dyn Error>> {
+    let dir = std::env::temp_dir()
+        .join("erp_attachments")
+        .join(attachment.hash.get(..12).unwrap_or(&attachment.hash));
+    fs::create_dir_all(&dir)?;
+    let path = dir.join(sanitize_file_name(&attachment.name));
+    fs::copy(stored_path(store, &attachment.hash), &path)?;
+    open::that(&path)?;
+    Ok(())
+}
+
+fn export_attachment(store: &Path, attachment: &Attachment) -> Result<(), Box<dyn Error>> {
+    let Some(path) = FileDialog::new()
+        .set_file_name(&sanitize_file_name(&attachment.name))
+        .save_file()
+    else {
+        return Ok(());
+    };
+    fs::copy(stored_path(store, &attachment.hash), &path)?;
+    println!("첨부 파일 저장 완료: {}", path.display());
+    Ok(())
+}
+
+// 표의 첨부 파일 셀: 📎 버튼을 누르면 파일 목록과 추가/열기/내보내기/삭제.
+// 목록에서 빼도 저장소 파일은 정리할 때까지 남는다
+pub(crate) fn attachment_cell(
+    ui: &mut egui::Ui,
+    id: impl std::hash::Hash,
+    store: Option<&Path>,
+    value: &mut String,
+) -> Response {
+    ui.push_id(id, |ui| attachment_menu(ui, store, value)).inner
+}
+
+fn attachment_menu(ui: &mut egui::Ui, store: Option<&Path>, value: &mut String) -> Response {
+    let mut attachments = match parse_attachments(value) {
+        Ok(attachments) => attachments,
+        Err(e) => {
+            return ui
+                .colored_label(egui::Color32::RED, "#오류")
+                .on_hover_text(e)
+        }
+    };
+    let Some(store) = store else {
+        return ui.label(format!("📎 {}", attachments.len()));
+    };
+    let missing = attachments
+        .iter()
+        .filter(|a| !stored_path(store, &a.hash).exists())
+        .count();
+    let label = match (attachments.len(), missing) {
+        (0, _) => "📎 추가".to_string(),
+        (count, 0) => format!("📎 {}개", count),
+        (count, missing) => format!("📎 {}개 ⚠{}", count, missing),
+    };
+    let mut to_remove = None;
+    let mut changed = false;
+    let response = ui
+        .menu_button(label, |ui| {
+            for (idx, attachment) in attachments.iter().enumerate() {
+                ui.horizontal(|ui| {
+                    let exists = stored_path(store, &attachment.hash).exists();
+                    let text = format!("{} ({})", attachment.name, format_size(attachment.size));
+                    if exists {
+                        ui.label(text);
+                    } else {
+                        ui.colored_label(egui::Color32::RED, text)
+                            .on_hover_text("저장소에 파일이 없습니다");
+                    }
+                    if ui.add_enabled(exists, egui::Button::new("열기")).clicked() {
+                        if let Err(e) = open_attachment(store, attachment) {
+                            eprintln!("첨부 파일을 열 수 없습니다: {}", e);
+                        }
+                        ui.close_menu();
+                    }
+                    if ui
+                        .add_enabled(exists, egui::Button::new("💾 내보내기"))
+                        .clicked()
+                    {
+                        if let Err(e) = export_attachment(store, attachment) {
+                            eprintln!("첨부 파일 내보내기 실패: {}", e);
+                        }
+                        ui.close_menu();
+                    }
+                    if ui.button("🗑️").clicked() {
+                        to_remove = Some(idx);
+                    }
+                });
+            }
+            if !attachments.is_empty() {
+                ui.separator();
+            }
+            if ui.button("➕ 파일 추가").clicked() {
+                for path in FileDialog::new().pick_files().unwrap_or_default() {
+                    match store_file(store, &path) {
+                        Ok(attachment) => {
+                            attachments.push(attachment);
+                            changed = true;
+                        }
+                        Err(e) => eprintln!("첨부 파일 복사 실패 ({}): {}", path.display(), e),
+                    }
+                }
+                ui.close_menu();
+            }
+        })
+        .response;
+    if let Some(idx) = to_remove {
+        attachments.remove(idx);
+        changed = true;
+    }
+    if changed {
+        *value = attachments_to_value(&attachments);
+    }
+    response
+}
+
+impl ERPApp {
+    pub(crate) fn attachments_dir(&self) -> Option<PathBuf> {
+        self.workspace.as_ref().map(|w| w.attachments_dir())
+    }
+
+    // 모든 구조체의 첨부 파일 필드가 가리키는 해시
+    fn referenced_attachments(&self) -> HashSet<String> {
+        let mut hashes = HashSet::new();
+        for structure in self
+            .custom_structures
+            .iter()
+            .flat_map(|c| c.subcategories.iter())
+            .flat_map(|s| s.structures.iter())
+        {
+            let rows = self.erp_data.data.get(&structure.id);
+            for field in &structure.fields {
+                if field.field_type != FieldType::Attachment {
+                    continue;
+                }
+                for row in rows.into_iter().flatten() {
+                    let value = row.values.get(&field.id).map(|v| v.value.as_str());
+                    let attachments = parse_attachments(value.unwrap_or_default());
+                    hashes.extend(attachments.unwrap_or_default().into_iter().map(|a| a.hash));
+                }
+            }
+        }
+        hashes
+    }
+
+    // 어느 행에서도 쓰지 않는 저장소 파일 삭제. 지운 파일 수
+    pub(crate) fn clean_orphan_attachments(&self) -> io::Result<usize> {
+        if self.writes_blocked() {
+            return Ok(0);
+        }
+        let Some(store) = self.attachments_dir() else {
+            return Ok(0);
+        };
+        let referenced = self.referenced_attachments();
+        let mut removed = 0;
+        for dir in fs::read_dir(&store)? {
+            let dir = dir?.path();
+            if !dir.is_dir() {
+                continue;
+            }
+            for file in fs::read_dir(&dir)? {
+                let path = file?.path();
+                let name = path
+                    .file_name()
+                    .map(|n| n.to_string_lossy().to_string())
+                    .unwrap_or_default();
+                if !referenced.contains(&name) {
+                    fs::remove_file(&path)?;
+                    removed += 1;
+                }
+            }
+            // 비었으면 폴더도 지운다 (실패해도 무시)
+            let _ = fs::remove_dir(&dir);
+        }
+        Ok(removed)
+    }
+
+    // 카테고리에 첨부 파일이 하나라도 있는지 (통합 문서를 zip으로 묶을지)
+    pub(crate) fn category_has_attachments(&self, category: &CustomCategory) -> bool {
+        category
+            .subcategories
+            .iter()
+            .flat_map(|s| s.structures.iter())
+            .any(|structure| {
+                let rows = self.erp_data.data.get(&structure.id);
+                structure
+                    .fields
+                    .iter()
+                    .filter(|f| f.field_type == FieldType::Attachment)
+                    .any(|field| {
+                        rows.into_iter().flatten().any(|row| {
+                            row.values
+                                .get(&field.id)
+                                .is_some_and(|v| !v.value.trim().is_empty())
+                        })
+                    })
+            })
+    }
+
+    // 통합 문서와 첨부 파일을 zip 하나로: 통합 문서는 맨 위, 첨부 파일은
+    // "첨부 파일/시트/행/필드/해시 앞 8자_파일 이름" (같은 행에 이름이 같은 다른 파일이 있어도 모두 넣는다)
+    pub(crate) fn write_category_zip(
+        &self,
+        category: &CustomCategory,
+        workbook: &Path,
+        sheets: &[(String, String)], // (구조체 ID, 시트 이름)
+        path: &Path,
+    ) -> Result<(), Box<dyn Error>> {
+        let store = self
+            .attachments_dir()
+            .ok_or("열린 워크스페이스가 없습니다")?;
+        let mut zip = ZipWriter::new(File::create(path)?);
+        let options = FileOptions::default().compression_method(CompressionMethod::Deflated);
+        zip.start_file(
+            format!("{}.xlsx", sanitize_file_name(&category.name)),
+            options,
+        )?;
+        zip.write_all(&fs::read(workbook)?)?;
+
+        let mut written = HashSet::new();
+        for (structure_id, sheet) in sheets {
+            let Some(structure) = self.find_structure(structure_id) else {
+                continue;
+            };
+            let rows = self
+                .erp_data
+                .data
+                .get(structure_id)
+                .cloned()
+                .unwrap_or_default();
+            for (idx, row) in rows.iter().enumerate() {
+                for field in &structure.fields {
+                    if field.field_type != FieldType::Attachment {
+                        continue;
+                    }
+                    let value = row.values.get(&field.id).map(|v| v.value.as_str());
+                    for attachment in
+                        parse_attachments(value.unwrap_or_default()).unwrap_or_default()
+                    {
+                        // 시트의 행 번호 (머리글 다음부터)
+                        let name = format!(
+                            "{}/{}/{}행/{}/{}_{}",
+                            ZIP_ATTACHMENTS_DIR,
+                            sanitize_file_name(sheet),
+                            idx + 2,
+                            sanitize_file_name(&field.name),
+                            attachment.hash.get(..8).unwrap_or(&attachment.hash),
+                            sanitize_file_name(&attachment.name)
+                        );
+                        if !written.insert(name.clone()) {
+                            continue;
+                        }
+                        match fs::read(stored_path(&store, &attachment.hash)) {
+                            Ok(bytes) => {
+                                zip.start_file(name, options)?;
+                                zip.write_all(&bytes)?;
+                            }
+                            Err(e) => eprintln!(
+                                "첨부 파일을 넣지 못했습니다 ({}): {}",
+                                attachment.name, e
+                            ),
+                        }
+                    }
+                }
+            }
+        }
+        zip.finish()?;
+        Ok(())
+    }
+
+    // 카테고리 zip에서 첨부 파일을 저장소에 되살리고 통합 문서를 임시 폴더에 꺼낸다
+    pub(crate) fn unpack_category_zip(&self, path: &Path) -> Result<PathBuf, Box<dyn Error>> {
+        let store = self
+            .attachments_dir()
+            .ok_or("열린 워크스페이스가 없습니다")?;
+        let mut archive = ZipArchive::new(File::open(path)?)?;
+        let mut workbook = None;
+        let mut restored = 0;
+        for i in 0..archive.len() {
+            let mut entry = archive.by_index(i)?;
+            if entry.is_dir() {
+                continue;
+            }
+            let name = entry.name().to_string();
+            let mut bytes = Vec::new();
+            entry.read_to_end(&mut bytes)?;
+            if name.starts_with(ZIP_ATTACHMENTS_DIR) {
+                // 내용 해시로 저장하므로 zip 안의 이름과 상관없이 행의 해시와 이어진다
+                store_bytes(&store, &bytes)?;
+                restored += 1;
+            } else if workbook.is_none() && name.to_lowercase().ends_with(".xlsx") {
+                let dir = std::env::temp_dir().join("erp_category_import");
+                fs::create_dir_all(&dir)?;
+                let target = dir.join(sanitize_file_name(
+                    Path::new(&name)
+                        .file_name()
+                        .and_then(|n| n.to_str())
+                        .unwrap_or("workbook.xlsx"),
+                ));
+                fs::write(&target, &bytes)?;
+                workbook = Some(target);
+            }
+        }
+        println!("첨부 파일 {}개를 되살렸습니다", restored);
+        workbook.ok_or_else(|| "zip 안에 통합 문서(.xlsx)가 없습니다".into())
+    }
+}
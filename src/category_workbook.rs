@@ -0,0 +1,595 @@
+// 카테고리 전체를 Excel 통합 문서 하나로 내보내고 다시 가져오기.
+// 구조체마다 "서브카테고리 - 구조체" 시트, 마지막에 스키마 시트
+use crate::csv_import::{
+    empty_row, match_headers, render_import_errors, row_id_column, ImportError, ROW_ID_COLUMN,
+};
+use crate::excel_export::write_structure_sheet;
+use crate::excel_import::{cell_to_string, convert_cells, read_sheet};
+use crate::korean_id;
+use crate::reference::{resolve_with, ReferenceOptions};
+use crate::validation::{accept_rows, rejected_to_errors, render_violations};
+use crate::{CustomCategory, CustomStructure, ERPApp, Field, FieldValue, Row, SubCategory};
+use calamine::{open_workbook, Reader, Xlsx};
+use eframe::egui;
+use egui::{Context, ScrollArea};
+use rfd::FileDialog;
+use std::collections::HashSet;
+use std::error::Error;
+use std::path::Path;
+use xlsxwriter::format::FormatBorder;
+use xlsxwriter::{Format, Workbook};
+
+const SCHEMA_SHEET: &str = "_schema";
+const ROW_ID_FIELD: &str = "__row_id";
+const SCHEMA_HEADERS: [&str; 6] = [
+    "카테고리",
+    "서브카테고리",
+    "구조체",
+    "시트",
+    "필드",
+    "정의(JSON)",
+];
+// Excel 시트 이름 최대 길이
+const MAX_SHEET_NAME: usize = 31;
+
+// Excel이 시트 이름에 허용하지 않는 문자는 _로 바꾸고 31자로 자른다
+pub(crate) fn sheet_base_name(subcategory: &str, structure: &str) -> String {
+    let name: String = format!("{} - {}", subcategory, structure)
+        .chars()
+        .map(|c| match c {
+            '[' | ']' | ':' | '*' | '?' | '/' | '\\' => '_',
+            c if c.is_control() => '_',
+            c => c,
+        })
+        .collect();
+    let name = name.trim_matches('\'').trim();
+    let name: String = name.chars().take(MAX_SHEET_NAME).collect();
+    if name.is_empty() {
+        "Sheet".to_string()
+    } else {
+        name
+    }
+}
+
+// 이미 쓴 이름과 겹치지 않는 시트 이름 (Excel은 대소문자를 구분하지 않음)
+pub(crate) fn unique_sheet_name(base: &str, used: &[String]) -> String {
+    let taken = |name: &str| {
+        name.eq_ignore_ascii_case(SCHEMA_SHEET)
+            || used.iter().any(|u| u.to_lowercase() == name.to_lowercase())
+    };
+    if !taken(base) {
+        return base.to_string();
+    }
+    (2..)
+        .map(|n| {
+            let suffix = format!(" ({})", n);
+            let keep = MAX_SHEET_NAME - suffix.chars().count();
+            format!("{}{}", base.chars().take(keep).collect::<String>(), suffix)
+        })
+        .find(|name| !taken(name))
+        .unwrap_or_default()
+}
+
+// 시트 맨 앞에 행 ID 열을 붙인 구조체와 행. 다시 가져올 때 같은 ID를 써서
+// 통합 문서 밖의 구조체가 참조하는 행이 끊기지 않는다
+fn with_row_ids(structure: &CustomStructure, rows: &[Row]) -> (CustomStructure, Vec<Row>) {
+    let mut structure = structure.clone();
+    structure.fields.insert(
+        0,
+        Field {
+            id: ROW_ID_FIELD.to_string(),
+            name: ROW_ID_COLUMN.to_string(),
+            ..Default::default()
+        },
+    );
+    let rows = rows
+        .iter()
+        .map(|row| {
+            let mut row = row.clone();
+            row.values.insert(
+                ROW_ID_FIELD.to_string(),
+                FieldValue {
+                    value: row.id.clone(),
+                    field_type: Default::default(),
+                },
+            );
+            row
+        })
+        .collect();
+    (structure, rows)
+}
+
+// 스키마 시트 한 행: 어느 시트가 어느 구조체인지
+struct SchemaEntry {
+    category: String,
+    subcategory: String,
+    sheet: String,
+    structure: CustomStructure,
+}
+
+fn read_schema(path: &Path) -> Result<Vec<SchemaEntry>, Box<dyn Error>> {
+    let (headers, cells) = read_sheet(path, SCHEMA_SHEET)?;
+    let column = |name: &str| {
+        headers
+            .iter()
+            .position(|h| h == name)
+            .ok_or_else(|| format!("스키마 시트에 '{}' 열이 없습니다", name))
+    };
+    let category_col = column(SCHEMA_HEADERS[0])?;
+    let subcategory_col = column(SCHEMA_HEADERS[1])?;
+    let sheet_col = column(SCHEMA_HEADERS[3])?;
+    let json_col = column(SCHEMA_HEADERS[5])?;
+
+    let text = |row: &[calamine::DataType], col: usize| {
+        row.get(col).map(|c| c.to_string()).unwrap_or_default()
+    };
+    let mut entries = Vec::new();
+    for (idx, row) in cells.iter().enumerate() {
+        let mut structure: CustomStructure = serde_json::from_str(&text(row, json_col))
+            .map_err(|e| format!("스키마 {}행을 읽을 수 없습니다: {}", idx + 2, e))?;
+        structure.ensure_ids();
+        entries.push(SchemaEntry {
+            category: text(row, category_col),
+            subcategory: text(row, subcategory_col),
+            sheet: text(row, sheet_col),
+            structure,
+        });
+    }
+    Ok(entries)
+}
+
+// 통합 문서의 시트 하나를 어느 구조체에 넣을지
+pub(crate) struct SheetImport {
+    pub(crate) sheet: String,
+    pub(crate) subcategory: String,
+    pub(crate) structure: CustomStructure,
+    pub(crate) is_new: bool, // 워크스페이스에 없어 스키마로 새로 만드는 구조체
+    pub(crate) existing_rows: usize,
+    pub(crate) removed: Vec<String>, // 통합 문서에 없어 참조 규칙에 따라 지울 기존 행 ID
+    pub(crate) has_row_ids: bool, // 행 ID 열이 없는 예전 통합 문서면 false (모든 행을 새로 만든다)
+    pub(crate) rows: Vec<Row>,
+    pub(crate) errors: Vec<ImportError>,
+    pub(crate) violations: Vec<ImportError>, // 제약 조건 때문에 제외한 행
+}
+
+// 확인을 기다리는 카테고리 통합 문서 가져오기
+pub(crate) struct CategoryImport {
+    pub(crate) file_name: String,
+    pub(crate) category: String,
+    pub(crate) category_exists: bool,
+    pub(crate) sheets: Vec<SheetImport>,
+    pub(crate) missing_sheets: Vec<String>,
+}
+
+impl ERPApp {
+    // 카테고리 전체를 시트 여러 개짜리 Excel 파일로 저장.
+    // 첨부 파일이 있으면 통합 문서와 첨부 파일을 zip 하나로 묶는다
+    pub(crate) fn export_category_workbook(
+        &self,
+        category: &CustomCategory,
+    ) -> Result<(), Box<dyn Error>> {
+        if !self.category_has_attachments(category) {
+            let Some(path) = FileDialog::new()
+                .add_filter("Excel Files", &["xlsx"])
+                .set_file_name(&format!("{}.xlsx", category.name))
+                .save_file()
+            else {
+                return Ok(());
+            };
+            self.write_category_workbook(category, &path)?;
+            println!("카테고리 통합 문서 저장 완료: {}", path.display());
+            return Ok(());
+        }
+
+        let Some(path) = FileDialog::new()
+            .add_filter("Zip Files", &["zip"])
+            .set_file_name(&format!("{}.zip", category.name))
+            .save_file()
+        else {
+            return Ok(());
+        };
+        let workbook = std::env::temp_dir().join(format!("{}.xlsx", crate::new_id()));
+        let result = self
+            .write_category_workbook(category, &workbook)
+            .and_then(|sheets| self.write_category_zip(category, &workbook, &sheets, &path));
+        let _ = std::fs::remove_file(&workbook);
+        result?;
+        println!(
+            "카테고리 통합 문서와 첨부 파일 저장 완료: {}",
+            path.display()
+        );
+        Ok(())
+    }
+
+    // 통합 문서 기록. (구조체 ID, 시트 이름) 목록 반환
+    fn write_category_workbook(
+        &self,
+        category: &CustomCategory,
+        path: &Path,
+    ) -> Result<Vec<(String, String)>, Box<dyn Error>> {
+        let workbook = Workbook::new(&path.to_string_lossy())?;
+        let mut schema_rows = Vec::new();
+        let mut used = Vec::new();
+        for subcategory in &category.subcategories {
+            for structure in &subcategory.structures {
+                let name =
+                    unique_sheet_name(&sheet_base_name(&subcategory.name, &structure.name), &used);
+                let mut sheet = workbook.add_worksheet(Some(&name))?;
+                let rows = self
+                    .erp_data
+                    .data
+                    .get(&structure.id)
+                    .map(|rows| self.display_rows(structure, rows))
+                    .unwrap_or_default();
+                let (with_ids, rows) = with_row_ids(structure, &rows);
+                write_structure_sheet(&mut sheet, &with_ids, &rows, &self.settings.excel_export)?;
+                schema_rows.push((subcategory.name.clone(), structure, name.clone()));
+                used.push(name);
+            }
+        }
+
+        // 스키마 시트: 사람이 읽는 요약과 다시 가져올 때 쓰는 구조체 정의
+        let mut sheet = workbook.add_worksheet(Some(SCHEMA_SHEET))?;
+        let mut header_format = Format::new();
+        header_format
+            .set_bold()
+            .set_border_bottom(FormatBorder::Thin);
+        for (col, header) in SCHEMA_HEADERS.iter().enumerate() {
+            sheet.write_string(0, col as u16, header, Some(&header_format))?;
+        }
+        for (idx, (subcategory, structure, sheet_name)) in schema_rows.iter().enumerate() {
+            let row = idx as u32 + 1;
+            let fields = structure
+                .fields
+                .iter()
+                .map(|f| format!("{} ({:?})", f.name, f.field_type))
+                .collect::<Vec<_>>()
+                .join(", ");
+            sheet.write_string(row, 0, &category.name, None)?;
+            sheet.write_string(row, 1, subcategory, None)?;
+            sheet.write_string(row, 2, &structure.name, None)?;
+            sheet.write_string(row, 3, sheet_name, None)?;
+            sheet.write_string(row, 4, &fields, None)?;
+            sheet.write_string(row, 5, &serde_json::to_string(structure)?, None)?;
+        }
+        sheet.set_column(0, 3, 18.0, None)?;
+        sheet.set_column(4, 4, 50.0, None)?;
+        sheet.freeze_panes(1, 0);
+
+        workbook.close()?;
+        Ok(schema_rows
+            .iter()
+            .map(|(_, structure, sheet)| (structure.id.clone(), sheet.clone()))
+            .collect())
+    }
+
+    pub(crate) fn start_category_import(&mut self) {
+        let Some(path) = FileDialog::new()
+            .add_filter("Excel/Zip Files", &["xlsx", "zip"])
+            .pick_file()
+        else {
+            return;
+        };
+        // 첨부 파일과 함께 내보낸 zip이면 첨부 파일을 되살리고 안의 통합 문서를 읽는다
+        let is_zip = path
+            .extension()
+            .is_some_and(|ext| ext.eq_ignore_ascii_case("zip"));
+        let path = if is_zip {
+            match self.unpack_category_zip(&path) {
+                Ok(workbook) => workbook,
+                Err(e) => {
+                    eprintln!("zip을 읽을 수 없습니다: {}", e);
+                    return;
+                }
+            }
+        } else {
+            path
+        };
+        match self.plan_category_import(&path) {
+            Ok(import) => self.category_import = Some(import),
+            Err(e) => eprintln!("통합 문서를 읽을 수 없습니다: {}", e),
+        }
+    }
+
+    // 스키마 시트를 기준으로 시트마다 대상 구조체를 정하고 행을 변환
+    fn plan_category_import(&self, path: &Path) -> Result<CategoryImport, Box<dyn Error>> {
+        let workbook: Xlsx<_> = open_workbook(path)?;
+        let sheet_names = workbook.sheet_names().to_vec();
+        let entries = read_schema(path)?;
+        let Some(category) = entries.first().map(|e| e.category.clone()) else {
+            return Err("스키마 시트에 구조체가 없습니다".into());
+        };
+        let existing_category = self.custom_structures.iter().find(|c| c.name == category);
+
+        let mut import = CategoryImport {
+            file_name: path
+                .file_name()
+                .map(|n| n.to_string_lossy().to_string())
+                .unwrap_or_default(),
+            category: category.clone(),
+            category_exists: existing_category.is_some(),
+            sheets: Vec::new(),
+            missing_sheets: Vec::new(),
+        };
+
+        for entry in entries {
+            if !sheet_names.contains(&entry.sheet) {
+                import.missing_sheets.push(entry.sheet);
+                continue;
+            }
+
+            // ID가 같은 구조체, 없으면 같은 서브카테고리의 같은 이름 구조체
+            let existing = self.find_structure(&entry.structure.id).or_else(|| {
+                existing_category
+                    .and_then(|c| c.subcategories.iter().find(|s| s.name == entry.subcategory))
+                    .and_then(|s| s.structures.iter().find(|s| s.name == entry.structure.name))
+                    .cloned()
+            });
+            let is_new = existing.is_none();
+            let structure = existing.unwrap_or(entry.structure);
+
+            let (headers, cells) = read_sheet(path, &entry.sheet)?;
+            let mapping = match_headers(&headers, &structure);
+            let (rows, mut errors) = convert_cells(&structure, &headers, &cells, &mapping);
+            let existing = self
+                .erp_data
+                .data
+                .get(&structure.id)
+                .cloned()
+                .unwrap_or_default();
+            // 행 ID 열이 있으면 그 ID를 다시 쓴다. 비었거나 겹치는 ID는 새 행
+            let id_col = row_id_column(&headers);
+            let mut seen = HashSet::new();
+            let rows = rows
+                .into_iter()
+                .zip(&cells)
+                .enumerate()
+                .map(|(idx, (row, record))| {
+                    let mut full = empty_row(&structure);
+                    full.id = id_col
+                        .and_then(|col| record.get(col))
+                        .and_then(|cell| cell_to_string(cell, None).ok())
+                        .map(|id| id.trim().to_string())
+                        .filter(|id| !id.is_empty() && seen.insert(id.clone()))
+                        .unwrap_or(row.id);
+                    full.values.extend(row.values);
+                    // 가려서 내보낸 식별 번호는 같은 행에 저장된 값을 그대로 둔다
+                    let Some(stored) = existing.iter().find(|r| r.id == full.id) else {
+                        return full;
+                    };
+                    for (col, field_id) in mapping.iter().enumerate() {
+                        let (Some(field_id), Some(cell)) = (field_id, record.get(col)) else {
+                            continue;
+                        };
+                        let is_identifier = structure
+                            .fields
+                            .iter()
+                            .any(|f| &f.id == field_id && korean_id::is_identifier(&f.field_type));
+                        if !is_identifier || !korean_id::is_masked(&cell.to_string()) {
+                            continue;
+                        }
+                        if let Some(value) = stored.values.get(field_id) {
+                            full.values.insert(field_id.clone(), value.clone());
+                        }
+                        errors.retain(|e| e.row != idx + 1 || Some(&e.column) != headers.get(col));
+                    }
+                    full
+                })
+                .collect();
+
+            import.sheets.push(SheetImport {
+                sheet: entry.sheet,
+                subcategory: entry.subcategory,
+                existing_rows: existing.len(),
+                removed: Vec::new(),
+                has_row_ids: id_col.is_some(),
+                structure,
+                is_new,
+                rows,
+                errors,
+                violations: Vec::new(),
+            });
+        }
+
+        // 시트끼리 참조할 수 있으므로 모든 시트를 읽은 뒤 참조 값을 행 ID로 바꾸고 제약 조건 검사
+        let lookups: Vec<ReferenceOptions> = {
+            let pending: Vec<(&CustomStructure, &[Row])> = import
+                .sheets
+                .iter()
+                .map(|s| (&s.structure, s.rows.as_slice()))
+                .collect();
+            import
+                .sheets
+                .iter()
+                .map(|s| self.reference_lookup(&s.structure, &pending))
+                .collect()
+        };
+        for (sheet, references) in import.sheets.iter_mut().zip(&lookups) {
+            sheet
+                .errors
+                .extend(resolve_with(&sheet.structure, references, &mut sheet.rows));
+            let (rows, rejected) =
+                accept_rows(&sheet.structure, &[], std::mem::take(&mut sheet.rows));
+            sheet.violations = rejected_to_errors(&rejected);
+            sheet.rows = rows;
+            if !sheet.has_row_ids {
+                continue;
+            }
+            let kept: HashSet<&str> = sheet.rows.iter().map(|r| r.id.as_str()).collect();
+            sheet.removed = self
+                .erp_data
+                .data
+                .get(&sheet.structure.id)
+                .map(|rows| {
+                    rows.iter()
+                        .filter(|r| !kept.contains(r.id.as_str()))
+                        .map(|r| r.id.clone())
+                        .collect()
+                })
+                .unwrap_or_default();
+        }
+        Ok(import)
+    }
+
+    // 없는 카테고리/서브카테고리/구조체를 만들고 시트 내용으로 행을 교체
+    fn apply_category_import(&mut self, import: CategoryImport) {
+        if self.writes_blocked() {
+            return;
+        }
+        if !import.category_exists {
+            self.custom_structures.push(CustomCategory {
+                name: import.category.clone(),
+                subcategories: Vec::new(),
+            });
+        }
+
+        let mut created = false;
+        for sheet in import.sheets.iter().filter(|s| s.is_new) {
+            let Some(category) = self
+                .custom_structures
+                .iter_mut()
+                .find(|c| c.name == import.category)
+            else {
+                continue;
+            };
+            let subcategory = match category
+                .subcategories
+                .iter()
+                .position(|s| s.name == sheet.subcategory)
+            {
+                Some(idx) => &mut category.subcategories[idx],
+                None => {
+                    category.subcategories.push(SubCategory {
+                        name: sheet.subcategory.clone(),
+                        structures: Vec::new(),
+                    });
+                    category.subcategories.last_mut().unwrap()
+                }
+            };
+            subcategory.structures.push(sheet.structure.clone());
+            created = true;
+            println!(
+                "통합 문서에서 구조체를 만들었습니다: {}",
+                sheet.structure.name
+            );
+        }
+        if created || !import.category_exists {
+            self.save_custom_structures();
+        }
+
+        // 통합 문서에서 빠진 행은 참조 규칙대로 지운다. 삭제가 막힌 행은 남긴다
+        let mut sheets = import.sheets;
+        for sheet in &mut sheets {
+            for row_id in &sheet.removed {
+                if let Err(e) = self.delete_row_with_references(&sheet.structure.id, row_id) {
+                    println!("{}: {}", sheet.sheet, e);
+                    let kept = self
+                        .erp_data
+                        .data
+                        .get(&sheet.structure.id)
+                        .and_then(|rows| rows.iter().find(|r| &r.id == row_id))
+                        .cloned();
+                    sheet.rows.extend(kept);
+                }
+            }
+        }
+        for sheet in sheets {
+            let structure_id = sheet.structure.id;
+            self.erp_data.data.insert(structure_id.clone(), sheet.rows);
+            self.save_structure_rows(&structure_id);
+            self.save_to_csv(&structure_id);
+        }
+        println!("카테고리 통합 문서 가져오기 완료: {}", import.file_name);
+    }
+
+    pub(crate) fn render_category_import(&mut self, ctx: &Context) {
+        let Some(import) = &self.category_import else {
+            return;
+        };
+
+        let mut apply = false;
+        let mut cancel = false;
+        egui::Window::new("카테고리 통합 문서 가져오기")
+            .collapsible(false)
+            .default_size([560.0, 420.0])
+            .show(ctx, |ui| {
+                ui.label(format!("{} → {}", import.file_name, import.category));
+                if !import.category_exists {
+                    ui.label("카테고리가 없어 새로 만듭니다.");
+                }
+                ui.label(
+                    "기존 행은 통합 문서의 내용으로 교체됩니다. 행 ID가 같은 행은 그대로 이어지고, \
+                     통합 문서에 없는 행은 참조 규칙에 따라 삭제됩니다.",
+                );
+                ui.separator();
+
+                ScrollArea::vertical()
+                    .id_salt("category_import_sheets")
+                    .max_height(300.0)
+                    .show(ui, |ui| {
+                        for sheet in &import.sheets {
+                            ui.group(|ui| {
+                                ui.horizontal(|ui| {
+                                    ui.strong(&sheet.sheet);
+                                    ui.label(format!(
+                                        "→ {} / {}",
+                                        sheet.subcategory, sheet.structure.name
+                                    ));
+                                    if sheet.is_new {
+                                        ui.colored_label(egui::Color32::DARK_GREEN, "새 구조체");
+                                    }
+                                });
+                                ui.label(format!(
+                                    "기존 {}행 → {}행 (삭제 {}행)",
+                                    sheet.existing_rows,
+                                    sheet.rows.len(),
+                                    sheet.removed.len()
+                                ));
+                                if !sheet.has_row_ids {
+                                    ui.colored_label(
+                                        egui::Color32::from_rgb(200, 120, 0),
+                                        "행 ID 열이 없어 모든 행을 새 ID로 만듭니다. \
+                                         이 행을 가리키던 참조는 끊어집니다.",
+                                    );
+                                }
+                                render_import_errors(
+                                    ui,
+                                    &format!("category_import_errors_{}", sheet.sheet),
+                                    &sheet.errors,
+                                );
+                                render_violations(
+                                    ui,
+                                    &format!("category_import_violations_{}", sheet.sheet),
+                                    &sheet.violations,
+                                );
+                            });
+                        }
+                        for sheet in &import.missing_sheets {
+                            ui.colored_label(
+                                egui::Color32::RED,
+                                format!("시트를 찾을 수 없어 건너뜁니다: {}", sheet),
+                            );
+                        }
+                    });
+
+                ui.separator();
+                ui.horizontal(|ui| {
+                    if ui.button("✔ 적용").clicked() {
+                        apply = true;
+                    }
+                    if ui.button("❌ 취소").clicked() {
+                        cancel = true;
+                    }
+                });
+            });
+
+        if cancel {
+            self.category_import = None;
+        }
+        if apply {
+            if let Some(import) = self.category_import.take() {
+                self.apply_category_import(import);
+            }
+        }
+    }
+}
@@ -0,0 +1,339 @@
+// CSV 인코딩과 구분자: 한국어 Excel은 CP949로 저장하고, UTF-8은 BOM이 있어야 제대로 연다
+use crate::korean_id;
+use crate::line_items;
+use crate::{CustomStructure, ERPApp, Row};
+use eframe::egui;
+use egui::Context;
+use encoding_rs::{EUC_KR, UTF_8};
+use rfd::FileDialog;
+use serde::{Deserialize, Serialize};
+
+const UTF8_BOM: &[u8] = b"\xEF\xBB\xBF";
+
+#[derive(Clone, Copy, Default, Serialize, Deserialize, PartialEq, Debug)]
+pub(crate) enum CsvEncoding {
+    Utf8,
+    #[default]
+    Utf8Bom,
+    Cp949,
+}
+
+impl CsvEncoding {
+    pub(crate) fn label(&self) -> &'static str {
+        match self {
+            CsvEncoding::Utf8 => "UTF-8",
+            CsvEncoding::Utf8Bom => "UTF-8 (BOM)",
+            CsvEncoding::Cp949 => "CP949 (EUC-KR)",
+        }
+    }
+}
+
+#[derive(Clone, Copy, Default, Serialize, Deserialize, PartialEq, Debug)]
+pub(crate) enum CsvDelimiter {
+    #[default]
+    Comma,
+    Tab,
+    Semicolon,
+}
+
+impl CsvDelimiter {
+    pub(crate) fn byte(&self) -> u8 {
+        match self {
+            CsvDelimiter::Comma => b',',
+            CsvDelimiter::Tab => b'\t',
+            CsvDelimiter::Semicolon => b';',
+        }
+    }
+
+    pub(crate) fn label(&self) -> &'static str {
+        match self {
+            CsvDelimiter::Comma => "쉼표 (,)",
+            CsvDelimiter::Tab => "탭",
+            CsvDelimiter::Semicolon => "세미콜론 (;)",
+        }
+    }
+}
+
+#[derive(Clone, Copy, Default, Serialize, Deserialize, PartialEq, Debug)]
+pub(crate) struct CsvFormat {
+    #[serde(default)]
+    pub(crate) encoding: CsvEncoding,
+    #[serde(default)]
+    pub(crate) delimiter: CsvDelimiter,
+}
+
+// BOM이 있으면 UTF-8, BOM 없이도 UTF-8로 읽히면 UTF-8, 아니면 CP949로 해석
+pub(crate) fn decode(bytes: &[u8]) -> (String, CsvEncoding) {
+    if let Some(rest) = bytes.strip_prefix(UTF8_BOM) {
+        let (text, _) = UTF_8.decode_without_bom_handling(rest);
+        return (text.into_owned(), CsvEncoding::Utf8Bom);
+    }
+    match std::str::from_utf8(bytes) {
+        Ok(text) => (text.to_string(), CsvEncoding::Utf8),
+        Err(_) => {
+            // encoding_rs의 EUC-KR은 CP949 확장 문자까지 포함
+            let (text, _) = EUC_KR.decode_without_bom_handling(bytes);
+            (text.into_owned(), CsvEncoding::Cp949)
+        }
+    }
+}
+
+// 인코딩한 바이트와, CP949로 표현할 수 없는 문자가 있었는지 여부
+pub(crate) fn encode(text: &str, encoding: CsvEncoding) -> (Vec<u8>, bool) {
+    match encoding {
+        CsvEncoding::Utf8 => (text.as_bytes().to_vec(), false),
+        CsvEncoding::Utf8Bom => {
+            let mut bytes = UTF8_BOM.to_vec();
+            bytes.extend_from_slice(text.as_bytes());
+            (bytes, false)
+        }
+        CsvEncoding::Cp949 => {
+            let (bytes, _, unmappable) = EUC_KR.encode(text);
+            (bytes.into_owned(), unmappable)
+        }
+    }
+}
+
+// 첫 줄에서 가장 많이 나오는 구분자 (따옴표 안은 제외)
+pub(crate) fn sniff_delimiter(text: &str) -> CsvDelimiter {
+    let first_line = text.lines().next().unwrap_or_default();
+    let mut counts = [0usize; 3];
+    let mut quoted = false;
+    for c in first_line.chars() {
+        match c {
+            '"' => quoted = !quoted,
+            ',' if !quoted => counts[0] += 1,
+            '\t' if !quoted => counts[1] += 1,
+            ';' if !quoted => counts[2] += 1,
+            _ => {}
+        }
+    }
+    let delimiters = [
+        CsvDelimiter::Comma,
+        CsvDelimiter::Tab,
+        CsvDelimiter::Semicolon,
+    ];
+    let best = (0..3).max_by_key(|&i| (counts[i], i == 0)).unwrap_or(0);
+    if counts[best] == 0 {
+        CsvDelimiter::Comma
+    } else {
+        delimiters[best]
+    }
+}
+
+// 형식 선택 콤보 두 개 (설정 화면, 내보내기 창에서 공용)
+pub(crate) fn format_picker(ui: &mut egui::Ui, id: &str, format: &mut CsvFormat) -> bool {
+    let before = *format;
+    ui.horizontal(|ui| {
+        ui.label("인코딩:");
+        egui::ComboBox::from_id_salt((id, "encoding"))
+            .selected_text(format.encoding.label())
+            .show_ui(ui, |ui| {
+                for encoding in [CsvEncoding::Utf8, CsvEncoding::Utf8Bom, CsvEncoding::Cp949] {
+                    ui.selectable_value(&mut format.encoding, encoding, encoding.label());
+                }
+            });
+        ui.label("구분자:");
+        egui::ComboBox::from_id_salt((id, "delimiter"))
+            .selected_text(format.delimiter.label())
+            .show_ui(ui, |ui| {
+                for delimiter in [
+                    CsvDelimiter::Comma,
+                    CsvDelimiter::Tab,
+                    CsvDelimiter::Semicolon,
+                ] {
+                    ui.selectable_value(&mut format.delimiter, delimiter, delimiter.label());
+                }
+            });
+    });
+    *format != before
+}
+
+// 형식을 고르는 중인 CSV 내보내기
+pub(crate) struct CsvExport {
+    pub(crate) structure_id: String,
+    pub(crate) format: CsvFormat,
+    pub(crate) reveal: bool, // 주민등록번호 등을 가리지 않고 내보내기
+}
+
+impl ERPApp {
+    pub(crate) fn start_csv_export(&mut self, structure: &CustomStructure) {
+        self.csv_export = Some(CsvExport {
+            structure_id: structure.id.clone(),
+            format: self.settings.csv_export,
+            reveal: false,
+        });
+    }
+
+    pub(crate) fn render_csv_export(&mut self, ctx: &Context) {
+        let Some(export) = &mut self.csv_export else {
+            return;
+        };
+
+        let mut save = false;
+        let mut cancel = false;
+        egui::Window::new("CSV 내보내기")
+            .collapsible(false)
+            .resizable(false)
+            .show(ctx, |ui| {
+                format_picker(ui, "csv_export", &mut export.format);
+                if export.format.encoding == CsvEncoding::Cp949 {
+                    ui.label("CP949로 표현할 수 없는 문자는 &#번호; 형태로 저장됩니다.");
+                }
+                ui.checkbox(&mut export.reveal, "주민등록번호 등을 가리지 않고 내보내기");
+                ui.separator();
+                ui.horizontal(|ui| {
+                    if ui.button("💾 저장").clicked() {
+                        save = true;
+                    }
+                    if ui.button("❌ 취소").clicked() {
+                        cancel = true;
+                    }
+                });
+            });
+
+        if cancel {
+            self.csv_export = None;
+        }
+        if !save {
+            return;
+        }
+        let Some(export) = self.csv_export.take() else {
+            return;
+        };
+        let Some(structure) = self.find_structure(&export.structure_id) else {
+            return;
+        };
+
+        // 마지막 선택을 다음 내보내기 기본값으로
+        self.settings.csv_export = export.format;
+        if let Some(workspace) = &self.workspace {
+            if let Err(e) = self.settings.save(workspace) {
+                eprintln!("설정 저장 실패: {}", e);
+            }
+        }
+
+        let extension = match export.format.delimiter {
+            CsvDelimiter::Tab => "tsv",
+            _ => "csv",
+        };
+        if let Some(path) = FileDialog::new()
+            .add_filter("CSV", &[extension])
+            .set_file_name(&format!("{}.{}", structure.name, extension))
+            .save_file()
+        {
+            let rows = self
+                .erp_data
+                .data
+                .get(&structure.id)
+                .map(Vec::as_slice)
+                .unwrap_or_default();
+            // 식별 번호는 따로 고르지 않으면 가린다
+            let mask = |structure: &CustomStructure, rows: &[Row]| {
+                if export.reveal {
+                    rows.to_vec()
+                } else {
+                    korean_id::mask_rows(structure, rows)
+                }
+            };
+            // 하위 표는 상위 ID로 잇는 별도 파일로
+            let result = match line_items::split_line_tables(&structure, rows) {
+                None => ERPApp::write_csv_rows(
+                    &structure,
+                    &mask(&structure, rows),
+                    &path,
+                    &export.format,
+                    false,
+                ),
+                Some((main, main_rows, tables)) => ERPApp::write_csv_rows(
+                    &main,
+                    &mask(&main, &main_rows),
+                    &path,
+                    &export.format,
+                    false,
+                )
+                .and_then(|()| {
+                    for table in &tables {
+                        let table_path = line_items::table_path(&path, &table.field_name);
+                        ERPApp::write_csv_rows(
+                            &table.structure,
+                            &mask(&table.structure, &table.rows),
+                            &table_path,
+                            &export.format,
+                            false,
+                        )?;
+                        println!("CSV 내보내기 완료: {}", table_path.display());
+                    }
+                    Ok(())
+                }),
+            };
+            match result {
+                Ok(()) => println!("CSV 내보내기 완료: {}", path.display()),
+                Err(e) => eprintln!("CSV 내보내기 실패: {}", e),
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn decode_detects_encoding() {
+        assert_eq!(
+            decode(b"\xEF\xBB\xBF\xEC\x9D\xB4\xEB\xA6\x84,a"),
+            ("이름,a".to_string(), CsvEncoding::Utf8Bom)
+        );
+        assert_eq!(
+            decode("이름,a".as_bytes()),
+            ("이름,a".to_string(), CsvEncoding::Utf8)
+        );
+        // "한글"의 CP949 바이트는 UTF-8로 읽을 수 없다
+        assert_eq!(
+            decode(b"\xC7\xD1\xB1\xDB,a"),
+            ("한글,a".to_string(), CsvEncoding::Cp949)
+        );
+    }
+
+    #[test]
+    fn cp949_round_trip() {
+        // 똠, 햏은 EUC-KR에 없는 CP949 확장 문자
+        let text = "이름,메모\n똠방각하,햏\n";
+        let (bytes, unmappable) = encode(text, CsvEncoding::Cp949);
+        assert!(!unmappable);
+        assert_eq!(decode(&bytes), (text.to_string(), CsvEncoding::Cp949));
+
+        // 표현할 수 없는 문자는 알려 주고 숫자 참조로 남긴다
+        let (bytes, unmappable) = encode("가😀", CsvEncoding::Cp949);
+        assert!(unmappable);
+        assert_eq!(decode(&bytes).0, "가&#128512;");
+    }
+
+    #[test]
+    fn encode_utf8_with_and_without_bom() {
+        assert_eq!(encode("a", CsvEncoding::Utf8), (b"a".to_vec(), false));
+        assert_eq!(
+            encode("a", CsvEncoding::Utf8Bom),
+            (b"\xEF\xBB\xBFa".to_vec(), false)
+        );
+    }
+
+    #[test]
+    fn sniff_delimiter_ignores_quoted_fields() {
+        assert_eq!(sniff_delimiter("a,b,c\n1,2,3"), CsvDelimiter::Comma);
+        assert_eq!(sniff_delimiter("a\tb\tc"), CsvDelimiter::Tab);
+        assert_eq!(sniff_delimiter("a;b;c"), CsvDelimiter::Semicolon);
+        // 따옴표 안의 쉼표는 세지 않는다
+        assert_eq!(
+            sniff_delimiter("\"서울, 강남구\";\"1,000\";c"),
+            CsvDelimiter::Semicolon
+        );
+        assert_eq!(sniff_delimiter("\"a\tb\",c"), CsvDelimiter::Comma);
+        // 둘째 줄 이후는 보지 않는다
+        assert_eq!(sniff_delimiter("이름\n1;2;3"), CsvDelimiter::Comma);
+        // 같은 개수면 쉼표
+        assert_eq!(sniff_delimiter("a,b;c"), CsvDelimiter::Comma);
+        assert_eq!(sniff_delimiter(""), CsvDelimiter::Comma);
+    }
+}
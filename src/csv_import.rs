@@ -0,0 +1,720 @@
+// CSV 읽기: 헤더를 필드 이름과 맞춰 열을 연결하고, 가져오기 전 매핑 확인
+use crate::csv_format::{self, CsvFormat};
+use crate::reference::{resolve_with, ReferenceOptions};
+use crate::schema::convert_field_value;
+use crate::validation::{accept_rows, rejected_to_errors, render_violations};
+use crate::{CustomStructure, ERPApp, Field, FieldType, FieldValue, Row};
+use eframe::egui;
+use egui::{Context, ScrollArea};
+use rfd::FileDialog;
+use std::collections::HashSet;
+use std::error::Error;
+use std::fs;
+use std::path::Path;
+
+// CSV 백업과 카테고리 통합 문서에 쓰는 행 ID 열. 복원할 때 같은 ID를 써야 참조가 끊기지 않는다
+pub(crate) const ROW_ID_COLUMN: &str = "행 ID";
+
+// 행 ID 열의 위치
+pub(crate) fn row_id_column(headers: &[String]) -> Option<usize> {
+    headers.iter().position(|h| h.trim() == ROW_ID_COLUMN)
+}
+
+// CSV 파일의 헤더와 데이터 행
+pub(crate) struct CsvTable {
+    pub(crate) headers: Vec<String>,
+    pub(crate) records: Vec<Vec<String>>,
+    pub(crate) format: CsvFormat, // 감지한 인코딩과 구분자
+}
+
+// 인코딩(UTF-8, BOM, CP949)과 구분자를 감지해서 읽기
+pub(crate) fn read_csv_table(path: &Path) -> Result<CsvTable, Box<dyn Error>> {
+    let (text, encoding) = csv_format::decode(&fs::read(path)?);
+    let delimiter = csv_format::sniff_delimiter(&text);
+    let mut rdr = csv::ReaderBuilder::new()
+        .flexible(true)
+        .delimiter(delimiter.byte())
+        .from_reader(text.as_bytes());
+    let headers = rdr.headers()?.iter().map(|h| h.to_string()).collect();
+    let mut records = Vec::new();
+    for result in rdr.records() {
+        records.push(result?.iter().map(|v| v.to_string()).collect());
+    }
+    Ok(CsvTable {
+        headers,
+        records,
+        format: CsvFormat {
+            encoding,
+            delimiter,
+        },
+    })
+}
+
+// 비교용 이름: 대소문자, 공백, 구분 기호 무시
+fn normalize_header(name: &str) -> String {
+    name.chars()
+        .filter(|c| !c.is_whitespace() && !matches!(c, '_' | '-' | '.' | '(' | ')' | '[' | ']'))
+        .flat_map(|c| c.to_lowercase())
+        .collect()
+}
+
+fn edit_distance(a: &str, b: &str) -> usize {
+    let b: Vec<char> = b.chars().collect();
+    let mut prev: Vec<usize> = (0..=b.len()).collect();
+    for (i, ca) in a.chars().enumerate() {
+        let mut cur = vec![i + 1];
+        for (j, cb) in b.iter().enumerate() {
+            let cost = if ca == *cb { 0 } else { 1 };
+            cur.push((prev[j] + cost).min(prev[j + 1] + 1).min(cur[j] + 1));
+        }
+        prev = cur;
+    }
+    prev[b.len()]
+}
+
+// 헤더와 가장 비슷한 필드. 정규화한 이름이 같으면 우선, 아니면 오타 한두 글자나 포함 관계
+fn fuzzy_match<'a>(header: &str, fields: &[&'a Field]) -> Option<&'a Field> {
+    let header = normalize_header(header);
+    if header.is_empty() {
+        return None;
+    }
+    if let Some(field) = fields.iter().find(|f| normalize_header(&f.name) == header) {
+        return Some(field);
+    }
+
+    fields
+        .iter()
+        .filter_map(|field| {
+            let name = normalize_header(&field.name);
+            let distance = edit_distance(&header, &name);
+            let limit = (header.chars().count().max(name.chars().count()) / 4).max(1);
+            if distance <= limit {
+                Some((distance, *field))
+            } else if name.chars().count() >= 2
+                && (header.contains(&name) || name.contains(&header))
+            {
+                Some((limit + 1, *field))
+            } else {
+                None
+            }
+        })
+        .min_by_key(|(distance, _)| *distance)
+        .map(|(_, field)| field)
+}
+
+// 열마다 연결할 필드 ID. 정확히 같은 이름을 먼저 연결하고 남은 열은 비슷한 이름으로
+pub(crate) fn match_headers(
+    headers: &[String],
+    structure: &CustomStructure,
+) -> Vec<Option<String>> {
+    let mut mapping: Vec<Option<String>> = vec![None; headers.len()];
+    let mut taken = HashSet::new();
+
+    // 행 ID 열은 필드에 연결하지 않는다
+    let is_row_id = |header: &String| header.trim() == ROW_ID_COLUMN;
+    for (col, header) in headers.iter().enumerate() {
+        if is_row_id(header) {
+            continue;
+        }
+        if let Some(field) = structure
+            .fields
+            .iter()
+            .find(|f| f.name.trim() == header.trim() && !taken.contains(&f.id))
+        {
+            taken.insert(field.id.clone());
+            mapping[col] = Some(field.id.clone());
+        }
+    }
+
+    for (col, header) in headers.iter().enumerate() {
+        if mapping[col].is_some() || is_row_id(header) {
+            continue;
+        }
+        let free: Vec<&Field> = structure
+            .fields
+            .iter()
+            .filter(|f| !taken.contains(&f.id))
+            .collect();
+        if let Some(field) = fuzzy_match(header, &free) {
+            taken.insert(field.id.clone());
+            mapping[col] = Some(field.id.clone());
+        }
+    }
+    mapping
+}
+
+// 빈 값으로 채운 새 행
+pub(crate) fn empty_row(structure: &CustomStructure) -> Row {
+    let mut row = Row::new();
+    for field in &structure.fields {
+        row.values.insert(
+            field.id.clone(),
+            FieldValue {
+                value: String::new(),
+                field_type: field.field_type.clone(),
+            },
+        );
+    }
+    row
+}
+
+// CSV 백업 읽기. 헤더로 열을 찾고, 헤더가 하나도 맞지 않는 예전 파일은 열 순서대로 읽는다.
+// 행 ID 열이 있으면 그 ID를 그대로 쓴다 (예전 백업에는 없어 새 ID)
+pub(crate) fn read_csv_rows(
+    structure: &CustomStructure,
+    path: &Path,
+) -> Result<Vec<Row>, Box<dyn Error>> {
+    let table = read_csv_table(path)?;
+    let id_col = row_id_column(&table.headers);
+    let mut mapping = match_headers(&table.headers, structure);
+    if id_col.is_none() && mapping.iter().all(|m| m.is_none()) {
+        mapping = structure
+            .fields
+            .iter()
+            .map(|f| Some(f.id.clone()))
+            .collect();
+    }
+
+    let rows = table
+        .records
+        .iter()
+        .map(|record| {
+            let mut row = empty_row(structure);
+            if let Some(id) = id_col
+                .and_then(|col| record.get(col))
+                .filter(|id| !id.trim().is_empty())
+            {
+                row.id = id.trim().to_string();
+            }
+            for (col, field_id) in mapping.iter().enumerate() {
+                if let (Some(field_id), Some(value)) = (field_id, record.get(col)) {
+                    if let Some(field_value) = row.values.get_mut(field_id) {
+                        field_value.value = value.clone();
+                    }
+                }
+            }
+            row
+        })
+        .collect();
+    Ok(rows)
+}
+
+// 열을 어디에 넣을지
+#[derive(Clone, PartialEq, Debug)]
+pub(crate) enum ColumnTarget {
+    Skip,
+    Field(String),       // 기존 필드 ID
+    NewField(FieldType), // 헤더 이름으로 새 필드 추가
+}
+
+#[derive(Clone, Debug)]
+pub(crate) struct ImportError {
+    pub(crate) row: usize, // CSV 데이터 행 번호 (1부터)
+    pub(crate) column: String,
+    pub(crate) value: String,
+    pub(crate) error: String,
+}
+
+// 매핑 확인 중인 CSV 가져오기
+pub(crate) struct CsvImport {
+    pub(crate) structure_id: String,
+    pub(crate) file_name: String,
+    pub(crate) table: CsvTable,
+    pub(crate) targets: Vec<ColumnTarget>,
+    pub(crate) auto_matched: Vec<bool>,
+    pub(crate) errors: Vec<ImportError>,
+    pub(crate) violations: Vec<ImportError>, // 제약 조건 때문에 가져오지 않을 행
+}
+
+// 열 -> 필드 ID 연결대로 레코드를 행으로 변환. 변환되지 않는 값은 빈 값
+fn build_rows(
+    structure: &CustomStructure,
+    records: &[Vec<String>],
+    columns: &[Option<String>],
+) -> Vec<Row> {
+    let mut rows = Vec::new();
+    for record in records {
+        let mut row = empty_row(structure);
+        for (col, field_id) in columns.iter().enumerate() {
+            let (Some(field_id), Some(value)) = (field_id, record.get(col)) else {
+                continue;
+            };
+            let Some(field) = structure.fields.iter().find(|f| &f.id == field_id) else {
+                continue;
+            };
+            if let Some(field_value) = row.values.get_mut(field_id) {
+                field_value.value = convert_field_value(value, field).unwrap_or_default();
+            }
+        }
+        rows.push(row);
+    }
+    rows
+}
+
+impl CsvImport {
+    pub(crate) fn new(
+        structure: &CustomStructure,
+        existing: &[Row],
+        references: &ReferenceOptions,
+        file_name: String,
+        table: CsvTable,
+    ) -> Self {
+        let mapping = match_headers(&table.headers, structure);
+        let auto_matched = mapping.iter().map(|m| m.is_some()).collect();
+        let targets = mapping
+            .into_iter()
+            .map(|m| m.map(ColumnTarget::Field).unwrap_or(ColumnTarget::Skip))
+            .collect();
+        let mut import = CsvImport {
+            structure_id: structure.id.clone(),
+            file_name,
+            table,
+            targets,
+            auto_matched,
+            errors: Vec::new(),
+            violations: Vec::new(),
+        };
+        import.check(structure, existing, references);
+        import
+    }
+
+    fn target_field(&self, structure: &CustomStructure, col: usize) -> Option<Field> {
+        match &self.targets[col] {
+            ColumnTarget::Skip => None,
+            ColumnTarget::Field(id) => structure.fields.iter().find(|f| &f.id == id).cloned(),
+            ColumnTarget::NewField(field_type) => Some(Field {
+                field_type: field_type.clone(),
+                ..Default::default()
+            }),
+        }
+    }
+
+    // 연결된 필드 타입으로 변환되지 않는 값, 찾을 수 없는 참조, 제약 조건 위반 다시 계산
+    pub(crate) fn check(
+        &mut self,
+        structure: &CustomStructure,
+        existing: &[Row],
+        references: &ReferenceOptions,
+    ) {
+        self.errors.clear();
+        for col in 0..self.targets.len() {
+            let Some(field) = self.target_field(structure, col) else {
+                continue;
+            };
+            for (row_idx, record) in self.table.records.iter().enumerate() {
+                let value = record.get(col).map(|v| v.as_str()).unwrap_or_default();
+                if let Err(error) = convert_field_value(value, &field) {
+                    self.errors.push(ImportError {
+                        row: row_idx + 1,
+                        column: self.table.headers[col].clone(),
+                        value: value.to_string(),
+                        error,
+                    });
+                }
+            }
+        }
+
+        // 새 필드에는 제약 조건이 없으므로 기존 필드만으로 검사
+        let columns: Vec<Option<String>> = self
+            .targets
+            .iter()
+            .map(|t| match t {
+                ColumnTarget::Field(id) => Some(id.clone()),
+                _ => None,
+            })
+            .collect();
+        let mut rows = build_rows(structure, &self.table.records, &columns);
+        self.errors
+            .extend(resolve_with(structure, references, &mut rows));
+        let (_, rejected) = accept_rows(structure, existing, rows);
+        self.violations = rejected_to_errors(&rejected);
+    }
+}
+
+// 가져오기 창 아래의 변환 실패 목록
+pub(crate) fn render_import_errors(ui: &mut egui::Ui, id: &str, errors: &[ImportError]) {
+    if errors.is_empty() {
+        ui.label("모든 값을 변환할 수 있습니다.");
+        return;
+    }
+    ui.colored_label(
+        egui::Color32::RED,
+        format!(
+            "변환 실패 {}건 (가져오면 빈 값으로 들어갑니다)",
+            errors.len()
+        ),
+    );
+    ScrollArea::vertical()
+        .id_salt(id)
+        .max_height(140.0)
+        .show(ui, |ui| {
+            for error in errors {
+                ui.label(format!(
+                    "{}행 {}: \"{}\" - {}",
+                    error.row, error.column, error.value, error.error
+                ));
+            }
+        });
+}
+
+fn type_label(field_type: &FieldType) -> &'static str {
+    match field_type {
+        FieldType::Text => "텍스트",
+        FieldType::Number => "숫자",
+        FieldType::Currency => "통화",
+        FieldType::Date => "날짜",
+        FieldType::DateTime => "날짜와 시간",
+        FieldType::Time => "시간",
+        FieldType::Boolean => "참/거짓",
+        FieldType::Select => "선택",
+        FieldType::MultiSelect => "다중 선택",
+        FieldType::Reference => "참조",
+        FieldType::Table => "하위 표",
+        FieldType::Attachment => "첨부 파일",
+        FieldType::ResidentNumber => "주민등록번호",
+        FieldType::BusinessNumber => "사업자등록번호",
+        FieldType::Phone => "전화번호",
+        FieldType::Formula => "수식",
+        FieldType::Rollup => "집계",
+        FieldType::Lookup => "조회",
+    }
+}
+
+impl ERPApp {
+    // 파일을 고르고 매핑 대화상자 열기
+    pub(crate) fn start_csv_import(&mut self, structure: &CustomStructure) {
+        let Some(path) = FileDialog::new()
+            .add_filter("CSV", &["csv", "txt"])
+            .set_title("CSV 가져오기")
+            .pick_file()
+        else {
+            return;
+        };
+        match read_csv_table(&path) {
+            Ok(table) => {
+                let file_name = path
+                    .file_name()
+                    .map(|n| n.to_string_lossy().to_string())
+                    .unwrap_or_default();
+                let existing = self
+                    .erp_data
+                    .data
+                    .get(&structure.id)
+                    .cloned()
+                    .unwrap_or_default();
+                let references = self.reference_lookup(structure, &[]);
+                self.csv_import = Some(CsvImport::new(
+                    structure,
+                    &existing,
+                    &references,
+                    file_name,
+                    table,
+                ));
+            }
+            Err(e) => eprintln!("CSV 파일을 읽을 수 없습니다: {}", e),
+        }
+    }
+
+    pub(crate) fn render_csv_import(&mut self, ctx: &Context) {
+        let Some(structure_id) = self.csv_import.as_ref().map(|i| i.structure_id.clone()) else {
+            return;
+        };
+        let Some(structure) = self.find_structure(&structure_id) else {
+            self.csv_import = None;
+            return;
+        };
+        let Some(import) = &mut self.csv_import else {
+            return;
+        };
+
+        let mut apply = false;
+        let mut cancel = false;
+        let mut changed = false;
+
+        egui::Window::new("CSV 가져오기")
+            .collapsible(false)
+            .default_size([560.0, 480.0])
+            .show(ctx, |ui| {
+                ui.label(format!(
+                    "{} → {} ({}행)",
+                    import.file_name,
+                    structure.name,
+                    import.table.records.len()
+                ));
+                ui.label(format!(
+                    "인코딩: {}, 구분자: {}",
+                    import.table.format.encoding.label(),
+                    import.table.format.delimiter.label()
+                ));
+                ui.separator();
+
+                ScrollArea::vertical()
+                    .id_salt("csv_import_columns")
+                    .max_height(240.0)
+                    .show(ui, |ui| {
+                        egui::Grid::new("csv_import_grid")
+                            .striped(true)
+                            .num_columns(4)
+                            .show(ui, |ui| {
+                                ui.strong("CSV 열");
+                                ui.strong("예시 값");
+                                ui.strong("대상 필드");
+                                ui.label("");
+                                ui.end_row();
+
+                                for col in 0..import.targets.len() {
+                                    let header = import.table.headers[col].clone();
+                                    if import.auto_matched[col] {
+                                        ui.label(&header);
+                                    } else {
+                                        ui.colored_label(
+                                            egui::Color32::from_rgb(200, 120, 0),
+                                            format!("⚠ {}", header),
+                                        );
+                                    }
+                                    let sample = import
+                                        .table
+                                        .records
+                                        .iter()
+                                        .filter_map(|r| r.get(col))
+                                        .find(|v| !v.is_empty())
+                                        .cloned()
+                                        .unwrap_or_default();
+                                    ui.label(sample);
+
+                                    let target = &mut import.targets[col];
+                                    let selected = match target {
+                                        ColumnTarget::Skip => "건너뛰기".to_string(),
+                                        ColumnTarget::Field(id) => structure
+                                            .fields
+                                            .iter()
+                                            .find(|f| &f.id == id)
+                                            .map(|f| f.name.clone())
+                                            .unwrap_or_default(),
+                                        ColumnTarget::NewField(_) => "새 필드로 추가".to_string(),
+                                    };
+                                    let before = target.clone();
+                                    egui::ComboBox::from_id_salt(("csv_target", col))
+                                        .selected_text(selected)
+                                        .show_ui(ui, |ui| {
+                                            ui.selectable_value(
+                                                target,
+                                                ColumnTarget::Skip,
+                                                "건너뛰기",
+                                            );
+                                            for field in &structure.fields {
+                                                ui.selectable_value(
+                                                    target,
+                                                    ColumnTarget::Field(field.id.clone()),
+                                                    &field.name,
+                                                );
+                                            }
+                                            if !matches!(target, ColumnTarget::NewField(_)) {
+                                                ui.selectable_value(
+                                                    target,
+                                                    ColumnTarget::NewField(FieldType::Text),
+                                                    "새 필드로 추가",
+                                                );
+                                            }
+                                        });
+
+                                    if let ColumnTarget::NewField(field_type) = target {
+                                        egui::ComboBox::from_id_salt(("csv_new_type", col))
+                                            .selected_text(type_label(field_type))
+                                            .show_ui(ui, |ui| {
+                                                for t in [
+                                                    FieldType::Text,
+                                                    FieldType::Number,
+                                                    FieldType::Date,
+                                                    FieldType::DateTime,
+                                                    FieldType::Time,
+                                                    FieldType::Boolean,
+                                                ] {
+                                                    let label = type_label(&t);
+                                                    ui.selectable_value(field_type, t, label);
+                                                }
+                                            });
+                                    } else {
+                                        ui.label("");
+                                    }
+                                    changed |= *target != before;
+                                    ui.end_row();
+                                }
+                            });
+                    });
+
+                ui.separator();
+                render_import_errors(ui, "csv_import_errors", &import.errors);
+                render_violations(ui, "csv_import_violations", &import.violations);
+
+                ui.separator();
+                ui.horizontal(|ui| {
+                    if ui.button("✔ 가져오기").clicked() {
+                        apply = true;
+                    }
+                    if ui.button("❌ 취소").clicked() {
+                        cancel = true;
+                    }
+                });
+            });
+
+        if changed {
+            let existing = self
+                .erp_data
+                .data
+                .get(&structure.id)
+                .cloned()
+                .unwrap_or_default();
+            let references = self.reference_lookup(&structure, &[]);
+            if let Some(import) = &mut self.csv_import {
+                import.check(&structure, &existing, &references);
+            }
+        }
+        if apply {
+            if let Some(import) = self.csv_import.take() {
+                self.apply_csv_import(structure, import);
+            }
+        } else if cancel {
+            self.csv_import = None;
+        }
+    }
+
+    // 새 필드를 구조체에 추가한 뒤 매핑대로 행을 만들어 기존 데이터 뒤에 붙인다
+    fn apply_csv_import(&mut self, mut structure: CustomStructure, import: CsvImport) {
+        let mut columns: Vec<Option<String>> = Vec::new();
+        let mut added = false;
+        for (col, target) in import.targets.iter().enumerate() {
+            columns.push(match target {
+                ColumnTarget::Skip => None,
+                ColumnTarget::Field(id) => Some(id.clone()),
+                ColumnTarget::NewField(field_type) => {
+                    let mut field = Field::new();
+                    field.name = import.table.headers[col].trim().to_string();
+                    field.field_type = field_type.clone();
+                    let id = field.id.clone();
+                    structure.fields.push(field);
+                    added = true;
+                    Some(id)
+                }
+            });
+        }
+
+        if added {
+            let Some((cat_name, subcat_name)) = self.structure_location(&structure.id) else {
+                return;
+            };
+            self.commit_structure(&cat_name, &subcat_name, structure.clone(), true);
+        }
+
+        let mut rows = build_rows(&structure, &import.table.records, &columns);
+        // 찾을 수 없는 참조는 미리보기의 변환 실패 목록에 이미 나왔다
+        resolve_with(
+            &structure,
+            &self.reference_lookup(&structure, &[]),
+            &mut rows,
+        );
+        let total = rows.len();
+        let rejected = self.insert_rows(&structure, rows);
+        println!(
+            "CSV에서 {}행을 가져왔습니다 (변환 실패 {}건, 제약 조건 위반으로 제외 {}행)",
+            total - rejected.len(),
+            import.errors.len(),
+            rejected.len()
+        );
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn structure(names: &[&str]) -> CustomStructure {
+        CustomStructure {
+            fields: names
+                .iter()
+                .map(|name| Field {
+                    name: name.to_string(),
+                    ..Field::new()
+                })
+                .collect(),
+            ..CustomStructure::new()
+        }
+    }
+
+    // 열마다 연결된 필드 이름
+    fn matched(headers: &[&str], structure: &CustomStructure) -> Vec<Option<String>> {
+        let headers: Vec<String> = headers.iter().map(|h| h.to_string()).collect();
+        match_headers(&headers, structure)
+            .into_iter()
+            .map(|id| {
+                let id = id?;
+                structure
+                    .fields
+                    .iter()
+                    .find(|f| f.id == id)
+                    .map(|f| f.name.clone())
+            })
+            .collect()
+    }
+
+    fn names(names: &[Option<&str>]) -> Vec<Option<String>> {
+        names.iter().map(|n| n.map(str::to_string)).collect()
+    }
+
+    #[test]
+    fn exact_and_normalized_names() {
+        let structure = structure(&["이름", "전화 번호", "Unit Price"]);
+        assert_eq!(
+            matched(
+                &[" 이름 ", "전화번호", "unit_price", ROW_ID_COLUMN],
+                &structure
+            ),
+            names(&[Some("이름"), Some("전화 번호"), Some("Unit Price"), None])
+        );
+    }
+
+    #[test]
+    fn near_miss_names() {
+        let structure = structure(&["Quantity", "고객 이름"]);
+        // 오타 한 글자, 이름을 포함한 헤더
+        assert_eq!(
+            matched(&["Quantty", "고객 이름 (필수)"], &structure),
+            names(&[Some("Quantity"), Some("고객 이름")])
+        );
+    }
+
+    #[test]
+    fn exact_match_wins_over_earlier_near_miss() {
+        let structure = structure(&["이름", "주소"]);
+        assert_eq!(
+            matched(&["이룸", "이름"], &structure),
+            names(&[None, Some("이름")])
+        );
+    }
+
+    #[test]
+    fn ambiguous_headers_take_each_field_once() {
+        // 같은 이름이 두 번 나오면 앞 열만 연결
+        let structure_a = structure(&["수량"]);
+        assert_eq!(
+            matched(&["수량", "수량 "], &structure_a),
+            names(&[Some("수량"), None])
+        );
+
+        // 똑같이 비슷한 필드가 둘이면 구조체에서 앞선 필드, 다음 열은 남은 필드로
+        let structure_b = structure(&["공급 금액", "금액 합계"]);
+        assert_eq!(
+            matched(&["금액", "금액"], &structure_b),
+            names(&[Some("공급 금액"), Some("금액 합계")])
+        );
+    }
+
+    #[test]
+    fn unmatched_headers() {
+        let structure = structure(&["이름", "주소"]);
+        assert_eq!(
+            matched(&["비고", "", "  ", "extra"], &structure),
+            names(&[None, None, None, None])
+        );
+    }
+}
@@ -0,0 +1,433 @@
+// 통화(고정 소수) 필드: 부동소수 없이 정해진 소수 자릿수로 저장하고, 천 단위 구분 기호와 통화 기호로 표시
+use crate::Field;
+use eframe::egui;
+use egui::Response;
+use rust_decimal::{Decimal, RoundingStrategy};
+use serde::{Deserialize, Serialize};
+use std::str::FromStr;
+
+// 통화 코드, 기본 소수 자릿수, 표시 기호
+const PRESETS: [(&str, u32, &str); 6] = [
+    ("KRW", 0, "₩"),
+    ("USD", 2, "$"),
+    ("EUR", 2, "€"),
+    ("JPY", 0, "¥"),
+    ("CNY", 2, "¥"),
+    ("", 2, ""), // 통화 없는 소수
+];
+
+#[derive(Clone, Serialize, Deserialize, PartialEq, Debug)]
+pub(crate) struct Currency {
+    #[serde(default)]
+    pub(crate) code: String,
+    #[serde(default)]
+    pub(crate) scale: u32, // 소수 자릿수
+    #[serde(default, skip_serializing_if = "std::ops::Not::not")]
+    pub(crate) per_row: bool, // 행마다 거래 통화를 골라 "1234.50 USD"처럼 금액 뒤에 저장
+    #[serde(default, skip_serializing_if = "String::is_empty")]
+    pub(crate) date_field_id: String, // 원화 환산 기준일 필드, 비어 있으면 첫 날짜 필드
+}
+
+impl Default for Currency {
+    fn default() -> Self {
+        Currency {
+            code: "KRW".to_string(),
+            scale: 0,
+            per_row: false,
+            date_field_id: String::new(),
+        }
+    }
+}
+
+impl Currency {
+    fn symbol(&self) -> &'static str {
+        PRESETS
+            .iter()
+            .find(|(code, _, _)| *code == self.code)
+            .map_or("", |(_, _, symbol)| symbol)
+    }
+}
+
+// 필드의 통화 설정, 없으면 원화
+pub(crate) fn currency_of(field: &Field) -> Currency {
+    field.currency.clone().unwrap_or_default()
+}
+
+// 값에 적힌 통화 코드 (USD, jpy 등 영문 세 글자)
+pub(crate) fn code_in(value: &str) -> Option<String> {
+    value
+        .split(|c: char| !c.is_ascii_alphabetic())
+        .find(|token| token.len() == 3)
+        .map(|token| token.to_ascii_uppercase())
+}
+
+// 값 하나의 통화: 거래 통화 필드는 값에 적힌 코드, 아니면 필드 설정
+pub(crate) fn amount_currency(field: &Field, value: &str) -> Currency {
+    let currency = currency_of(field);
+    if !currency.per_row {
+        return currency;
+    }
+    match code_in(value) {
+        Some(code) if code != currency.code => {
+            let scale = PRESETS
+                .iter()
+                .find(|(preset, _, _)| *preset == code)
+                .map_or(currency.scale, |(_, scale, _)| *scale);
+            Currency {
+                code,
+                scale,
+                ..currency
+            }
+        }
+        _ => currency,
+    }
+}
+
+// 거래 통화 필드가 아닌데 다른 통화 코드가 적힌 값의 코드
+pub(crate) fn foreign_code(field: &Field, value: &str) -> Option<String> {
+    let currency = currency_of(field);
+    code_in(value).filter(|code| !currency.per_row && *code != currency.code)
+}
+
+// 필드 설정에 맞춘 저장 형식. 거래 통화 필드는 "1234.50 USD"
+pub(crate) fn normalize_field(field: &Field, value: &str) -> Result<String, String> {
+    if let Some(code) = foreign_code(field, value) {
+        return Err(format!("{} 금액은 넣을 수 없습니다: {}", code, value));
+    }
+    let currency = amount_currency(field, value);
+    let amount = normalize(&currency, value)?;
+    if currency.per_row && !amount.is_empty() && !currency.code.is_empty() {
+        return Ok(format!("{} {}", amount, currency.code));
+    }
+    Ok(amount)
+}
+
+pub(crate) fn display_field(field: &Field, value: &str) -> String {
+    display(&amount_currency(field, value), value)
+}
+
+// 천 단위 구분 기호, 통화 기호와 코드, "원"을 걷어 내고 읽는다. (1,234)처럼 괄호로 쓴 음수도 허용
+pub(crate) fn parse_decimal(value: &str) -> Option<Decimal> {
+    let text: String = value
+        .chars()
+        .filter(|c| !matches!(c, ',' | ' ' | '₩' | '$' | '€' | '¥' | '원'))
+        .collect();
+    let text = text.trim_matches(|c: char| c.is_ascii_alphabetic());
+    let (negative, text) = match text.strip_prefix('(').and_then(|t| t.strip_suffix(')')) {
+        Some(inner) => (true, inner),
+        None => (false, text),
+    };
+    if text.is_empty() {
+        return None;
+    }
+    let number = Decimal::from_str(text)
+        .or_else(|_| Decimal::from_scientific(text))
+        .ok()?;
+    Some(if negative { -number } else { number })
+}
+
+// 소수 자릿수에 맞춰 반올림(0.5는 0에서 먼 쪽)한 저장 형식: 1234.50
+pub(crate) fn format_fixed(number: Decimal, scale: u32) -> String {
+    let mut rounded = number.round_dp_with_strategy(scale, RoundingStrategy::MidpointAwayFromZero);
+    rounded.rescale(scale);
+    rounded.to_string()
+}
+
+pub(crate) fn normalize(currency: &Currency, value: &str) -> Result<String, String> {
+    if value.trim().is_empty() {
+        return Ok(String::new());
+    }
+    let number = parse_decimal(value).ok_or(format!("금액이 아닙니다: {}", value))?;
+    Ok(format_fixed(number, currency.scale))
+}
+
+// 천 단위 구분 기호를 넣은 값 (통화 기호 없이): 1,234.50
+pub(crate) fn with_separators(number: Decimal, scale: u32) -> String {
+    let text = format_fixed(number, scale);
+    let (sign, digits) = match text.strip_prefix('-') {
+        Some(digits) => ("-", digits),
+        None => ("", text.as_str()),
+    };
+    let (integer, fraction) = match digits.split_once('.') {
+        Some((integer, fraction)) => (integer, Some(fraction)),
+        None => (digits, None),
+    };
+    let mut grouped = String::new();
+    for (i, c) in integer.chars().enumerate() {
+        if i > 0 && (integer.len() - i) % 3 == 0 {
+            grouped.push(',');
+        }
+        grouped.push(c);
+    }
+    match fraction {
+        Some(fraction) => format!("{}{}.{}", sign, grouped, fraction),
+        None => format!("{}{}", sign, grouped),
+    }
+}
+
+// 표시 값: ₩1,234, -$1,234.50, 1,234.50 CHF. 읽을 수 없는 값은 그대로
+pub(crate) fn display(currency: &Currency, value: &str) -> String {
+    let Some(number) = parse_decimal(value) else {
+        return value.to_string();
+    };
+    let text = with_separators(number, currency.scale);
+    match (currency.symbol(), currency.code.as_str()) {
+        ("", "") => text,
+        ("", code) => format!("{} {}", text, code),
+        (symbol, _) => match text.strip_prefix('-') {
+            Some(abs) => format!("-{}{}", symbol, abs),
+            None => format!("{}{}", symbol, text),
+        },
+    }
+}
+
+// Excel 통화 형식: "₩"#,##0, "$"#,##0.00
+pub(crate) fn excel_format(currency: &Currency) -> String {
+    let digits = match currency.scale {
+        0 => "#,##0".to_string(),
+        scale => format!("#,##0.{}", "0".repeat(scale as usize)),
+    };
+    match (currency.symbol(), currency.code.as_str()) {
+        ("", "") => digits,
+        ("", code) => format!("{} \"{}\"", digits, code),
+        (symbol, _) => format!("\"{}\"{}", symbol, digits),
+    }
+}
+
+fn code_label(code: &str) -> &str {
+    if code.is_empty() {
+        "통화 없음"
+    } else {
+        code
+    }
+}
+
+// 거래 통화 선택 목록 (통화 없음 제외)
+pub(crate) fn codes() -> impl Iterator<Item = &'static str> {
+    PRESETS
+        .iter()
+        .map(|(code, _, _)| *code)
+        .filter(|code| !code.is_empty())
+}
+
+// 구조체 편집기의 거래 통화 설정: 행마다 통화를 고르고 원화 환산 기준일 필드를 정한다
+pub(crate) fn transaction_editor(
+    ui: &mut egui::Ui,
+    idx: usize,
+    field: &mut Field,
+    date_fields: &[Field],
+) {
+    let currency = field.currency.get_or_insert_with(Currency::default);
+    ui.horizontal(|ui| {
+        ui.checkbox(&mut currency.per_row, "행마다 거래 통화 선택");
+        if !currency.per_row {
+            return;
+        }
+        ui.label("환산 기준일:");
+        let text = date_fields
+            .iter()
+            .find(|f| f.id == currency.date_field_id)
+            .map_or("첫 날짜 필드", |f| f.name.as_str());
+        egui::ComboBox::from_id_salt(("currency_date_field", idx))
+            .selected_text(text)
+            .show_ui(ui, |ui| {
+                ui.selectable_value(&mut currency.date_field_id, String::new(), "첫 날짜 필드");
+                for date_field in date_fields {
+                    ui.selectable_value(
+                        &mut currency.date_field_id,
+                        date_field.id.clone(),
+                        &date_field.name,
+                    );
+                }
+            });
+    });
+}
+
+// 구조체 편집기의 통화와 소수 자릿수
+pub(crate) fn currency_editor(ui: &mut egui::Ui, id: impl std::hash::Hash, field: &mut Field) {
+    let currency = field.currency.get_or_insert_with(Currency::default);
+    let id = ui.make_persistent_id(id);
+    ui.horizontal(|ui| {
+        ui.label("통화:");
+        egui::ComboBox::from_id_salt(id.with("code"))
+            .selected_text(code_label(&currency.code))
+            .show_ui(ui, |ui| {
+                for (code, scale, _) in PRESETS {
+                    if ui
+                        .selectable_label(currency.code == code, code_label(code))
+                        .clicked()
+                    {
+                        currency.code = code.to_string();
+                        currency.scale = scale;
+                    }
+                }
+            });
+        ui.label("소수 자릿수:");
+        ui.add(egui::DragValue::new(&mut currency.scale).range(0..=6));
+        ui.weak(format!("예: {}", display(currency, "1234567.891")));
+    });
+}
+
+// 통화 셀: 끌어서 금액이 바뀌지 않도록 글자로 입력. 입력하는 동안은 그대로 두고 다 쓰면 자릿수에 맞춘다.
+// 거래 통화 필드는 옆의 목록에서 통화를 고른다
+pub(crate) fn currency_cell(
+    ui: &mut egui::Ui,
+    id: impl std::hash::Hash,
+    field: &Field,
+    value: &mut String,
+) -> Response {
+    let currency = amount_currency(field, value);
+    let id = ui.make_persistent_id(id).with("currency_cell");
+    // 금액을 쓰기 전에 고른 통화는 잠시 기억해 둔다
+    let pending = id.with("pending_code");
+    let mut code = value
+        .trim()
+        .is_empty()
+        .then(|| ui.data(|d| d.get_temp::<String>(pending)))
+        .flatten()
+        .unwrap_or_else(|| currency.code.clone());
+    let mut text = ui
+        .data_mut(|d| d.get_temp::<String>(id))
+        .unwrap_or_else(|| {
+            parse_decimal(value).map_or_else(
+                || value.clone(),
+                |number| with_separators(number, currency.scale),
+            )
+        });
+    let response = ui
+        .horizontal(|ui| {
+            let response = ui.add(
+                egui::TextEdit::singleline(&mut text)
+                    .horizontal_align(egui::Align::Max)
+                    .desired_width(110.0),
+            );
+            if currency.per_row {
+                egui::ComboBox::from_id_salt(id.with("code"))
+                    .width(60.0)
+                    .selected_text(&code)
+                    .show_ui(ui, |ui| {
+                        for preset in codes() {
+                            ui.selectable_value(&mut code, preset.to_string(), preset);
+                        }
+                    });
+            } else if !currency.code.is_empty() {
+                ui.weak(&currency.code);
+            }
+            response
+        })
+        .inner;
+    if value.trim().is_empty() {
+        ui.data_mut(|d| d.insert_temp(pending, code.clone()));
+    } else if code != currency.code {
+        // 금액은 그대로 두고 통화만 바꾼다
+        let amount = value.trim_end_matches(|c: char| c.is_ascii_alphabetic() || c == ' ');
+        *value = normalize_field(field, &format!("{} {}", amount, code))
+            .unwrap_or_else(|_| value.clone());
+        return response;
+    }
+    if response.has_focus() {
+        ui.data_mut(|d| d.insert_temp(id, text));
+    } else {
+        ui.data_mut(|d| d.remove::<String>(id));
+        if response.lost_focus() {
+            match entered_value(field, &text, &code) {
+                Some(entered) => *value = entered,
+                None => eprintln!("금액이 아닙니다: {}", text),
+            }
+        }
+    }
+    response
+}
+
+// 셀에 입력한 금액의 저장 값. 비우면 빈 값, 읽을 수 없으면 None (기존 값을 유지)
+fn entered_value(field: &Field, text: &str, code: &str) -> Option<String> {
+    if text.trim().is_empty() {
+        return Some(String::new());
+    }
+    // 입력에 통화 코드가 없으면 고른 통화
+    let text = match code_in(text) {
+        None if currency_of(field).per_row => format!("{} {}", text.trim(), code),
+        _ => text.to_string(),
+    };
+    normalize_field(field, &text).ok()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn number(n: &str) -> Decimal {
+        Decimal::from_str(n).unwrap()
+    }
+
+    #[test]
+    fn parse_decimal_formats() {
+        assert_eq!(parse_decimal("1,234.50"), Some(number("1234.50")));
+        assert_eq!(parse_decimal("₩1,234,567"), Some(number("1234567")));
+        assert_eq!(parse_decimal("1,000원"), Some(number("1000")));
+        assert_eq!(parse_decimal("$ 12.5"), Some(number("12.5")));
+        assert_eq!(parse_decimal("1234.50 USD"), Some(number("1234.50")));
+        assert_eq!(parse_decimal("-7"), Some(number("-7")));
+        assert_eq!(parse_decimal("1e3"), Some(number("1000")));
+        // 회계 표기: 괄호는 음수
+        assert_eq!(parse_decimal("(1,234.50)"), Some(number("-1234.50")));
+        assert_eq!(parse_decimal("₩(500)"), Some(number("-500")));
+        assert_eq!(parse_decimal(""), None);
+        assert_eq!(parse_decimal("()"), None);
+        assert_eq!(parse_decimal("USD"), None);
+        assert_eq!(parse_decimal("1.2.3"), None);
+    }
+
+    #[test]
+    fn format_fixed_rounds_half_away_from_zero() {
+        assert_eq!(format_fixed(number("2.5"), 0), "3");
+        assert_eq!(format_fixed(number("-2.5"), 0), "-3");
+        assert_eq!(format_fixed(number("1.005"), 2), "1.01");
+        assert_eq!(format_fixed(number("-1.005"), 2), "-1.01");
+        assert_eq!(format_fixed(number("1.004"), 2), "1.00");
+        // 자릿수가 모자라면 0을 채운다
+        assert_eq!(format_fixed(number("1.2"), 2), "1.20");
+        assert_eq!(format_fixed(number("1234"), 0), "1234");
+        assert_eq!(format_fixed(number("0.1") + number("0.2"), 2), "0.30");
+    }
+
+    #[test]
+    fn entered_value_clears_and_rejects() {
+        let field = Field {
+            field_type: crate::FieldType::Currency,
+            currency: Some(Currency {
+                code: "KRW".to_string(),
+                per_row: true,
+                ..Currency::default()
+            }),
+            ..Field::default()
+        };
+        assert_eq!(entered_value(&field, "", "USD"), Some(String::new()));
+        assert_eq!(entered_value(&field, "  ", "USD"), Some(String::new()));
+        assert_eq!(
+            entered_value(&field, "1,234.5", "USD"),
+            Some("1234.50 USD".to_string())
+        );
+        assert_eq!(
+            entered_value(&field, "1000 JPY", "USD"),
+            Some("1000 JPY".to_string())
+        );
+        assert_eq!(
+            entered_value(&field, "1500", "KRW"),
+            Some("1500 KRW".to_string())
+        );
+        assert_eq!(entered_value(&field, "천 원", "KRW"), None);
+    }
+
+    #[test]
+    fn with_separators_groups_thousands() {
+        assert_eq!(with_separators(number("999"), 0), "999");
+        assert_eq!(with_separators(number("1000"), 0), "1,000");
+        assert_eq!(with_separators(number("100000"), 0), "100,000");
+        assert_eq!(with_separators(number("1234567"), 0), "1,234,567");
+        assert_eq!(with_separators(number("1234567.891"), 2), "1,234,567.89");
+        assert_eq!(with_separators(number("-1234.5"), 2), "-1,234.50");
+        assert_eq!(with_separators(number("-999.995"), 2), "-1,000.00");
+        assert_eq!(with_separators(number("0.5"), 0), "1");
+    }
+}
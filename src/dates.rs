@@ -0,0 +1,692 @@
+// 날짜, 날짜와 시간, 시간 필드: 여러 입력 형식을 ISO 8601로 맞춰 저장하고 표시 형식은 필드마다 정한다
+use crate::{ERPApp, Field, FieldType, Row};
+use chrono::{
+    DateTime, Datelike, FixedOffset, Local, NaiveDate, NaiveDateTime, NaiveTime, Offset, TimeZone,
+    Timelike,
+};
+use eframe::egui;
+use egui::Response;
+use egui_extras::DatePickerButton;
+use serde::{Deserialize, Serialize};
+
+// 저장 형식
+const DATE_FORMAT: &str = "%Y-%m-%d";
+const DATETIME_FORMAT: &str = "%Y-%m-%dT%H:%M:%S%:z";
+const TIME_FORMAT: &str = "%H:%M:%S";
+
+#[derive(Clone, Copy, Default, Serialize, Deserialize, PartialEq, Debug)]
+pub(crate) enum DateFormat {
+    #[default]
+    Iso, // 2024-01-03
+    Korean, // 2024년 01월 03일
+    Dotted, // 2024.01.03
+    Us,     // 01/03/2024
+}
+
+impl DateFormat {
+    const ALL: [DateFormat; 4] = [
+        DateFormat::Iso,
+        DateFormat::Korean,
+        DateFormat::Dotted,
+        DateFormat::Us,
+    ];
+
+    pub(crate) fn is_default(&self) -> bool {
+        *self == DateFormat::Iso
+    }
+
+    fn label(&self) -> &'static str {
+        match self {
+            DateFormat::Iso => "YYYY-MM-DD",
+            DateFormat::Korean => "YYYY년 MM월 DD일",
+            DateFormat::Dotted => "YYYY.MM.DD",
+            DateFormat::Us => "MM/DD/YYYY",
+        }
+    }
+
+    fn pattern(&self) -> &'static str {
+        match self {
+            DateFormat::Iso => "%Y-%m-%d",
+            DateFormat::Korean => "%Y년 %m월 %d일",
+            DateFormat::Dotted => "%Y.%m.%d",
+            DateFormat::Us => "%m/%d/%Y",
+        }
+    }
+
+    // Excel 표시 형식. 기본(ISO)은 내보내기 설정의 날짜 형식을 따른다
+    pub(crate) fn excel_format(&self) -> Option<&'static str> {
+        match self {
+            DateFormat::Iso => None,
+            DateFormat::Korean => Some(r#"yyyy"년" mm"월" dd"일""#),
+            DateFormat::Dotted => Some("yyyy.mm.dd"),
+            DateFormat::Us => Some("mm/dd/yyyy"),
+        }
+    }
+}
+
+pub(crate) fn is_temporal(field_type: &FieldType) -> bool {
+    matches!(
+        field_type,
+        FieldType::Date | FieldType::DateTime | FieldType::Time
+    )
+}
+
+// 2024-01-03, 2024.1.3, 2024/01/03, 24/01/03, 01/03/2024, 20240103, 2024년 1월 3일, 1월 3일 (올해)
+pub(crate) fn parse_date(value: &str) -> Option<NaiveDate> {
+    let value = value.trim();
+    if value.len() == 8 && value.bytes().all(|b| b.is_ascii_digit()) {
+        return NaiveDate::parse_from_str(value, "%Y%m%d").ok();
+    }
+    let korean = value.contains('월');
+    let value = value.replace(['년', '월'], "-").replace('일', "");
+    let parts: Vec<&str> = value
+        .split(['-', '.', '/', ' '])
+        .filter(|p| !p.is_empty())
+        .collect();
+    if !parts.iter().all(|p| p.bytes().all(|b| b.is_ascii_digit())) {
+        return None;
+    }
+    let number = |i: usize| parts[i].parse::<u32>().ok();
+    let (year, month, day) = match parts.len() {
+        2 if korean => (Local::now().year(), number(0)?, number(1)?),
+        3 if parts[0].len() == 4 => (number(0)? as i32, number(1)?, number(2)?),
+        // 미국식 MM/DD/YYYY
+        3 if parts[2].len() == 4 => (number(2)? as i32, number(0)?, number(1)?),
+        // 24/01/03처럼 두 자리 연도는 2000년대
+        3 if parts[0].len() == 2 && (korean || parts.iter().all(|p| p.len() == 2)) => {
+            (2000 + number(0)? as i32, number(1)?, number(2)?)
+        }
+        _ => return None,
+    };
+    NaiveDate::from_ymd_opt(year, month, day)
+}
+
+// 15:30, 15:30:45, 오후 3:30, 3:30 PM, 15시 30분
+pub(crate) fn parse_time(value: &str) -> Option<NaiveTime> {
+    let mut value = value.trim().to_uppercase();
+    let mut pm = None;
+    for (marker, is_pm) in [("오전", false), ("오후", true), ("AM", false), ("PM", true)] {
+        if value.contains(marker) {
+            pm = Some(is_pm);
+            value = value.replace(marker, "");
+        }
+    }
+    let korean = value.contains('시');
+    let value = value.replace(['시', '분'], ":").replace('초', "");
+    let parts: Vec<&str> = value
+        .split(':')
+        .map(str::trim)
+        .filter(|p| !p.is_empty())
+        .collect();
+    if parts.is_empty() || parts.len() > 3 || (parts.len() == 1 && !korean) {
+        return None;
+    }
+    let mut hour: u32 = parts[0].parse().ok()?;
+    let minute: u32 = parts.get(1).map_or(Some(0), |p| p.parse().ok())?;
+    // 초의 소수점 아래는 버린다
+    let second = parts.get(2).map_or(Some(0.0), |p| p.parse::<f64>().ok())?;
+    if second < 0.0 {
+        return None;
+    }
+    match pm {
+        Some(_) if hour > 12 => return None,
+        Some(true) if hour < 12 => hour += 12,
+        Some(false) if hour == 12 => hour = 0,
+        _ => {}
+    }
+    NaiveTime::from_hms_opt(hour, minute, second as u32)
+}
+
+// +09:00, +0900, +09, Z, UTC, KST
+fn parse_offset(value: &str) -> Option<FixedOffset> {
+    match value {
+        "Z" | "UTC" => return FixedOffset::east_opt(0),
+        "KST" => return FixedOffset::east_opt(9 * 3600),
+        _ => {}
+    }
+    let sign = match value.chars().next()? {
+        '+' => 1,
+        '-' => -1,
+        _ => return None,
+    };
+    let digits = value[1..].replace(':', "");
+    if !matches!(digits.len(), 2 | 4) || !digits.bytes().all(|b| b.is_ascii_digit()) {
+        return None;
+    }
+    let hours: i32 = digits[..2].parse().ok()?;
+    let minutes: i32 = digits[2..].parse().unwrap_or(0);
+    FixedOffset::east_opt(sign * (hours * 3600 + minutes * 60))
+}
+
+// 시간 부분 끝의 시간대 표기를 떼어 낸다
+fn split_offset(value: &str) -> (&str, Option<&str>) {
+    let value = value.trim();
+    for suffix in ["UTC", "KST", "Z"] {
+        if let Some(rest) = value.strip_suffix(suffix) {
+            return (rest.trim_end(), Some(suffix));
+        }
+    }
+    match value.rfind(['+', '-']) {
+        Some(pos) => (value[..pos].trim_end(), Some(&value[pos..])),
+        None => (value, None),
+    }
+}
+
+// 시간대가 없는 값은 이 컴퓨터의 시간대로 본다
+pub(crate) fn local_datetime(naive: NaiveDateTime) -> Option<DateTime<FixedOffset>> {
+    Local
+        .from_local_datetime(&naive)
+        .earliest()
+        .map(|d| d.fixed_offset())
+}
+
+// RFC 3339 또는 "날짜 시간 [시간대]". 시간이 없으면 자정
+pub(crate) fn parse_datetime(value: &str) -> Option<DateTime<FixedOffset>> {
+    let value = value.trim();
+    if let Ok(datetime) = DateTime::parse_from_rfc3339(value) {
+        return Some(datetime);
+    }
+    let (date, time) = match value
+        .split_once('T')
+        .and_then(|(date, time)| Some((parse_date(date)?, time.to_string())))
+    {
+        Some(parts) => parts,
+        None => {
+            // 날짜로 읽히는 가장 긴 앞부분 뒤를 시간으로
+            let tokens: Vec<&str> = value.split_whitespace().collect();
+            let (n, date) = (1..=tokens.len())
+                .rev()
+                .find_map(|n| parse_date(&tokens[..n].join(" ")).map(|d| (n, d)))?;
+            (date, tokens[n..].join(" "))
+        }
+    };
+    let (time, offset) = split_offset(&time);
+    let time = if time.is_empty() {
+        NaiveTime::MIN
+    } else {
+        parse_time(time)?
+    };
+    let naive = date.and_time(time);
+    match offset {
+        Some(offset) => naive.and_local_timezone(parse_offset(offset)?).single(),
+        None => local_datetime(naive),
+    }
+}
+
+fn to_date(value: &str) -> Option<NaiveDate> {
+    parse_date(value).or_else(|| parse_datetime(value).map(|d| d.date_naive()))
+}
+
+pub(crate) fn normalize_date(value: &str) -> Option<String> {
+    to_date(value).map(|d| d.format(DATE_FORMAT).to_string())
+}
+
+pub(crate) fn normalize_datetime(value: &str) -> Option<String> {
+    parse_datetime(value).map(|d| d.format(DATETIME_FORMAT).to_string())
+}
+
+pub(crate) fn normalize_time(value: &str) -> Option<String> {
+    parse_time(value)
+        .or_else(|| parse_datetime(value).map(|d| d.time()))
+        .map(|t| t.format(TIME_FORMAT).to_string())
+}
+
+// 정렬과 비교에 쓰는 값: 날짜는 일 수, 날짜와 시간은 UTC 기준 초, 시간은 자정부터의 초
+pub(crate) fn temporal_key(field_type: &FieldType, value: &str) -> Option<i64> {
+    match field_type {
+        FieldType::Date => to_date(value).map(|d| d.num_days_from_ce() as i64),
+        FieldType::DateTime => parse_datetime(value).map(|d| d.timestamp()),
+        FieldType::Time => parse_time(value).map(|t| t.num_seconds_from_midnight() as i64),
+        _ => None,
+    }
+}
+
+// 오늘/지금을 필드 타입의 저장 형식으로 (시간은 분 단위)
+pub(crate) fn now_value(field_type: &FieldType) -> String {
+    let now = Local::now();
+    match field_type {
+        FieldType::DateTime => now.fixed_offset().format(DATETIME_FORMAT).to_string(),
+        FieldType::Time => now.time().format("%H:%M:00").to_string(),
+        _ => now.date_naive().format(DATE_FORMAT).to_string(),
+    }
+}
+
+fn time_text(time: NaiveTime) -> String {
+    if time.second() == 0 {
+        time.format("%H:%M").to_string()
+    } else {
+        time.format("%H:%M:%S").to_string()
+    }
+}
+
+// 표시 형식을 적용한 값. 읽을 수 없는 값은 그대로
+pub(crate) fn display(field: &Field, value: &str) -> String {
+    let pattern = field.date_format.pattern();
+    let formatted = match field.field_type {
+        FieldType::Date => to_date(value).map(|d| d.format(pattern).to_string()),
+        FieldType::DateTime => parse_datetime(value).map(|d| {
+            let text = format!("{} {}", d.format(pattern), time_text(d.time()));
+            // 이 컴퓨터와 다른 시간대만 표시
+            if d.with_timezone(&Local).offset().fix() == *d.offset() {
+                text
+            } else {
+                format!("{} ({})", text, d.format("%:z"))
+            }
+        }),
+        FieldType::Time => parse_time(value).map(time_text),
+        _ => None,
+    };
+    formatted.unwrap_or_else(|| value.to_string())
+}
+
+#[derive(Clone, Copy, PartialEq)]
+enum Unit {
+    Day,
+    Second,
+}
+
+// 필터 조건을 반열린 구간 [시작, 끝)으로. 2024, 2024-01, 2024년 1월처럼 연/월만 쓰면 그 기간 전체
+fn query_range(field_type: &FieldType, query: &str) -> Option<(Unit, i64, i64)> {
+    if *field_type == FieldType::Time {
+        let seconds = parse_time(query)?.num_seconds_from_midnight() as i64;
+        return Some((Unit::Second, seconds, seconds + 1));
+    }
+    let day = |d: NaiveDate| d.num_days_from_ce() as i64;
+    let cleaned = query.replace(['년', '월'], " ");
+    let parts: Vec<&str> = cleaned
+        .split(['-', '.', '/', ' '])
+        .filter(|p| !p.is_empty())
+        .collect();
+    let numeric = parts.iter().all(|p| p.bytes().all(|b| b.is_ascii_digit()));
+    let (start, end) = match parts.as_slice() {
+        [year] if numeric && year.len() == 4 => {
+            let year: i32 = year.parse().ok()?;
+            (
+                NaiveDate::from_ymd_opt(year, 1, 1)?,
+                NaiveDate::from_ymd_opt(year + 1, 1, 1)?,
+            )
+        }
+        [year, month] if numeric && year.len() == 4 => {
+            let start = NaiveDate::from_ymd_opt(year.parse().ok()?, month.parse().ok()?, 1)?;
+            (start, start.checked_add_months(chrono::Months::new(1))?)
+        }
+        _ => {
+            let date = parse_date(query)?;
+            (date, date.succ_opt()?)
+        }
+    };
+    Some((Unit::Day, day(start), day(end)))
+}
+
+// 날짜/시간 필드의 필터 조건. 앞에 >=, <=, >, <, = 를 붙일 수 있다.
+// 이 필드 타입의 조건으로 읽을 수 없으면 None
+pub(crate) fn matches_filter(field_type: &FieldType, value: &str, query: &str) -> Option<bool> {
+    if !is_temporal(field_type) {
+        return None;
+    }
+    let query = query.trim();
+    let (op, rest) = [">=", "<=", ">", "<", "="]
+        .iter()
+        .find_map(|op| query.strip_prefix(op).map(|rest| (*op, rest.trim())))
+        .unwrap_or(("=", query));
+    let (unit, start, end) = query_range(field_type, rest)?;
+    // 날짜 단위 조건은 값이 가진 시간대의 날짜로 비교
+    let key = match unit {
+        Unit::Day => to_date(value).map(|d| d.num_days_from_ce() as i64),
+        Unit::Second => temporal_key(field_type, value),
+    };
+    let Some(key) = key else {
+        return Some(false);
+    };
+    Some(match op {
+        ">=" => key >= start,
+        ">" => key >= end,
+        "<=" => key < end,
+        "<" => key < start,
+        _ => start <= key && key < end,
+    })
+}
+
+// 구조체 편집기의 날짜 표시 형식
+pub(crate) fn format_editor(ui: &mut egui::Ui, idx: usize, field: &mut Field) {
+    let today = Local::now().date_naive();
+    ui.horizontal(|ui| {
+        ui.label("표시 형식:");
+        egui::ComboBox::from_id_salt(("date_format", idx))
+            .selected_text(field.date_format.label())
+            .show_ui(ui, |ui| {
+                for format in DateFormat::ALL {
+                    let text = format!("{}  ({})", format.label(), today.format(format.pattern()));
+                    ui.selectable_value(&mut field.date_format, format, text);
+                }
+            });
+    });
+}
+
+// 읽을 수 없는 예전 값은 빨간 글자로 그대로 고칠 수 있게 하고, 다 쓰면 저장 형식으로
+fn invalid_cell(
+    ui: &mut egui::Ui,
+    value: &mut String,
+    normalize: fn(&str) -> Option<String>,
+) -> Response {
+    let response = ui.add(
+        egui::TextEdit::singleline(value)
+            .text_color(egui::Color32::RED)
+            .desired_width(120.0),
+    );
+    if response.lost_focus() {
+        if let Some(normalized) = normalize(value) {
+            *value = normalized;
+        }
+    }
+    response.on_hover_text("날짜/시간으로 읽을 수 없는 값입니다")
+}
+
+// 빈 값은 버튼을 눌러 지금 날짜/시간으로 채운다
+fn empty_cell(ui: &mut egui::Ui, icon: &str, value: &mut String, now: String) -> Response {
+    let mut response = ui.small_button(icon).on_hover_text("지금으로 채우기");
+    if response.clicked() {
+        *value = now;
+        response.mark_changed();
+    }
+    response
+}
+
+fn hour_minute(ui: &mut egui::Ui, time: &mut NaiveTime) -> Response {
+    let mut hour = time.hour();
+    let mut minute = time.minute();
+    let response = ui.add(egui::DragValue::new(&mut hour).range(0..=23));
+    ui.label(":");
+    let response = response.union(ui.add(egui::DragValue::new(&mut minute).range(0..=59)));
+    if response.changed() {
+        if let Some(new_time) = NaiveTime::from_hms_opt(hour, minute, time.second()) {
+            *time = new_time;
+        }
+    }
+    response
+}
+
+// 날짜 셀: 달력 선택기와 지우기 버튼
+pub(crate) fn date_cell(
+    ui: &mut egui::Ui,
+    id: impl std::hash::Hash,
+    field: &Field,
+    value: &mut String,
+) -> Response {
+    if value.trim().is_empty() {
+        return empty_cell(ui, "📆", value, now_value(&FieldType::Date));
+    }
+    let Some(mut date) = to_date(value) else {
+        return invalid_cell(ui, value, normalize_date);
+    };
+    let salt = format!("date_picker_{}", ui.make_persistent_id(id).value());
+    ui.horizontal(|ui| {
+        let mut response = ui.add(
+            DatePickerButton::new(&mut date)
+                .id_salt(&salt)
+                .format(field.date_format.pattern())
+                .calendar_week(false),
+        );
+        if response.changed() {
+            *value = date.format(DATE_FORMAT).to_string();
+        }
+        if ui.small_button("✖").on_hover_text("지우기").clicked() {
+            value.clear();
+            response.mark_changed();
+        }
+        response
+    })
+    .inner
+}
+
+// 날짜와 시간 셀: 달력, 시:분, 이 컴퓨터와 다른 시간대 표시
+pub(crate) fn datetime_cell(
+    ui: &mut egui::Ui,
+    id: impl std::hash::Hash,
+    field: &Field,
+    value: &mut String,
+) -> Response {
+    if value.trim().is_empty() {
+        return empty_cell(ui, "📆", value, now_value(&FieldType::DateTime));
+    }
+    let Some(datetime) = parse_datetime(value) else {
+        return invalid_cell(ui, value, normalize_datetime);
+    };
+    let salt = format!("datetime_picker_{}", ui.make_persistent_id(id).value());
+    let mut date = datetime.date_naive();
+    let mut time = datetime.time();
+    ui.horizontal(|ui| {
+        let mut response = ui.add(
+            DatePickerButton::new(&mut date)
+                .id_salt(&salt)
+                .format(field.date_format.pattern())
+                .calendar_week(false),
+        );
+        let time_changed = hour_minute(ui, &mut time).changed();
+        if datetime.with_timezone(&Local).offset().fix() != *datetime.offset() {
+            ui.weak(datetime.format("%:z").to_string());
+        }
+        if response.changed() || time_changed {
+            // 원래 값의 시간대를 유지
+            if let Some(new) = date
+                .and_time(time)
+                .and_local_timezone(*datetime.offset())
+                .single()
+            {
+                *value = new.format(DATETIME_FORMAT).to_string();
+                response.mark_changed();
+            }
+        }
+        if ui.small_button("✖").on_hover_text("지우기").clicked() {
+            value.clear();
+            response.mark_changed();
+        }
+        response
+    })
+    .inner
+}
+
+// 시간 셀: 시:분
+pub(crate) fn time_cell(ui: &mut egui::Ui, value: &mut String) -> Response {
+    if value.trim().is_empty() {
+        return empty_cell(ui, "🕒", value, now_value(&FieldType::Time));
+    }
+    let Some(mut time) = parse_time(value) else {
+        return invalid_cell(ui, value, normalize_time);
+    };
+    ui.horizontal(|ui| {
+        let mut response = hour_minute(ui, &mut time);
+        if response.changed() {
+            *value = time.format(TIME_FORMAT).to_string();
+        }
+        if ui.small_button("✖").on_hover_text("지우기").clicked() {
+            value.clear();
+            response.mark_changed();
+        }
+        response
+    })
+    .inner
+}
+
+impl ERPApp {
+    // 예전에 자유 형식으로 입력한 날짜/시간 값을 저장 형식으로 맞춘다 (읽을 수 없는 값은 그대로 둔다).
+    // CSV 백업과의 불일치를 확인하기 전인 구조체는 어느 쪽을 남길지 정할 때까지 건드리지 않는다
+    pub(crate) fn normalize_temporal_values(&mut self) {
+        let structures: Vec<_> = self
+            .custom_structures
+            .iter()
+            .flat_map(|c| c.subcategories.iter())
+            .flat_map(|s| s.structures.iter())
+            .filter(|s| s.fields.iter().any(|f| is_temporal(&f.field_type)))
+            .filter(|s| !self.backup_unresolved(&s.id))
+            .cloned()
+            .collect();
+        for structure in structures {
+            let Some(rows) = self.erp_data.data.get_mut(&structure.id) else {
+                continue;
+            };
+            let mut changed: Vec<Row> = Vec::new();
+            for row in rows.iter_mut() {
+                let mut row_changed = false;
+                for field in structure
+                    .fields
+                    .iter()
+                    .filter(|f| is_temporal(&f.field_type))
+                {
+                    let Some(field_value) = row.values.get_mut(&field.id) else {
+                        continue;
+                    };
+                    let normalized = match field.field_type {
+                        FieldType::Date => normalize_date(&field_value.value),
+                        FieldType::DateTime => normalize_datetime(&field_value.value),
+                        _ => normalize_time(&field_value.value),
+                    };
+                    if let Some(normalized) = normalized.filter(|n| *n != field_value.value) {
+                        field_value.value = normalized;
+                        row_changed = true;
+                    }
+                }
+                if row_changed {
+                    changed.push(row.clone());
+                }
+            }
+            if changed.is_empty() {
+                continue;
+            }
+            println!(
+                "{}: 날짜/시간 값 {}행을 표준 형식으로 바꿨습니다",
+                structure.name,
+                changed.len()
+            );
+            for row in &changed {
+                self.save_row(&structure, row);
+            }
+            self.save_to_csv(&structure.id);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn date(y: i32, m: u32, d: u32) -> NaiveDate {
+        NaiveDate::from_ymd_opt(y, m, d).unwrap()
+    }
+
+    fn time(h: u32, m: u32, s: u32) -> NaiveTime {
+        NaiveTime::from_hms_opt(h, m, s).unwrap()
+    }
+
+    #[test]
+    fn parse_date_formats() {
+        assert_eq!(parse_date("2024-01-03"), Some(date(2024, 1, 3)));
+        assert_eq!(parse_date("2024.1.3"), Some(date(2024, 1, 3)));
+        assert_eq!(parse_date("2024/01/03"), Some(date(2024, 1, 3)));
+        assert_eq!(parse_date("24/01/03"), Some(date(2024, 1, 3)));
+        assert_eq!(parse_date("01/03/2024"), Some(date(2024, 1, 3)));
+        assert_eq!(parse_date("20240103"), Some(date(2024, 1, 3)));
+        assert_eq!(parse_date("2024년 1월 3일"), Some(date(2024, 1, 3)));
+        assert_eq!(parse_date("1월 3일"), Some(date(Local::now().year(), 1, 3)));
+        assert_eq!(parse_date("2024-02-30"), None);
+        assert_eq!(parse_date("2024-01"), None);
+        assert_eq!(parse_date("1/3"), None);
+        assert_eq!(parse_date("내일"), None);
+    }
+
+    #[test]
+    fn parse_time_formats() {
+        assert_eq!(parse_time("15:30"), Some(time(15, 30, 0)));
+        assert_eq!(parse_time("15:30:45.9"), Some(time(15, 30, 45)));
+        assert_eq!(parse_time("오후 3:30"), Some(time(15, 30, 0)));
+        assert_eq!(parse_time("3:30 pm"), Some(time(15, 30, 0)));
+        assert_eq!(parse_time("12:05 AM"), Some(time(0, 5, 0)));
+        assert_eq!(parse_time("오전 12시"), Some(time(0, 0, 0)));
+        assert_eq!(parse_time("15시 30분"), Some(time(15, 30, 0)));
+        assert_eq!(parse_time("오후 13:00"), None);
+        assert_eq!(parse_time("15"), None);
+        assert_eq!(parse_time("25:00"), None);
+    }
+
+    #[test]
+    fn parse_datetime_formats() {
+        let kst = FixedOffset::east_opt(9 * 3600).unwrap();
+        let expected = date(2024, 1, 3)
+            .and_time(time(15, 30, 0))
+            .and_local_timezone(kst)
+            .unwrap();
+        assert_eq!(parse_datetime("2024-01-03T15:30:00+09:00"), Some(expected));
+        assert_eq!(parse_datetime("2024-01-03 15:30 +0900"), Some(expected));
+        assert_eq!(parse_datetime("2024.1.3 오후 3:30 KST"), Some(expected));
+        assert_eq!(
+            parse_datetime("2024-01-03T06:30:00Z"),
+            Some(expected.with_timezone(&FixedOffset::east_opt(0).unwrap()))
+        );
+        // 시간대가 없으면 이 컴퓨터의 시간대, 시간이 없으면 자정
+        let local = parse_datetime("2024년 1월 3일").unwrap();
+        assert_eq!(
+            local.naive_local(),
+            date(2024, 1, 3).and_time(NaiveTime::MIN)
+        );
+        assert_eq!(parse_datetime("2024-01-03 25:00"), None);
+        assert_eq!(parse_datetime("3:30 PM"), None);
+    }
+
+    #[test]
+    fn matches_filter_conditions() {
+        let date_type = FieldType::Date;
+        assert_eq!(matches_filter(&date_type, "2024-01-03", "2024"), Some(true));
+        assert_eq!(
+            matches_filter(&date_type, "2024-01-03", "2024-01"),
+            Some(true)
+        );
+        assert_eq!(
+            matches_filter(&date_type, "2024-01-03", "2024년 2월"),
+            Some(false)
+        );
+        assert_eq!(
+            matches_filter(&date_type, "2024-01-03", "2024.1.3"),
+            Some(true)
+        );
+        assert_eq!(
+            matches_filter(&date_type, "2024-01-03", ">= 2024-01-03"),
+            Some(true)
+        );
+        assert_eq!(
+            matches_filter(&date_type, "2024-01-03", "> 2024-01-03"),
+            Some(false)
+        );
+        assert_eq!(
+            matches_filter(&date_type, "2024-01-31", "<= 2024-01"),
+            Some(true)
+        );
+        assert_eq!(
+            matches_filter(&date_type, "2024-02-01", "<= 2024-01"),
+            Some(false)
+        );
+        assert_eq!(
+            matches_filter(&date_type, "2023-12-31", "< 2024"),
+            Some(true)
+        );
+        // 읽을 수 없는 값은 조건에 맞지 않는다
+        assert_eq!(matches_filter(&date_type, "언젠가", "2024"), Some(false));
+        // 날짜 조건이 아니거나 날짜 필드가 아니면 글자 검색으로
+        assert_eq!(matches_filter(&date_type, "2024-01-03", "메모"), None);
+        assert_eq!(matches_filter(&FieldType::Text, "2024-01-03", "2024"), None);
+
+        let datetime_type = FieldType::DateTime;
+        assert_eq!(
+            matches_filter(&datetime_type, "2024-01-03T23:30:00+09:00", "2024-01-03"),
+            Some(true)
+        );
+        let time_type = FieldType::Time;
+        assert_eq!(
+            matches_filter(&time_type, "15:30:00", ">= 오후 3시"),
+            Some(true)
+        );
+        assert_eq!(
+            matches_filter(&time_type, "15:30:00", "< 15:00"),
+            Some(false)
+        );
+    }
+}
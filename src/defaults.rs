@@ -0,0 +1,213 @@
+// 새 행의 기본값: 고정 값, 오늘 날짜, 현재 사용자, 일련번호 (예: INV-{YYYY}-{0000})
+use crate::csv_import::empty_row;
+use crate::dates;
+use crate::safe_file::atomic_write;
+use crate::schema::convert_field_value;
+use crate::workspace::Workspace;
+use crate::{CustomStructure, ERPApp, Field, FieldType, Row};
+use chrono::{Datelike, Local, NaiveDate};
+use eframe::egui;
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+use std::error::Error;
+use std::fs;
+
+#[derive(Clone, Default, Serialize, Deserialize, PartialEq, Debug)]
+pub(crate) enum DefaultValue {
+    #[default]
+    None,
+    Literal(String),
+    Today,
+    CurrentUser,
+    Sequence(String), // 번호 형식
+}
+
+impl DefaultValue {
+    pub(crate) fn is_none(&self) -> bool {
+        *self == DefaultValue::None
+    }
+
+    fn label(&self) -> &'static str {
+        match self {
+            DefaultValue::None => "없음",
+            DefaultValue::Literal(_) => "고정 값",
+            DefaultValue::Today => "오늘 / 지금",
+            DefaultValue::CurrentUser => "현재 사용자",
+            DefaultValue::Sequence(_) => "일련번호",
+        }
+    }
+}
+
+const DEFAULT_SEQUENCE_FORMAT: &str = "{0000}";
+
+// 형식의 {YYYY} {YY} {MM} {DD}는 날짜로, {0000}은 자릿수를 맞춘 번호로, {#}은 번호 그대로.
+// 번호 자리가 없으면 끝에 번호를 붙인다
+pub(crate) fn format_sequence(format: &str, number: u64, date: NaiveDate) -> String {
+    let mut result = String::new();
+    let mut has_number = false;
+    let mut rest = format;
+    while let Some(start) = rest.find('{') {
+        let Some(len) = rest[start..].find('}') else {
+            break;
+        };
+        result.push_str(&rest[..start]);
+        let token = &rest[start + 1..start + len];
+        match token {
+            "YYYY" => result.push_str(&format!("{:04}", date.year())),
+            "YY" => result.push_str(&format!("{:02}", date.year() % 100)),
+            "MM" => result.push_str(&format!("{:02}", date.month())),
+            "DD" => result.push_str(&format!("{:02}", date.day())),
+            "#" => {
+                result.push_str(&number.to_string());
+                has_number = true;
+            }
+            zeros if !zeros.is_empty() && zeros.chars().all(|c| c == '0') => {
+                result.push_str(&format!("{:0width$}", number, width = zeros.len()));
+                has_number = true;
+            }
+            _ => result.push_str(&rest[start..=start + len]),
+        }
+        rest = &rest[start + len + 1..];
+    }
+    result.push_str(rest);
+    if !has_number {
+        result.push_str(&number.to_string());
+    }
+    result
+}
+
+// 워크스페이스의 sequences.json: "구조체 ID/필드 ID" -> 마지막으로 쓴 번호.
+// 행을 지워도 줄지 않으므로 같은 번호가 다시 나오지 않는다
+fn next_sequence(
+    workspace: &Workspace,
+    structure_id: &str,
+    field_id: &str,
+) -> Result<u64, Box<dyn Error>> {
+    let path = workspace.sequences_path();
+    let mut sequences: HashMap<String, u64> = match fs::read_to_string(&path) {
+        Ok(data) => serde_json::from_str(&data)?,
+        Err(e) if e.kind() == std::io::ErrorKind::NotFound => HashMap::new(),
+        Err(e) => return Err(e.into()),
+    };
+    let number = sequences
+        .entry(format!("{}/{}", structure_id, field_id))
+        .or_insert(0);
+    *number += 1;
+    let number = *number;
+    atomic_write(&path, serde_json::to_string_pretty(&sequences)?.as_bytes())?;
+    Ok(number)
+}
+
+// 설정에 이름이 없으면 OS 사용자 이름
+pub(crate) fn system_user_name() -> String {
+    std::env::var("USER")
+        .or_else(|_| std::env::var("USERNAME"))
+        .unwrap_or_default()
+}
+
+// 행마다 달라지지 않는 기본값 (고정 값, 오늘, 현재 사용자). 일련번호와 기본값 없음은 None
+pub(crate) fn static_default(field: &Field, user_name: &str) -> Option<String> {
+    match &field.default {
+        DefaultValue::None | DefaultValue::Sequence(_) => None,
+        DefaultValue::Literal(value) => Some(convert_field_value(value, field).unwrap_or_default()),
+        DefaultValue::Today => Some(dates::now_value(&field.field_type)),
+        DefaultValue::CurrentUser => Some(user_name.to_string()),
+    }
+}
+
+// 구조체 편집기의 필드별 기본값 선택
+pub(crate) fn default_editor(ui: &mut egui::Ui, idx: usize, field: &mut Field) {
+    // 하위 표는 줄 항목 편집기에서, 첨부 파일은 파일 선택으로, 수식/집계/조회는 계산으로만 채운다.
+    // 식별 번호는 행마다 달라야 한다
+    if matches!(
+        field.field_type,
+        FieldType::Table
+            | FieldType::Attachment
+            | FieldType::ResidentNumber
+            | FieldType::BusinessNumber
+            | FieldType::Phone
+            | FieldType::Formula
+            | FieldType::Rollup
+            | FieldType::Lookup
+    ) {
+        return;
+    }
+    ui.horizontal(|ui| {
+        ui.label("기본값:");
+        let mut choices = vec![
+            DefaultValue::None,
+            DefaultValue::Literal(String::new()),
+            DefaultValue::CurrentUser,
+            DefaultValue::Sequence(DEFAULT_SEQUENCE_FORMAT.to_string()),
+        ];
+        if dates::is_temporal(&field.field_type) {
+            choices.insert(2, DefaultValue::Today);
+        }
+        egui::ComboBox::from_id_salt(("field_default", idx))
+            .selected_text(field.default.label())
+            .show_ui(ui, |ui| {
+                for choice in choices {
+                    let selected =
+                        std::mem::discriminant(&choice) == std::mem::discriminant(&field.default);
+                    let label = choice.label();
+                    if ui.selectable_label(selected, label).clicked() && !selected {
+                        field.default = choice;
+                    }
+                }
+            });
+        match &mut field.default {
+            DefaultValue::Literal(value) => {
+                ui.text_edit_singleline(value);
+            }
+            DefaultValue::Sequence(format) => {
+                ui.add(egui::TextEdit::singleline(format).desired_width(160.0));
+                ui.label(format!(
+                    "예: {}",
+                    format_sequence(format, 1, Local::now().date_naive())
+                ));
+            }
+            _ => {}
+        }
+    });
+}
+
+impl ERPApp {
+    // 설정에 적은 사용자 이름, 없으면 OS 사용자 이름
+    pub(crate) fn current_user_name(&self) -> String {
+        match self.settings.user_name.trim() {
+            "" => system_user_name(),
+            name => name.to_string(),
+        }
+    }
+
+    // 기본값을 채운 새 행. 일련번호는 이때 워크스페이스에 기록된다
+    pub(crate) fn new_row(&self, structure: &CustomStructure) -> Row {
+        let mut row = empty_row(structure);
+        let today = Local::now().date_naive();
+        let user_name = self.current_user_name();
+        for field in &structure.fields {
+            let value = match &field.default {
+                DefaultValue::Sequence(format) => {
+                    let Some(workspace) = &self.workspace else {
+                        continue;
+                    };
+                    match next_sequence(workspace, &structure.id, &field.id) {
+                        Ok(number) => format_sequence(format, number, today),
+                        Err(e) => {
+                            eprintln!("일련번호를 기록할 수 없습니다: {}", e);
+                            continue;
+                        }
+                    }
+                }
+                _ => match static_default(field, &user_name) {
+                    Some(value) => value,
+                    None => continue,
+                },
+            };
+            if let Some(field_value) = row.values.get_mut(&field.id) {
+                field_value.value = value;
+            }
+        }
+        row
+    }
+}
@@ -0,0 +1,371 @@
+// Excel 내보내기: 날짜 셀, 머리글 서식, 열 너비, 천 단위 구분, 참/거짓 표시
+use crate::category_workbook::{sheet_base_name, unique_sheet_name};
+use crate::currency::{self, parse_decimal};
+use crate::dates;
+use crate::formula;
+use crate::korean_id;
+use crate::line_items::split_line_tables;
+use crate::{CustomStructure, ERPApp, FieldType, Row};
+use chrono::{Datelike, NaiveDate, NaiveDateTime, Timelike};
+use eframe::egui;
+use rust_decimal::prelude::ToPrimitive;
+use serde::{Deserialize, Serialize};
+use xlsxwriter::format::{FormatAlignment, FormatBorder};
+use xlsxwriter::worksheet::DateTime;
+use xlsxwriter::{Format, Workbook, Worksheet, XlsxError};
+
+#[derive(Clone, Copy, Default, Serialize, Deserialize, PartialEq, Debug)]
+pub(crate) enum BooleanStyle {
+    #[default]
+    TrueFalse, // Excel 논리값 TRUE/FALSE
+    CheckMark, // ✓/✗ 문자
+}
+
+#[derive(Clone, Serialize, Deserialize, PartialEq, Debug)]
+pub(crate) struct ExcelExportOptions {
+    #[serde(default = "default_date_format")]
+    pub(crate) date_format: String, // Excel 표시 형식 (예: yyyy-mm-dd, yyyy"년" mm"월" dd"일")
+    #[serde(default)]
+    pub(crate) boolean_style: BooleanStyle,
+    #[serde(default)]
+    pub(crate) formulas_as_excel: bool, // 수식 필드를 값 대신 Excel 수식으로
+    #[serde(default)]
+    pub(crate) convert_to_base: bool, // 거래 통화 금액을 거래일 환율로 원화 환산
+    #[serde(default)]
+    pub(crate) reveal_identifiers: bool, // 주민등록번호 등을 가리지 않고 내보내기
+}
+
+fn default_date_format() -> String {
+    "yyyy-mm-dd".to_string()
+}
+
+impl Default for ExcelExportOptions {
+    fn default() -> Self {
+        ExcelExportOptions {
+            date_format: default_date_format(),
+            boolean_style: BooleanStyle::default(),
+            formulas_as_excel: false,
+            convert_to_base: false,
+            reveal_identifiers: false,
+        }
+    }
+}
+
+// Excel 셀에는 시간대가 없으므로 날짜와 시간은 값이 가진 시간대의 시각으로 쓴다
+fn parse_datetime(field_type: &FieldType, value: &str) -> Option<NaiveDateTime> {
+    let value = value.trim();
+    if *field_type == FieldType::DateTime {
+        return dates::parse_datetime(value).map(|d| d.naive_local());
+    }
+    NaiveDateTime::parse_from_str(value, "%Y-%m-%d %H:%M:%S")
+        .ok()
+        .or_else(|| {
+            dates::parse_date(value)
+                .or_else(|| NaiveDate::parse_from_str(value, "%Y-%m-%d").ok())
+                .and_then(|d| d.and_hms_opt(0, 0, 0))
+        })
+}
+
+fn excel_datetime(datetime: NaiveDateTime) -> DateTime {
+    DateTime {
+        year: datetime.year() as i16,
+        month: datetime.month() as i8,
+        day: datetime.day() as i8,
+        hour: datetime.hour() as i8,
+        min: datetime.minute() as i8,
+        second: datetime.second() as f64,
+    }
+}
+
+fn parse_number(value: &str) -> Option<f64> {
+    value.trim().replace(',', "").parse::<f64>().ok()
+}
+
+// 화면 폭 기준 글자 수 (한글 등 전각 문자는 2칸)
+fn display_width(text: &str) -> usize {
+    text.chars()
+        .map(|c| if (c as u32) >= 0x1100 { 2 } else { 1 })
+        .sum()
+}
+
+// 숫자 열의 소수 자릿수에 맞춘 천 단위 구분 형식
+fn number_format(values: &[&str]) -> String {
+    let decimals = values
+        .iter()
+        .filter(|v| parse_number(v).is_some())
+        .filter_map(|v| v.trim().split_once('.').map(|(_, frac)| frac.len()))
+        .max()
+        .unwrap_or(0)
+        .min(10);
+    if decimals == 0 {
+        "#,##0".to_string()
+    } else {
+        format!("#,##0.{}", "0".repeat(decimals))
+    }
+}
+
+fn field_value<'a>(row: &'a Row, field_id: &str) -> &'a str {
+    row.values
+        .get(field_id)
+        .map(|v| v.value.as_str())
+        .unwrap_or_default()
+}
+
+// 구조체 하나를 시트에 기록 (단일 내보내기와 카테고리 통합 내보내기에서 공용)
+pub(crate) fn write_structure_sheet(
+    sheet: &mut Worksheet,
+    structure: &CustomStructure,
+    rows: &[Row],
+    options: &ExcelExportOptions,
+) -> Result<(), XlsxError> {
+    let masked;
+    let rows = if options.reveal_identifiers {
+        rows
+    } else {
+        masked = korean_id::mask_rows(structure, rows);
+        &masked
+    };
+    let mut header_format = Format::new();
+    header_format
+        .set_bold()
+        .set_border_bottom(FormatBorder::Thin);
+    let mut check_format = Format::new();
+    check_format.set_align(FormatAlignment::Center);
+
+    for (col, field) in structure.fields.iter().enumerate() {
+        let col = col as u16;
+        sheet.write_string(0, col, &field.name, Some(&header_format))?;
+
+        let values: Vec<&str> = rows.iter().map(|r| field_value(r, &field.id)).collect();
+        // 필드에 표시 형식을 정했으면 그 형식, 아니면 내보내기 설정의 날짜 형식
+        let date_pattern = field
+            .date_format
+            .excel_format()
+            .unwrap_or(&options.date_format);
+        let date_pattern = match field.field_type {
+            FieldType::DateTime => format!("{} hh:mm", date_pattern),
+            FieldType::Time => "hh:mm".to_string(),
+            _ => date_pattern.to_string(),
+        };
+        let mut date_format = Format::new();
+        date_format.set_num_format(&date_pattern);
+        let mut number_format_cell = Format::new();
+        number_format_cell.set_num_format(&number_format(&values));
+
+        // Excel 수식으로 쓸 때 시트의 열로 옮긴 식
+        let excel_formula = |row: u32| {
+            let expr = formula::parse(&field.formula).ok()?;
+            let column = |name: &str| structure.fields.iter().position(|f| f.name == name);
+            formula::to_excel(&expr, &column, row + 1).map(|f| format!("={}", f))
+        };
+        let as_formula = options.formulas_as_excel && field.field_type == FieldType::Formula;
+
+        let mut width = display_width(&field.name);
+        for (row_idx, value) in values.iter().enumerate() {
+            let row = row_idx as u32 + 1;
+            if value.is_empty() && !as_formula {
+                continue;
+            }
+            match field.field_type {
+                // 계산해 둔 값을 결과로 함께 기록해 다시 계산 전에도 보인다
+                FieldType::Formula => {
+                    width = width.max(display_width(value));
+                    match (
+                        as_formula.then(|| excel_formula(row)).flatten(),
+                        parse_number(value),
+                    ) {
+                        (Some(excel), Some(num)) => {
+                            sheet.write_formula_num(
+                                row,
+                                col,
+                                &excel,
+                                Some(&number_format_cell),
+                                num,
+                            )?;
+                        }
+                        (Some(excel), None) => {
+                            sheet.write_formula_str(row, col, &excel, None, value)?;
+                        }
+                        (None, Some(num)) => {
+                            sheet.write_number(row, col, num, Some(&number_format_cell))?;
+                        }
+                        (None, None) if !value.is_empty() => {
+                            sheet.write_string(row, col, value, None)?;
+                        }
+                        (None, None) => {}
+                    }
+                }
+                FieldType::Number => match parse_number(value) {
+                    Some(num) => {
+                        sheet.write_number(row, col, num, Some(&number_format_cell))?;
+                        // 구분 기호가 들어간 표시 길이
+                        width = width.max(display_width(value) + display_width(value) / 3);
+                    }
+                    None => {
+                        sheet.write_string(row, col, value, None)?;
+                        width = width.max(display_width(value));
+                    }
+                },
+                FieldType::Currency => match parse_decimal(value).and_then(|n| n.to_f64()) {
+                    // 거래 통화 필드는 값마다 통화가 다르다
+                    Some(num) => {
+                        let currency = currency::amount_currency(field, value);
+                        let mut currency_format = Format::new();
+                        currency_format.set_num_format(&currency::excel_format(&currency));
+                        sheet.write_number(row, col, num, Some(&currency_format))?;
+                        width = width.max(display_width(&currency::display(&currency, value)));
+                    }
+                    None => {
+                        sheet.write_string(row, col, value, None)?;
+                        width = width.max(display_width(value));
+                    }
+                },
+                // 하루 중 비율로 기록해 Excel에서 시간으로 정렬/필터된다
+                FieldType::Time => match dates::parse_time(value) {
+                    Some(time) => {
+                        let fraction = time.num_seconds_from_midnight() as f64 / 86_400.0;
+                        sheet.write_number(row, col, fraction, Some(&date_format))?;
+                        width = width.max(display_width(&date_pattern));
+                    }
+                    None => {
+                        sheet.write_string(row, col, value, None)?;
+                        width = width.max(display_width(value));
+                    }
+                },
+                FieldType::Date | FieldType::DateTime => {
+                    match parse_datetime(&field.field_type, value) {
+                        Some(datetime) => {
+                            sheet.write_datetime(
+                                row,
+                                col,
+                                &excel_datetime(datetime),
+                                Some(&date_format),
+                            )?;
+                            width = width.max(display_width(&date_pattern));
+                        }
+                        None => {
+                            sheet.write_string(row, col, value, None)?;
+                            width = width.max(display_width(value));
+                        }
+                    }
+                }
+                FieldType::Boolean => match (value.parse::<bool>(), options.boolean_style) {
+                    (Ok(b), BooleanStyle::TrueFalse) => {
+                        sheet.write_boolean(row, col, b, None)?;
+                        width = width.max(5);
+                    }
+                    (Ok(b), BooleanStyle::CheckMark) => {
+                        sheet.write_string(
+                            row,
+                            col,
+                            if b { "✓" } else { "✗" },
+                            Some(&check_format),
+                        )?;
+                    }
+                    (Err(_), _) => {
+                        sheet.write_string(row, col, value, None)?;
+                        width = width.max(display_width(value));
+                    }
+                },
+                _ => {
+                    sheet.write_string(row, col, value, None)?;
+                    width = width.max(display_width(value));
+                }
+            }
+        }
+        sheet.set_column(col, col, (width + 2).clamp(8, 60) as f64, None)?;
+    }
+
+    // 머리글 고정과 자동 필터
+    sheet.freeze_panes(1, 0);
+    if !structure.fields.is_empty() {
+        sheet.autofilter(0, 0, rows.len() as u32, structure.fields.len() as u16 - 1)?;
+    }
+    Ok(())
+}
+
+// 설정 화면의 Excel 내보내기 옵션. 바뀌면 true
+pub(crate) fn options_editor(ui: &mut egui::Ui, options: &mut ExcelExportOptions) -> bool {
+    let mut changed = false;
+    ui.horizontal(|ui| {
+        ui.label("Excel 날짜 형식:");
+        changed |= ui
+            .add(egui::TextEdit::singleline(&mut options.date_format).desired_width(160.0))
+            .changed();
+        ui.label("참/거짓 표시:");
+        changed |= ui
+            .radio_value(
+                &mut options.boolean_style,
+                BooleanStyle::TrueFalse,
+                "TRUE/FALSE",
+            )
+            .changed();
+        changed |= ui
+            .radio_value(&mut options.boolean_style, BooleanStyle::CheckMark, "✓/✗")
+            .changed();
+        changed |= ui
+            .checkbox(&mut options.formulas_as_excel, "수식 필드를 Excel 수식으로")
+            .changed();
+        changed |= ui
+            .checkbox(&mut options.convert_to_base, "거래 통화 금액을 원화로 환산")
+            .changed();
+        changed |= ui
+            .checkbox(
+                &mut options.reveal_identifiers,
+                "주민등록번호 등을 가리지 않고 내보내기",
+            )
+            .on_hover_text("끄면 900101-1****** 처럼 뒷자리를 가립니다")
+            .changed();
+    });
+    changed
+}
+
+impl ERPApp {
+    // Excel 내보내기 (파일 선택 대화상자 사용)
+    pub(crate) fn export_to_excel(
+        &self,
+        structure: &CustomStructure,
+    ) -> Result<(), Box<dyn std::error::Error>> {
+        if let Some(path) = rfd::FileDialog::new()
+            .add_filter("Excel Files", &["xlsx"])
+            .set_file_name(&format!("{}.xlsx", structure.name))
+            .save_file()
+        {
+            let workbook = Workbook::new(&path.to_string_lossy())?;
+            let mut sheet = workbook.add_worksheet(None)?;
+            let rows = self
+                .erp_data
+                .data
+                .get(&structure.id)
+                .map(|rows| self.display_rows(structure, rows))
+                .unwrap_or_default();
+            let options = &self.settings.excel_export;
+            let (converted, rows) = if options.convert_to_base {
+                self.base_currency_rows(structure, &rows)
+            } else {
+                (structure.clone(), rows)
+            };
+            let structure = &converted;
+            match split_line_tables(structure, &rows) {
+                None => write_structure_sheet(&mut sheet, structure, &rows, options)?,
+                // 하위 표마다 상위 ID 열이 붙은 시트를 하나씩
+                Some((main, main_rows, tables)) => {
+                    write_structure_sheet(&mut sheet, &main, &main_rows, options)?;
+                    let mut used = vec!["Sheet1".to_string()];
+                    for table in tables {
+                        let name = unique_sheet_name(
+                            &sheet_base_name(&structure.name, &table.field_name),
+                            &used,
+                        );
+                        let mut sheet = workbook.add_worksheet(Some(&name))?;
+                        write_structure_sheet(&mut sheet, &table.structure, &table.rows, options)?;
+                        used.push(name);
+                    }
+                }
+            }
+            workbook.close()?;
+            println!("Excel 파일 저장 완료!");
+        }
+        Ok(())
+    }
+}
@@ -0,0 +1,547 @@
+// Excel 가져오기: 시트 선택, 헤더 매칭, 날짜 변환, 교체/추가/키 기준 갱신 미리보기
+use crate::csv_import::{empty_row, match_headers, render_import_errors, ImportError};
+use crate::dates;
+use crate::reference::{resolve_with, ReferenceOptions};
+use crate::schema::{convert_field_value, convert_value};
+use crate::validation::{accept_rows, rejected_to_errors, render_violations, RowValidator};
+use crate::{CustomStructure, ERPApp, FieldType, FieldValue, Row};
+use calamine::{open_workbook, DataType, Reader, Xlsx};
+use chrono::{Duration, NaiveDate, NaiveDateTime};
+use eframe::egui;
+use egui::{Context, ScrollArea};
+use rfd::FileDialog;
+use std::error::Error;
+use std::path::{Path, PathBuf};
+
+// Excel 일련번호(1900 날짜 체계)를 날짜/시각으로.
+// Excel은 1900-02-29가 있다고 보므로 60 이하는 하루를 보정
+pub(crate) fn serial_to_datetime(serial: f64) -> Option<NaiveDateTime> {
+    if !(0.0..2_958_466.0).contains(&serial) {
+        return None;
+    }
+    let base = if serial < 60.0 {
+        NaiveDate::from_ymd_opt(1899, 12, 31)?
+    } else {
+        NaiveDate::from_ymd_opt(1899, 12, 30)?
+    };
+    let days = serial.trunc() as i64;
+    let seconds = ((serial.fract() * 86_400.0).round()) as i64;
+    base.and_hms_opt(0, 0, 0)
+        .map(|start| start + Duration::days(days) + Duration::seconds(seconds))
+}
+
+fn format_datetime(datetime: NaiveDateTime, field_type: Option<&FieldType>) -> String {
+    let has_time = datetime.time() != chrono::NaiveTime::MIN;
+    match field_type {
+        // Excel 셀에는 시간대가 없으므로 이 컴퓨터의 시간대로 본다
+        Some(FieldType::DateTime) => dates::local_datetime(datetime)
+            .map(|d| d.format("%Y-%m-%dT%H:%M:%S%:z").to_string())
+            .unwrap_or_default(),
+        Some(FieldType::Time) => datetime.format("%H:%M:%S").to_string(),
+        Some(FieldType::Date) => datetime.format("%Y-%m-%d").to_string(),
+        _ if !has_time => datetime.format("%Y-%m-%d").to_string(),
+        _ => datetime.format("%Y-%m-%d %H:%M:%S").to_string(),
+    }
+}
+
+// 셀 값을 필드 타입에 맞는 문자열로. 오류 셀(#DIV/0! 등)은 Err
+pub(crate) fn cell_to_string(
+    cell: &DataType,
+    field_type: Option<&FieldType>,
+) -> Result<String, String> {
+    Ok(match cell {
+        DataType::Int(i) => i.to_string(),
+        DataType::Float(f) => {
+            // 서식 없이 일련번호만 들어 있는 날짜/시간 열
+            if field_type.is_some_and(dates::is_temporal) {
+                if let Some(datetime) = serial_to_datetime(*f) {
+                    return Ok(format_datetime(datetime, field_type));
+                }
+            }
+            if f.fract() == 0.0 && f.abs() < 1e15 {
+                format!("{}", *f as i64)
+            } else {
+                f.to_string()
+            }
+        }
+        DataType::String(s) => s.clone(),
+        DataType::Bool(b) => b.to_string(),
+        DataType::DateTime(serial) => match serial_to_datetime(*serial) {
+            Some(datetime) => format_datetime(datetime, field_type),
+            None => return Err(format!("날짜 범위를 벗어났습니다: {}", serial)),
+        },
+        DataType::DateTimeIso(s) => match field_type {
+            Some(t) if dates::is_temporal(t) => convert_value(s, t).unwrap_or_else(|_| s.clone()),
+            _ => s.replace('T', " "),
+        },
+        DataType::Duration(d) => d.to_string(),
+        DataType::DurationIso(s) => s.clone(),
+        DataType::Error(e) => return Err(format!("Excel 오류 셀 {}", e)),
+        DataType::Empty => String::new(),
+    })
+}
+
+#[derive(Clone, Copy, PartialEq, Debug)]
+pub(crate) enum ImportMode {
+    Replace, // 기존 행을 모두 지우고 교체
+    Append,  // 기존 행 뒤에 추가
+    Upsert,  // 키 필드 값이 같은 행은 갱신, 없으면 추가
+}
+
+impl ImportMode {
+    fn label(&self) -> &'static str {
+        match self {
+            ImportMode::Replace => "교체",
+            ImportMode::Append => "추가",
+            ImportMode::Upsert => "키 기준 갱신",
+        }
+    }
+}
+
+#[derive(Clone, Default)]
+pub(crate) struct ImportPreview {
+    pub(crate) added: usize,
+    pub(crate) updated: usize,
+    pub(crate) unchanged: usize,
+    pub(crate) removed: usize,
+    pub(crate) errors: Vec<ImportError>,
+    pub(crate) violations: Vec<ImportError>, // 제약 조건 때문에 제외한 행
+    pub(crate) rows: Vec<Row>,               // 적용하면 저장될 전체 행
+}
+
+// 헤더 아래 데이터 행의 셀
+pub(crate) type SheetCells = Vec<Vec<DataType>>;
+
+pub(crate) struct ExcelImport {
+    pub(crate) structure_id: String,
+    pub(crate) path: PathBuf,
+    pub(crate) sheets: Vec<String>,
+    pub(crate) sheet: usize,
+    pub(crate) headers: Vec<String>,
+    pub(crate) cells: SheetCells,
+    pub(crate) mapping: Vec<Option<String>>, // 열 -> 필드 ID
+    pub(crate) mode: ImportMode,
+    pub(crate) key_field: Option<String>,
+    pub(crate) preview: ImportPreview,
+}
+
+pub(crate) fn read_sheet(
+    path: &Path,
+    sheet: &str,
+) -> Result<(Vec<String>, SheetCells), Box<dyn Error>> {
+    let mut workbook: Xlsx<_> = open_workbook(path)?;
+    let range = match workbook.worksheet_range(sheet) {
+        Some(Ok(range)) => range,
+        Some(Err(e)) => return Err(e.into()),
+        None => return Err(format!("시트를 찾을 수 없습니다: {}", sheet).into()),
+    };
+    let mut rows = range.rows();
+    let headers = rows
+        .next()
+        .map(|row| {
+            row.iter()
+                .map(|cell| {
+                    cell_to_string(cell, None)
+                        .unwrap_or_default()
+                        .trim()
+                        .to_string()
+                })
+                .collect()
+        })
+        .unwrap_or_default();
+    let cells = rows
+        .filter(|row| row.iter().any(|cell| !cell.is_empty()))
+        .map(|row| row.to_vec())
+        .collect();
+    Ok((headers, cells))
+}
+
+// 매핑대로 변환한 행 (필드 ID -> 값만 채운 행)과 변환 오류.
+// 변환하지 못한 셀은 행에 넣지 않아, 키 기준 갱신에서 기존 값을 덮어쓰지 않는다
+pub(crate) fn convert_cells(
+    structure: &CustomStructure,
+    headers: &[String],
+    cells: &SheetCells,
+    mapping: &[Option<String>],
+) -> (Vec<Row>, Vec<ImportError>) {
+    let mut errors = Vec::new();
+    let mut rows = Vec::new();
+    for (row_idx, record) in cells.iter().enumerate() {
+        let mut row = Row::new();
+        for (col, field_id) in mapping.iter().enumerate() {
+            let Some(field) = field_id
+                .as_ref()
+                .and_then(|id| structure.fields.iter().find(|f| &f.id == id))
+            else {
+                continue;
+            };
+            let cell = record.get(col).unwrap_or(&DataType::Empty);
+            let value = cell_to_string(cell, Some(&field.field_type))
+                .and_then(|value| convert_field_value(&value, field));
+            let value = match value {
+                Ok(value) => value,
+                Err(error) => {
+                    errors.push(ImportError {
+                        row: row_idx + 1,
+                        column: headers.get(col).cloned().unwrap_or_default(),
+                        value: cell.to_string(),
+                        error,
+                    });
+                    continue;
+                }
+            };
+            row.values.insert(
+                field.id.clone(),
+                FieldValue {
+                    value,
+                    field_type: field.field_type.clone(),
+                },
+            );
+        }
+        rows.push(row);
+    }
+    (rows, errors)
+}
+
+impl ExcelImport {
+    fn open(structure: &CustomStructure, path: PathBuf) -> Result<Self, Box<dyn Error>> {
+        let workbook: Xlsx<_> = open_workbook(&path)?;
+        let sheets = workbook.sheet_names().to_vec();
+        if sheets.is_empty() {
+            return Err("시트가 비어있습니다".into());
+        }
+        let mut import = ExcelImport {
+            structure_id: structure.id.clone(),
+            path,
+            sheets,
+            sheet: 0,
+            headers: Vec::new(),
+            cells: Vec::new(),
+            mapping: Vec::new(),
+            mode: ImportMode::Replace,
+            key_field: structure.fields.first().map(|f| f.id.clone()),
+            preview: ImportPreview::default(),
+        };
+        import.load_sheet(structure)?;
+        Ok(import)
+    }
+
+    fn load_sheet(&mut self, structure: &CustomStructure) -> Result<(), Box<dyn Error>> {
+        let (headers, cells) = read_sheet(&self.path, &self.sheets[self.sheet])?;
+        self.mapping = match_headers(&headers, structure);
+        self.headers = headers;
+        self.cells = cells;
+        Ok(())
+    }
+
+    // 선택한 방식으로 적용했을 때의 결과 계산
+    fn update_preview(
+        &mut self,
+        structure: &CustomStructure,
+        existing: &[Row],
+        references: &ReferenceOptions,
+    ) {
+        let (mut imported, mut errors) =
+            convert_cells(structure, &self.headers, &self.cells, &self.mapping);
+        // 찾을 수 없는 참조도 변환하지 못한 셀처럼 행에서 뺀다
+        for error in resolve_with(structure, references, &mut imported) {
+            if let Some(field) = structure.fields.iter().find(|f| f.name == error.column) {
+                imported[error.row - 1].values.remove(&field.id);
+            }
+            errors.push(error);
+        }
+        let mut preview = ImportPreview {
+            errors,
+            ..Default::default()
+        };
+
+        // 가져온 행에 없는 필드는 빈 값으로
+        let complete = |row: Row| {
+            let mut full = empty_row(structure);
+            full.id = row.id;
+            full.values.extend(row.values);
+            full
+        };
+
+        match self.mode {
+            ImportMode::Replace => {
+                preview.removed = existing.len();
+                let (accepted, rejected) =
+                    accept_rows(structure, &[], imported.into_iter().map(complete).collect());
+                preview.added = accepted.len();
+                preview.rows = accepted;
+                preview.violations = rejected_to_errors(&rejected);
+            }
+            ImportMode::Append => {
+                let (accepted, rejected) = accept_rows(
+                    structure,
+                    existing,
+                    imported.into_iter().map(complete).collect(),
+                );
+                preview.added = accepted.len();
+                preview.rows = existing.to_vec();
+                preview.rows.extend(accepted);
+                preview.violations = rejected_to_errors(&rejected);
+            }
+            ImportMode::Upsert => {
+                let Some(key) = &self.key_field else {
+                    self.preview = preview;
+                    return;
+                };
+                preview.rows = existing.to_vec();
+                let mut validator = RowValidator::new(structure, existing);
+                let mut rejected = Vec::new();
+                for (idx, row) in imported.into_iter().enumerate() {
+                    let key_value = row
+                        .values
+                        .get(key)
+                        .map(|v| v.value.clone())
+                        .unwrap_or_default();
+                    let target = (!key_value.is_empty())
+                        .then(|| {
+                            preview.rows.iter_mut().find(|r| {
+                                r.values.get(key).map(|v| v.value.as_str())
+                                    == Some(key_value.as_str())
+                            })
+                        })
+                        .flatten();
+                    match target {
+                        Some(target) => {
+                            // 매핑된 필드 중 변환된 값만 덮어쓰고 나머지 값과 행 ID는 유지
+                            let mut merged = target.clone();
+                            merged.values.extend(row.values);
+                            if merged == *target {
+                                preview.unchanged += 1;
+                                continue;
+                            }
+                            // 자기 자신과의 고유성 충돌은 빼고 검사
+                            validator.remove(target);
+                            let violations = validator.check(&merged);
+                            if violations.is_empty() {
+                                validator.add(&merged);
+                                *target = merged;
+                                preview.updated += 1;
+                            } else {
+                                validator.add(target);
+                                rejected.push((idx, violations));
+                            }
+                        }
+                        None => {
+                            let row = complete(row);
+                            let violations = validator.check(&row);
+                            if violations.is_empty() {
+                                validator.add(&row);
+                                preview.added += 1;
+                                preview.rows.push(row);
+                            } else {
+                                rejected.push((idx, violations));
+                            }
+                        }
+                    }
+                }
+                preview.violations = rejected_to_errors(&rejected);
+            }
+        }
+        self.preview = preview;
+    }
+}
+
+impl ERPApp {
+    pub(crate) fn start_excel_import(&mut self, structure: &CustomStructure) {
+        let Some(path) = FileDialog::new()
+            .add_filter("Excel Files", &["xlsx"])
+            .pick_file()
+        else {
+            return;
+        };
+        match ExcelImport::open(structure, path) {
+            Ok(mut import) => {
+                let existing = self
+                    .erp_data
+                    .data
+                    .get(&structure.id)
+                    .cloned()
+                    .unwrap_or_default();
+                import.update_preview(structure, &existing, &self.reference_lookup(structure, &[]));
+                self.excel_import = Some(import);
+            }
+            Err(e) => eprintln!("Excel 불러오기 실패: {}", e),
+        }
+    }
+
+    pub(crate) fn render_excel_import(&mut self, ctx: &Context) {
+        let Some(structure_id) = self.excel_import.as_ref().map(|i| i.structure_id.clone()) else {
+            return;
+        };
+        let Some(structure) = self.find_structure(&structure_id) else {
+            self.excel_import = None;
+            return;
+        };
+        let existing = self
+            .erp_data
+            .data
+            .get(&structure_id)
+            .cloned()
+            .unwrap_or_default();
+        let references = self.reference_lookup(&structure, &[]);
+        let Some(import) = &mut self.excel_import else {
+            return;
+        };
+
+        let mut apply = false;
+        let mut cancel = false;
+        let mut sheet_changed = false;
+        let mut changed = false;
+
+        egui::Window::new("Excel 불러오기")
+            .collapsible(false)
+            .default_size([560.0, 520.0])
+            .show(ctx, |ui| {
+                ui.horizontal(|ui| {
+                    ui.label("시트:");
+                    let before = import.sheet;
+                    egui::ComboBox::from_id_salt("excel_sheet")
+                        .selected_text(import.sheets[import.sheet].clone())
+                        .show_ui(ui, |ui| {
+                            for (idx, name) in import.sheets.iter().enumerate() {
+                                ui.selectable_value(&mut import.sheet, idx, name);
+                            }
+                        });
+                    sheet_changed = import.sheet != before;
+                    ui.label(format!("{}행", import.cells.len()));
+                });
+                ui.separator();
+
+                ScrollArea::vertical()
+                    .id_salt("excel_import_columns")
+                    .max_height(200.0)
+                    .show(ui, |ui| {
+                        egui::Grid::new("excel_import_grid")
+                            .striped(true)
+                            .num_columns(2)
+                            .show(ui, |ui| {
+                                ui.strong("Excel 열");
+                                ui.strong("대상 필드");
+                                ui.end_row();
+                                for (col, header) in import.headers.iter().enumerate() {
+                                    let target = &mut import.mapping[col];
+                                    if target.is_some() {
+                                        ui.label(header);
+                                    } else {
+                                        ui.colored_label(
+                                            egui::Color32::from_rgb(200, 120, 0),
+                                            format!("⚠ {}", header),
+                                        );
+                                    }
+                                    let selected = target
+                                        .as_ref()
+                                        .and_then(|id| {
+                                            structure.fields.iter().find(|f| &f.id == id)
+                                        })
+                                        .map(|f| f.name.clone())
+                                        .unwrap_or_else(|| "건너뛰기".to_string());
+                                    let before = target.clone();
+                                    egui::ComboBox::from_id_salt(("excel_target", col))
+                                        .selected_text(selected)
+                                        .show_ui(ui, |ui| {
+                                            ui.selectable_value(target, None, "건너뛰기");
+                                            for field in &structure.fields {
+                                                ui.selectable_value(
+                                                    target,
+                                                    Some(field.id.clone()),
+                                                    &field.name,
+                                                );
+                                            }
+                                        });
+                                    changed |= *target != before;
+                                    ui.end_row();
+                                }
+                            });
+                    });
+
+                ui.separator();
+                ui.horizontal(|ui| {
+                    ui.label("방식:");
+                    for mode in [ImportMode::Replace, ImportMode::Append, ImportMode::Upsert] {
+                        changed |= ui
+                            .radio_value(&mut import.mode, mode, mode.label())
+                            .changed();
+                    }
+                });
+                if import.mode == ImportMode::Upsert {
+                    ui.horizontal(|ui| {
+                        ui.label("키 필드:");
+                        let selected = import
+                            .key_field
+                            .as_ref()
+                            .and_then(|id| structure.fields.iter().find(|f| &f.id == id))
+                            .map(|f| f.name.clone())
+                            .unwrap_or_default();
+                        let before = import.key_field.clone();
+                        egui::ComboBox::from_id_salt("excel_key_field")
+                            .selected_text(selected)
+                            .show_ui(ui, |ui| {
+                                for field in &structure.fields {
+                                    ui.selectable_value(
+                                        &mut import.key_field,
+                                        Some(field.id.clone()),
+                                        &field.name,
+                                    );
+                                }
+                            });
+                        changed |= import.key_field != before;
+                    });
+                }
+
+                ui.separator();
+                let preview = &import.preview;
+                ui.label(format!(
+                    "추가 {} / 갱신 {} / 변경 없음 {} / 삭제 {} → 적용 후 {}행",
+                    preview.added,
+                    preview.updated,
+                    preview.unchanged,
+                    preview.removed,
+                    preview.rows.len()
+                ));
+                render_import_errors(ui, "excel_import_errors", &preview.errors);
+                if import.mode == ImportMode::Upsert && !preview.errors.is_empty() {
+                    ui.label("갱신하는 행은 변환하지 못한 셀의 기존 값을 유지합니다.");
+                }
+                render_violations(ui, "excel_import_violations", &preview.violations);
+
+                ui.separator();
+                ui.horizontal(|ui| {
+                    if ui.button("✔ 적용").clicked() {
+                        apply = true;
+                    }
+                    if ui.button("❌ 취소").clicked() {
+                        cancel = true;
+                    }
+                });
+            });
+
+        if sheet_changed {
+            if let Err(e) = import.load_sheet(&structure) {
+                eprintln!("시트를 읽을 수 없습니다: {}", e);
+            }
+            changed = true;
+        }
+        if changed {
+            import.update_preview(&structure, &existing, &references);
+        }
+
+        if apply {
+            if let Some(import) = self.excel_import.take() {
+                // 데이터 저장 및 CSV 자동 백업
+                self.erp_data
+                    .data
+                    .insert(structure.id.clone(), import.preview.rows);
+                self.save_structure_rows(&structure.id);
+                self.save_to_csv(&structure.id);
+                println!("Excel 파일 불러오기 완료!");
+            }
+        } else if cancel {
+            self.excel_import = None;
+        }
+    }
+}
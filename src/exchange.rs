@@ -0,0 +1,622 @@
+// 환율표: 기본 제공 구조체에 날짜별 통화 1단위의 원화 값을 두고, 거래 통화 금액을 거래일 환율로 원화 환산
+use crate::csv_import::{read_csv_table, CsvTable};
+use crate::currency::{self, amount_currency, code_in, currency_of, parse_decimal, Currency};
+use crate::dates;
+use crate::validation::FieldConstraints;
+use crate::{
+    CustomCategory, CustomStructure, ERPApp, Field, FieldType, FieldValue, Row, SubCategory,
+};
+use chrono::{Local, NaiveDate};
+use rfd::FileDialog;
+use rust_decimal::Decimal;
+use std::collections::HashMap;
+
+pub(crate) const BASE_CURRENCY: &str = "KRW";
+pub(crate) const RATES_STRUCTURE_ID: &str = "builtin-exchange-rates";
+const DATE_FIELD_ID: &str = "builtin-exchange-rates-date";
+const CODE_FIELD_ID: &str = "builtin-exchange-rates-code";
+const RATE_FIELD_ID: &str = "builtin-exchange-rates-rate";
+const CATEGORY: &str = "기준 정보";
+const SUBCATEGORY: &str = "환율";
+
+// 환율표 필드: 날짜, 통화 코드, 1단위의 원화 값
+fn builtin_fields() -> Vec<Field> {
+    let field = |id: &str, name: &str, field_type: FieldType, constraints| Field {
+        id: id.to_string(),
+        name: name.to_string(),
+        field_type,
+        constraints,
+        ..Default::default()
+    };
+    vec![
+        field(
+            DATE_FIELD_ID,
+            "날짜",
+            FieldType::Date,
+            FieldConstraints {
+                required: true,
+                ..Default::default()
+            },
+        ),
+        field(
+            CODE_FIELD_ID,
+            "통화",
+            FieldType::Text,
+            FieldConstraints {
+                required: true,
+                pattern: Some("[A-Z]{3}".to_string()),
+                ..Default::default()
+            },
+        ),
+        field(
+            RATE_FIELD_ID,
+            "환율",
+            FieldType::Number,
+            FieldConstraints {
+                required: true,
+                min: Some(0.0),
+                ..Default::default()
+            },
+        ),
+    ]
+}
+
+fn field_value<'a>(row: &'a Row, field_id: &str) -> &'a str {
+    row.values
+        .get(field_id)
+        .map(|v| v.value.as_str())
+        .unwrap_or_default()
+}
+
+// CSV에서 읽은 환율 한 건: 날짜, 통화 코드, 1단위의 원화 값
+pub(crate) type DailyRate = (NaiveDate, String, Decimal);
+
+// 통화별 (날짜, 원화 값)을 날짜 순서로
+#[derive(Default)]
+pub(crate) struct ExchangeRates {
+    rates: HashMap<String, Vec<(NaiveDate, Decimal)>>,
+}
+
+impl ExchangeRates {
+    pub(crate) fn from_rows(rows: &[Row]) -> Self {
+        let mut rates: HashMap<String, Vec<(NaiveDate, Decimal)>> = HashMap::new();
+        for row in rows {
+            let date = dates::parse_date(field_value(row, DATE_FIELD_ID));
+            let code = field_value(row, CODE_FIELD_ID).trim().to_ascii_uppercase();
+            let rate = parse_decimal(field_value(row, RATE_FIELD_ID));
+            if let (Some(date), Some(rate)) = (date, rate) {
+                rates.entry(code).or_default().push((date, rate));
+            }
+        }
+        for list in rates.values_mut() {
+            list.sort_by_key(|(date, _)| *date);
+        }
+        ExchangeRates { rates }
+    }
+
+    // 그날 또는 그 전 가장 가까운 날의 환율 (주말·공휴일에는 직전 영업일)
+    pub(crate) fn rate_on(&self, code: &str, date: NaiveDate) -> Option<(NaiveDate, Decimal)> {
+        if code == BASE_CURRENCY {
+            return Some((date, Decimal::ONE));
+        }
+        let list = self.rates.get(code)?;
+        let index = list.partition_point(|(day, _)| *day <= date);
+        index.checked_sub(1).map(|i| list[i])
+    }
+
+    pub(crate) fn to_base(&self, amount: Decimal, code: &str, date: NaiveDate) -> Option<Decimal> {
+        let (_, rate) = self.rate_on(code, date)?;
+        amount.checked_mul(rate)
+    }
+}
+
+// 거래일: 통화 필드에 정한 날짜 필드, 없으면 구조체의 첫 날짜 필드. 날짜가 없는 행은 오늘
+pub(crate) fn transaction_date(structure: &CustomStructure, field: &Field, row: &Row) -> NaiveDate {
+    let currency = currency_of(field);
+    let date_field = structure
+        .fields
+        .iter()
+        .find(|f| f.id == currency.date_field_id)
+        .or_else(|| {
+            structure
+                .fields
+                .iter()
+                .find(|f| matches!(f.field_type, FieldType::Date | FieldType::DateTime))
+        });
+    date_field
+        .and_then(|f| {
+            let value = field_value(row, &f.id);
+            match f.field_type {
+                FieldType::DateTime => dates::parse_datetime(value).map(|d| d.date_naive()),
+                _ => dates::parse_date(value),
+            }
+        })
+        .unwrap_or_else(|| Local::now().date_naive())
+}
+
+// 행의 통화 필드 금액을 원화로. 통화가 없거나 그날 환율이 없으면 None
+pub(crate) fn base_amount(
+    rates: &ExchangeRates,
+    structure: &CustomStructure,
+    field: &Field,
+    row: &Row,
+) -> Option<Decimal> {
+    let value = field_value(row, &field.id);
+    let amount = parse_decimal(value)?;
+    let currency = amount_currency(field, value);
+    if currency.code.is_empty() {
+        return None;
+    }
+    rates.to_base(
+        amount,
+        &currency.code,
+        transaction_date(structure, field, row),
+    )
+}
+
+// 표에서 거래 통화 셀에 보여 줄 원화 환산 금액과 쓴 환율
+pub(crate) fn base_hint(
+    rates: &ExchangeRates,
+    structure: &CustomStructure,
+    field: &Field,
+    row: &Row,
+) -> Option<String> {
+    let value = field_value(row, &field.id);
+    let amount = parse_decimal(value)?;
+    let currency = amount_currency(field, value);
+    if !currency.per_row || currency.code.is_empty() || currency.code == BASE_CURRENCY {
+        return None;
+    }
+    let date = transaction_date(structure, field, row);
+    Some(match rates.rate_on(&currency.code, date) {
+        Some((day, rate)) => {
+            let base = currency::format_fixed(amount.checked_mul(rate)?, 0);
+            format!(
+                "≈ {} ({} 환율 {})",
+                currency::display(&Currency::default(), &base),
+                day,
+                rate.normalize()
+            )
+        }
+        None => format!("{} 이전의 {} 환율이 없습니다", date, currency.code),
+    })
+}
+
+// 원화로 환산한 값을 쓰는 필드 (원 단위)
+pub(crate) fn base_field(field: &Field) -> Field {
+    Field {
+        currency: Some(Currency::default()),
+        ..field.clone()
+    }
+}
+
+// 통화 머리글이나 값의 코드와 단위: "JPY(100)"은 100엔당 환율
+fn code_and_unit(text: &str) -> Option<(String, Decimal)> {
+    let code = code_in(text)?;
+    let unit: String = text.chars().filter(|c| c.is_ascii_digit()).collect();
+    let unit = unit.parse::<u32>().ok().filter(|u| *u > 0).unwrap_or(1);
+    Some((code, Decimal::from(unit)))
+}
+
+fn header_is(header: &str, names: &[&str]) -> bool {
+    let header = header.trim().to_lowercase();
+    names.iter().any(|name| header == *name)
+}
+
+// 환율 CSV 읽기. 날짜·통화·환율 열이 있는 세로 형식과 날짜 옆에 통화별 열이 있는 가로 형식(USD, JPY(100) ...)을 모두 받는다.
+// 읽은 환율과 건너뛴 줄 수
+pub(crate) fn parse_rates(table: &CsvTable) -> Result<(Vec<DailyRate>, usize), String> {
+    let find = |names: &[&str]| table.headers.iter().position(|h| header_is(h, names));
+    let date_col = find(&["날짜", "일자", "기준일", "기준일자", "date"]).unwrap_or(0);
+    let code_col = find(&["통화", "통화코드", "통화 코드", "currency", "code"]);
+    let rate_col = find(&["환율", "매매기준율", "기준환율", "rate"]);
+    // (열, 고정 통화) 목록: 세로 형식은 통화 열에서, 가로 형식은 머리글에서 통화를 읽는다
+    let columns: Vec<(usize, Option<(String, Decimal)>)> = match (code_col, rate_col) {
+        (Some(_), Some(rate_col)) => vec![(rate_col, None)],
+        _ => table
+            .headers
+            .iter()
+            .enumerate()
+            .filter(|(col, _)| *col != date_col)
+            .filter_map(|(col, header)| Some((col, Some(code_and_unit(header)?))))
+            .collect(),
+    };
+    if columns.is_empty() {
+        return Err("환율 열을 찾을 수 없습니다 (날짜, 통화, 환율 열 또는 통화별 열)".to_string());
+    }
+
+    let mut rates = Vec::new();
+    let mut skipped = 0;
+    for record in &table.records {
+        let cell = |col: usize| record.get(col).map(|v| v.trim()).unwrap_or_default();
+        let Some(date) = dates::parse_date(cell(date_col)) else {
+            skipped += 1;
+            continue;
+        };
+        for (col, fixed) in &columns {
+            let code_unit = fixed
+                .clone()
+                .or_else(|| code_col.and_then(|c| code_and_unit(cell(c))));
+            match (code_unit, parse_decimal(cell(*col))) {
+                (Some((code, unit)), Some(rate)) if rate > Decimal::ZERO => {
+                    rates.push((date, code, rate / unit));
+                }
+                // 가로 형식의 빈 칸은 그날 고시가 없는 통화
+                _ if cell(*col).is_empty() && fixed.is_some() => {}
+                _ => skipped += 1,
+            }
+        }
+    }
+    Ok((rates, skipped))
+}
+
+// 같은 날짜와 통화의 환율은 새 값으로 바꾸고 나머지는 추가. (추가, 변경) 건수
+pub(crate) fn merge_rates(rows: &mut Vec<Row>, rates: &[DailyRate]) -> (usize, usize) {
+    let mut index: HashMap<(String, String), usize> = rows
+        .iter()
+        .enumerate()
+        .map(|(i, row)| {
+            let date = dates::normalize_date(field_value(row, DATE_FIELD_ID)).unwrap_or_default();
+            let code = field_value(row, CODE_FIELD_ID).trim().to_ascii_uppercase();
+            ((date, code), i)
+        })
+        .collect();
+    let (mut added, mut updated) = (0, 0);
+    for (date, code, rate) in rates {
+        let date = date.format("%Y-%m-%d").to_string();
+        let rate = rate.normalize().to_string();
+        match index.get(&(date.clone(), code.clone())) {
+            Some(&i) => {
+                if field_value(&rows[i], RATE_FIELD_ID) != rate {
+                    rows[i].values.insert(
+                        RATE_FIELD_ID.to_string(),
+                        FieldValue {
+                            value: rate,
+                            field_type: FieldType::Number,
+                        },
+                    );
+                    updated += 1;
+                }
+            }
+            None => {
+                let mut row = Row::new();
+                for (id, value, field_type) in [
+                    (DATE_FIELD_ID, date.clone(), FieldType::Date),
+                    (CODE_FIELD_ID, code.clone(), FieldType::Text),
+                    (RATE_FIELD_ID, rate, FieldType::Number),
+                ] {
+                    row.values
+                        .insert(id.to_string(), FieldValue { value, field_type });
+                }
+                index.insert((date, code.clone()), rows.len());
+                rows.push(row);
+                added += 1;
+            }
+        }
+    }
+    (added, updated)
+}
+
+impl ERPApp {
+    // 환율표 구조체가 없거나 기본 필드가 빠졌으면 다시 만든다. 바뀌었으면 true
+    pub(crate) fn ensure_exchange_rates(&mut self) -> bool {
+        let existing = self
+            .custom_structures
+            .iter_mut()
+            .flat_map(|c| c.subcategories.iter_mut())
+            .flat_map(|s| s.structures.iter_mut())
+            .find(|s| s.id == RATES_STRUCTURE_ID);
+        if let Some(structure) = existing {
+            let mut changed = false;
+            for (i, builtin) in builtin_fields().into_iter().enumerate() {
+                match structure.fields.iter_mut().find(|f| f.id == builtin.id) {
+                    Some(field) if field.field_type == builtin.field_type => {}
+                    Some(field) => {
+                        field.field_type = builtin.field_type;
+                        changed = true;
+                    }
+                    None => {
+                        structure
+                            .fields
+                            .insert(i.min(structure.fields.len()), builtin);
+                        changed = true;
+                    }
+                }
+            }
+            return changed;
+        }
+
+        let structure = CustomStructure {
+            id: RATES_STRUCTURE_ID.to_string(),
+            name: "환율".to_string(),
+            fields: builtin_fields(),
+            version: 0,
+        };
+        let category = match self
+            .custom_structures
+            .iter_mut()
+            .position(|c| c.name == CATEGORY)
+        {
+            Some(i) => &mut self.custom_structures[i],
+            None => {
+                self.custom_structures.push(CustomCategory {
+                    name: CATEGORY.to_string(),
+                    subcategories: Vec::new(),
+                });
+                self.custom_structures.last_mut().unwrap()
+            }
+        };
+        match category
+            .subcategories
+            .iter_mut()
+            .find(|s| s.name == SUBCATEGORY)
+        {
+            Some(subcategory) => subcategory.structures.push(structure),
+            None => category.subcategories.push(SubCategory {
+                name: SUBCATEGORY.to_string(),
+                structures: vec![structure],
+            }),
+        }
+        true
+    }
+
+    pub(crate) fn exchange_rates(&self) -> ExchangeRates {
+        self.erp_data
+            .data
+            .get(RATES_STRUCTURE_ID)
+            .map(|rows| ExchangeRates::from_rows(rows))
+            .unwrap_or_default()
+    }
+
+    // 일별 환율 CSV를 환율표에 합친다 (파일 선택 대화상자 사용)
+    pub(crate) fn import_exchange_rates(&mut self) {
+        let Some(path) = FileDialog::new()
+            .add_filter("CSV", &["csv", "txt"])
+            .pick_file()
+        else {
+            return;
+        };
+        let parsed = read_csv_table(&path)
+            .map_err(|e| e.to_string())
+            .and_then(|table| parse_rates(&table));
+        let (rates, skipped) = match parsed {
+            Ok(parsed) => parsed,
+            Err(e) => {
+                eprintln!("환율 가져오기 실패: {}", e);
+                return;
+            }
+        };
+        let rows = self
+            .erp_data
+            .data
+            .entry(RATES_STRUCTURE_ID.to_string())
+            .or_default();
+        let (added, updated) = merge_rates(rows, &rates);
+        self.save_structure_rows(RATES_STRUCTURE_ID);
+        self.save_to_csv(RATES_STRUCTURE_ID);
+        println!(
+            "환율 가져오기 완료: 추가 {}건, 변경 {}건, 건너뜀 {}건",
+            added, updated, skipped
+        );
+    }
+
+    // 보고서용으로 거래 통화 금액을 원화로 바꾼 구조체와 행. 환율이 없는 값은 글자로 남겨 표시한다
+    pub(crate) fn base_currency_rows(
+        &self,
+        structure: &CustomStructure,
+        rows: &[Row],
+    ) -> (CustomStructure, Vec<Row>) {
+        let rates = self.exchange_rates();
+        let mut converted = structure.clone();
+        let mut rows = rows.to_vec();
+        for field in &mut converted.fields {
+            if field.field_type != FieldType::Currency || !currency_of(field).per_row {
+                continue;
+            }
+            for row in &mut rows {
+                let amount = base_amount(&rates, structure, field, row);
+                if let Some(field_value) = row.values.get_mut(&field.id) {
+                    field_value.value = match amount {
+                        Some(amount) => currency::format_fixed(amount, 0),
+                        None if field_value.value.trim().is_empty() => String::new(),
+                        None => format!("{} (환율 없음)", field_value.value.trim()),
+                    };
+                }
+            }
+            *field = base_field(field);
+            field.name = format!("{} ({})", field.name, BASE_CURRENCY);
+        }
+        (converted, rows)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::csv_format::CsvFormat;
+
+    fn table(headers: &[&str], records: &[&[&str]]) -> CsvTable {
+        CsvTable {
+            headers: headers.iter().map(|h| h.to_string()).collect(),
+            records: records
+                .iter()
+                .map(|r| r.iter().map(|v| v.to_string()).collect())
+                .collect(),
+            format: CsvFormat::default(),
+        }
+    }
+
+    fn date(value: &str) -> NaiveDate {
+        NaiveDate::parse_from_str(value, "%Y-%m-%d").unwrap()
+    }
+
+    fn number(value: &str) -> Decimal {
+        value.parse().unwrap()
+    }
+
+    fn value(row: &Row, field: &Field, value: &str) -> Row {
+        let mut row = row.clone();
+        row.values.insert(
+            field.id.clone(),
+            FieldValue {
+                value: value.to_string(),
+                field_type: field.field_type.clone(),
+            },
+        );
+        row
+    }
+
+    #[test]
+    fn parse_long_format() {
+        let (rates, skipped) = parse_rates(&table(
+            &["date", "currency", "rate"],
+            &[
+                &["2024-01-04", "usd", "1,320.5"],
+                &["2024.1.5", "JPY(100)", "920"],
+                &["2024-01-05", "EUR", "-1"],
+                &["언제", "USD", "1300"],
+            ],
+        ))
+        .unwrap();
+        assert_eq!(
+            rates,
+            vec![
+                (date("2024-01-04"), "USD".to_string(), number("1320.5")),
+                (date("2024-01-05"), "JPY".to_string(), number("9.2")),
+            ]
+        );
+        // 음수 환율과 읽을 수 없는 날짜
+        assert_eq!(skipped, 2);
+    }
+
+    #[test]
+    fn parse_wide_format_with_units() {
+        let (rates, skipped) = parse_rates(&table(
+            &["일자", "USD", "JPY(100)", "EUR"],
+            &[
+                &["2024-01-02", "1,300.50", "900", ""],
+                &["2024-01-03", "1310", "910", "없음"],
+            ],
+        ))
+        .unwrap();
+        assert_eq!(
+            rates,
+            vec![
+                (date("2024-01-02"), "USD".to_string(), number("1300.50")),
+                (date("2024-01-02"), "JPY".to_string(), number("9")),
+                (date("2024-01-03"), "USD".to_string(), number("1310")),
+                (date("2024-01-03"), "JPY".to_string(), number("9.1")),
+            ]
+        );
+        // 빈 칸은 고시가 없는 날이라 세지 않는다
+        assert_eq!(skipped, 1);
+        assert!(parse_rates(&table(&["날짜", "메모"], &[])).is_err());
+    }
+
+    #[test]
+    fn merge_replaces_same_day_rates() {
+        let mut rows = Vec::new();
+        let first = vec![
+            (date("2024-01-02"), "USD".to_string(), number("1300")),
+            (date("2024-01-02"), "JPY".to_string(), number("9")),
+        ];
+        assert_eq!(merge_rates(&mut rows, &first), (2, 0));
+        let second = vec![
+            (date("2024-01-02"), "USD".to_string(), number("1305.0")),
+            (date("2024-01-02"), "JPY".to_string(), number("9.00")),
+            (date("2024-01-03"), "USD".to_string(), number("1310")),
+        ];
+        assert_eq!(merge_rates(&mut rows, &second), (1, 1));
+        assert_eq!(merge_rates(&mut rows, &second), (0, 0));
+        assert_eq!(rows.len(), 3);
+        assert_eq!(field_value(&rows[0], RATE_FIELD_ID), "1305");
+    }
+
+    #[test]
+    fn rate_on_uses_latest_earlier_day() {
+        let mut rows = Vec::new();
+        merge_rates(
+            &mut rows,
+            &[
+                (date("2024-01-05"), "USD".to_string(), number("1320")),
+                (date("2024-01-02"), "USD".to_string(), number("1300")),
+            ],
+        );
+        let rates = ExchangeRates::from_rows(&rows);
+        assert_eq!(
+            rates.rate_on("USD", date("2024-01-04")),
+            Some((date("2024-01-02"), number("1300")))
+        );
+        assert_eq!(
+            rates.rate_on("USD", date("2024-01-05")),
+            Some((date("2024-01-05"), number("1320")))
+        );
+        assert_eq!(rates.rate_on("USD", date("2024-01-01")), None);
+        assert_eq!(rates.rate_on("EUR", date("2024-01-05")), None);
+        assert_eq!(
+            rates.rate_on(BASE_CURRENCY, date("2000-01-01")),
+            Some((date("2000-01-01"), Decimal::ONE))
+        );
+    }
+
+    #[test]
+    fn base_currency_rows_convert_on_transaction_date() {
+        let mut app = ERPApp::default();
+        let mut rates = Vec::new();
+        merge_rates(
+            &mut rates,
+            &[
+                (date("2024-01-02"), "USD".to_string(), number("1300.5")),
+                (date("2024-01-02"), "JPY".to_string(), number("9.1")),
+                (date("2024-01-05"), "USD".to_string(), number("1320")),
+            ],
+        );
+        app.erp_data
+            .data
+            .insert(RATES_STRUCTURE_ID.to_string(), rates);
+
+        let paid_on = Field {
+            name: "결제일".to_string(),
+            field_type: FieldType::Date,
+            ..Field::new()
+        };
+        let amount = Field {
+            name: "금액".to_string(),
+            field_type: FieldType::Currency,
+            currency: Some(Currency {
+                per_row: true,
+                ..Currency::default()
+            }),
+            ..Field::new()
+        };
+        let structure = CustomStructure {
+            fields: vec![paid_on.clone(), amount.clone()],
+            ..CustomStructure::new()
+        };
+        let row =
+            |day: &str, money: &str| value(&value(&Row::new(), &paid_on, day), &amount, money);
+        let rows = vec![
+            row("2024-01-04", "10.00 USD"),
+            row("2024-01-05", "10.00 USD"),
+            row("2024-01-03", "1000 JPY"),
+            row("2024-01-03", "5000 KRW"),
+            row("2023-12-29", "10.00 USD"),
+            row("2024-01-03", ""),
+        ];
+        let (converted, rows) = app.base_currency_rows(&structure, &rows);
+        assert_eq!(converted.fields[1].name, "금액 (KRW)");
+        assert_eq!(currency_of(&converted.fields[1]), Currency::default());
+        let values: Vec<&str> = rows.iter().map(|r| field_value(r, &amount.id)).collect();
+        assert_eq!(
+            values,
+            [
+                "13005",
+                "13200",
+                "9100",
+                "5000",
+                "10.00 USD (환율 없음)",
+                ""
+            ]
+        );
+    }
+}
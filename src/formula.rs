@@ -0,0 +1,977 @@
+// 수식 필드: 같은 행의 다른 필드로 계산한 값 (예: 수량 * 단가, IF(금액 > 1000000, "검토", "")).
+// 문법은 Excel과 같게 두어 내보낼 때 그대로 Excel 수식으로 옮길 수 있다
+use crate::currency::parse_decimal;
+use crate::line_items::format_total;
+use crate::schema::convert_value;
+use crate::{CustomStructure, Field, FieldType, Row};
+use chrono::{Local, NaiveDate};
+use eframe::egui;
+use rust_decimal::prelude::ToPrimitive;
+use rust_decimal::{Decimal, RoundingStrategy};
+use std::collections::HashMap;
+
+// 계산 중 소수 범위를 넘었을 때
+const OVERFLOW: &str = "숫자가 너무 큽니다";
+
+#[derive(Clone, Debug, PartialEq)]
+enum Token {
+    Number(Decimal),
+    Text(String),
+    Name(String), // 필드 이름 또는 함수 이름
+    Op(&'static str),
+    LParen,
+    RParen,
+    Comma,
+}
+
+const OPERATORS: [&str; 11] = ["<>", "<=", ">=", "+", "-", "*", "/", "&", "=", "<", ">"];
+
+fn tokenize(source: &str) -> Result<Vec<Token>, String> {
+    let chars: Vec<char> = source.chars().collect();
+    let mut tokens = Vec::new();
+    let mut i = 0;
+    while i < chars.len() {
+        let c = chars[i];
+        if c.is_whitespace() {
+            i += 1;
+        } else if c.is_ascii_digit()
+            || (c == '.' && chars.get(i + 1).is_some_and(|d| d.is_ascii_digit()))
+        {
+            let start = i;
+            while i < chars.len() && (chars[i].is_ascii_digit() || chars[i] == '.') {
+                i += 1;
+            }
+            let text: String = chars[start..i].iter().collect();
+            let number = text
+                .parse::<Decimal>()
+                .map_err(|_| format!("잘못된 숫자입니다: {}", text))?;
+            tokens.push(Token::Number(number));
+        } else if c == '"' {
+            // 문자열 안의 큰따옴표는 ""로 (Excel과 같음)
+            let mut text = String::new();
+            i += 1;
+            loop {
+                match chars.get(i) {
+                    None => return Err("닫는 따옴표가 없습니다".to_string()),
+                    Some('"') if chars.get(i + 1) == Some(&'"') => {
+                        text.push('"');
+                        i += 2;
+                    }
+                    Some('"') => {
+                        i += 1;
+                        break;
+                    }
+                    Some(c) => {
+                        text.push(*c);
+                        i += 1;
+                    }
+                }
+            }
+            tokens.push(Token::Text(text));
+        } else if c == '[' {
+            // 공백이나 기호가 들어간 필드 이름은 [필드 이름]
+            let Some(len) = chars[i..].iter().position(|c| *c == ']') else {
+                return Err("닫는 ]가 없습니다".to_string());
+            };
+            let name: String = chars[i + 1..i + len].iter().collect();
+            tokens.push(Token::Name(name.trim().to_string()));
+            i += len + 1;
+        } else if c.is_alphabetic() || c == '_' {
+            let start = i;
+            while i < chars.len() && (chars[i].is_alphanumeric() || chars[i] == '_') {
+                i += 1;
+            }
+            tokens.push(Token::Name(chars[start..i].iter().collect()));
+        } else if c == '(' {
+            tokens.push(Token::LParen);
+            i += 1;
+        } else if c == ')' {
+            tokens.push(Token::RParen);
+            i += 1;
+        } else if c == ',' {
+            tokens.push(Token::Comma);
+            i += 1;
+        } else {
+            let rest: String = chars[i..chars.len().min(i + 2)].iter().collect();
+            let Some(op) = OPERATORS.iter().find(|op| rest.starts_with(**op)) else {
+                return Err(format!("알 수 없는 문자입니다: {}", c));
+            };
+            tokens.push(Token::Op(op));
+            i += op.chars().count();
+        }
+    }
+    Ok(tokens)
+}
+
+#[derive(Clone, Debug, PartialEq)]
+pub(crate) enum Expr {
+    Number(Decimal),
+    Text(String),
+    Bool(bool),
+    Field(String),
+    Neg(Box<Expr>),
+    Binary(&'static str, Box<Expr>, Box<Expr>),
+    Call(String, Vec<Expr>),
+}
+
+// 우선순위: 비교 < & < + - < * / < 단항 -
+struct Parser {
+    tokens: Vec<Token>,
+    pos: usize,
+}
+
+impl Parser {
+    fn peek(&self) -> Option<&Token> {
+        self.tokens.get(self.pos)
+    }
+
+    fn next(&mut self) -> Option<Token> {
+        let token = self.tokens.get(self.pos).cloned();
+        self.pos += 1;
+        token
+    }
+
+    fn binary(
+        &mut self,
+        ops: &[&str],
+        operand: fn(&mut Self) -> Result<Expr, String>,
+    ) -> Result<Expr, String> {
+        let mut left = operand(self)?;
+        while let Some(Token::Op(op)) = self.peek() {
+            let op = *op;
+            if !ops.contains(&op) {
+                break;
+            }
+            self.pos += 1;
+            let right = operand(self)?;
+            left = Expr::Binary(op, Box::new(left), Box::new(right));
+        }
+        Ok(left)
+    }
+
+    fn comparison(&mut self) -> Result<Expr, String> {
+        self.binary(&["=", "<>", "<", ">", "<=", ">="], Self::concat)
+    }
+
+    fn concat(&mut self) -> Result<Expr, String> {
+        self.binary(&["&"], Self::additive)
+    }
+
+    fn additive(&mut self) -> Result<Expr, String> {
+        self.binary(&["+", "-"], Self::multiplicative)
+    }
+
+    fn multiplicative(&mut self) -> Result<Expr, String> {
+        self.binary(&["*", "/"], Self::unary)
+    }
+
+    fn unary(&mut self) -> Result<Expr, String> {
+        match self.peek() {
+            Some(Token::Op("-")) => {
+                self.pos += 1;
+                Ok(Expr::Neg(Box::new(self.unary()?)))
+            }
+            Some(Token::Op("+")) => {
+                self.pos += 1;
+                self.unary()
+            }
+            _ => self.primary(),
+        }
+    }
+
+    fn primary(&mut self) -> Result<Expr, String> {
+        match self.next() {
+            Some(Token::Number(n)) => Ok(Expr::Number(n)),
+            Some(Token::Text(s)) => Ok(Expr::Text(s)),
+            Some(Token::LParen) => {
+                let expr = self.comparison()?;
+                match self.next() {
+                    Some(Token::RParen) => Ok(expr),
+                    _ => Err("닫는 괄호가 없습니다".to_string()),
+                }
+            }
+            Some(Token::Name(name)) => {
+                if self.peek() != Some(&Token::LParen) {
+                    return Ok(match name.to_uppercase().as_str() {
+                        "TRUE" => Expr::Bool(true),
+                        "FALSE" => Expr::Bool(false),
+                        _ => Expr::Field(name),
+                    });
+                }
+                self.pos += 1;
+                let mut args = Vec::new();
+                if self.peek() == Some(&Token::RParen) {
+                    self.pos += 1;
+                } else {
+                    loop {
+                        args.push(self.comparison()?);
+                        match self.next() {
+                            Some(Token::Comma) => continue,
+                            Some(Token::RParen) => break,
+                            _ => return Err(format!("{}( 의 괄호가 닫히지 않았습니다", name)),
+                        }
+                    }
+                }
+                Ok(Expr::Call(name.to_uppercase(), args))
+            }
+            Some(Token::Op(op)) => Err(format!("{} 앞에 값이 없습니다", op)),
+            Some(Token::RParen) | Some(Token::Comma) | None => {
+                Err("수식이 끝나지 않았습니다".to_string())
+            }
+        }
+    }
+}
+
+// 함수 이름과 인수 개수 (최소, 최대)
+const FUNCTIONS: [(&str, usize, usize); 10] = [
+    ("IF", 2, 3),
+    ("AND", 1, usize::MAX),
+    ("OR", 1, usize::MAX),
+    ("NOT", 1, 1),
+    ("ROUND", 1, 2),
+    ("ABS", 1, 1),
+    ("MIN", 1, usize::MAX),
+    ("MAX", 1, usize::MAX),
+    ("DATEDIFF", 2, 2),
+    ("TODAY", 0, 0),
+];
+
+fn check_calls(expr: &Expr) -> Result<(), String> {
+    match expr {
+        Expr::Call(name, args) => {
+            let Some((_, min, max)) = FUNCTIONS.iter().find(|(f, _, _)| f == name) else {
+                return Err(format!("알 수 없는 함수입니다: {}", name));
+            };
+            if args.len() < *min || args.len() > *max {
+                return Err(format!("{} 함수의 인수 개수가 맞지 않습니다", name));
+            }
+            args.iter().try_for_each(check_calls)
+        }
+        Expr::Neg(inner) => check_calls(inner),
+        Expr::Binary(_, left, right) => {
+            check_calls(left)?;
+            check_calls(right)
+        }
+        _ => Ok(()),
+    }
+}
+
+pub(crate) fn parse(source: &str) -> Result<Expr, String> {
+    let tokens = tokenize(source)?;
+    if tokens.is_empty() {
+        return Err("수식이 비어 있습니다".to_string());
+    }
+    let mut parser = Parser { tokens, pos: 0 };
+    let expr = parser.comparison()?;
+    if parser.pos < parser.tokens.len() {
+        return Err("수식 끝에 남은 내용이 있습니다".to_string());
+    }
+    check_calls(&expr)?;
+    Ok(expr)
+}
+
+// 수식이 쓰는 필드 이름
+fn dependencies(expr: &Expr, names: &mut Vec<String>) {
+    match expr {
+        Expr::Field(name) if !names.contains(name) => names.push(name.clone()),
+        Expr::Neg(inner) => dependencies(inner, names),
+        Expr::Binary(_, left, right) => {
+            dependencies(left, names);
+            dependencies(right, names);
+        }
+        Expr::Call(_, args) => args.iter().for_each(|arg| dependencies(arg, names)),
+        _ => {}
+    }
+}
+
+#[derive(Clone, Debug, PartialEq)]
+enum Value {
+    Empty,
+    Number(Decimal), // 금액 계산에 오차가 없도록 고정 소수
+    Text(String),
+    Bool(bool),
+    Date(NaiveDate),
+}
+
+fn parse_date(value: &str) -> Option<NaiveDate> {
+    let normalized = convert_value(value, &FieldType::Date).ok()?;
+    NaiveDate::parse_from_str(&normalized, "%Y-%m-%d").ok()
+}
+
+impl Value {
+    fn number(&self) -> Result<Decimal, String> {
+        match self {
+            Value::Empty => Ok(Decimal::ZERO),
+            Value::Number(n) => Ok(*n),
+            Value::Bool(b) => Ok(if *b { Decimal::ONE } else { Decimal::ZERO }),
+            Value::Text(s) => parse_decimal(s).ok_or(format!("숫자가 아닙니다: {}", s)),
+            Value::Date(d) => Err(format!("숫자가 아닙니다: {}", d)),
+        }
+    }
+
+    fn date(&self) -> Result<NaiveDate, String> {
+        match self {
+            Value::Date(d) => Ok(*d),
+            Value::Text(s) => parse_date(s).ok_or(format!("날짜가 아닙니다: {}", s)),
+            other => Err(format!("날짜가 아닙니다: {}", other.text())),
+        }
+    }
+
+    fn truthy(&self) -> bool {
+        match self {
+            Value::Empty => false,
+            Value::Number(n) => !n.is_zero(),
+            Value::Text(s) => !s.is_empty(),
+            Value::Bool(b) => *b,
+            Value::Date(_) => true,
+        }
+    }
+
+    // 저장하는 값
+    fn text(&self) -> String {
+        match self {
+            Value::Empty => String::new(),
+            Value::Number(n) => format_total(*n),
+            Value::Text(s) => s.clone(),
+            Value::Bool(b) => b.to_string(),
+            Value::Date(d) => d.format("%Y-%m-%d").to_string(),
+        }
+    }
+}
+
+// 필드 값을 필드 타입에 맞는 값으로
+fn field_value(field: &Field, value: &str) -> Value {
+    let value = value.trim();
+    if value.is_empty() {
+        return Value::Empty;
+    }
+    let parsed = match field.field_type {
+        FieldType::Number | FieldType::Currency | FieldType::Formula | FieldType::Rollup => {
+            parse_decimal(value).map(Value::Number)
+        }
+        FieldType::Date | FieldType::DateTime => parse_date(value).map(Value::Date),
+        FieldType::Boolean => value.parse().ok().map(Value::Bool),
+        _ => None,
+    };
+    parsed.unwrap_or_else(|| Value::Text(value.to_string()))
+}
+
+fn compare(op: &str, left: &Value, right: &Value) -> Result<bool, String> {
+    use std::cmp::Ordering;
+    let ordering = match (left, right) {
+        // 빈 날짜는 어떤 날짜보다도 앞
+        (Value::Empty, Value::Date(_)) => Ordering::Less,
+        (Value::Date(_), Value::Empty) => Ordering::Greater,
+        (Value::Date(a), b) | (b, Value::Date(a)) if b.date().is_ok() => {
+            let (a, b) = (*a, b.date()?);
+            if matches!(left, Value::Date(_)) {
+                a.cmp(&b)
+            } else {
+                b.cmp(&a)
+            }
+        }
+        (Value::Text(_), _) | (_, Value::Text(_))
+            if left.number().is_err() || right.number().is_err() =>
+        {
+            left.text().to_lowercase().cmp(&right.text().to_lowercase())
+        }
+        _ => left.number()?.cmp(&right.number()?),
+    };
+    Ok(match op {
+        "=" => ordering == Ordering::Equal,
+        "<>" => ordering != Ordering::Equal,
+        "<" => ordering == Ordering::Less,
+        ">" => ordering == Ordering::Greater,
+        "<=" => ordering != Ordering::Greater,
+        _ => ordering != Ordering::Less,
+    })
+}
+
+fn checked(number: Option<Decimal>) -> Result<Value, String> {
+    number.map(Value::Number).ok_or(OVERFLOW.to_string())
+}
+
+// Excel의 ROUND처럼 0.5는 0에서 먼 쪽으로. 자릿수가 음수면 10, 100, ... 단위로
+fn round(number: Decimal, digits: i64) -> Result<Decimal, String> {
+    let strategy = RoundingStrategy::MidpointAwayFromZero;
+    if digits >= 0 {
+        return Ok(number.round_dp_with_strategy(digits.min(28) as u32, strategy));
+    }
+    let mut unit = Decimal::ONE;
+    for _ in 0..digits.unsigned_abs() {
+        unit = unit.checked_mul(Decimal::TEN).ok_or(OVERFLOW)?;
+    }
+    (number / unit)
+        .round_dp_with_strategy(0, strategy)
+        .checked_mul(unit)
+        .ok_or(OVERFLOW.to_string())
+}
+
+fn eval(expr: &Expr, lookup: &dyn Fn(&str) -> Result<Value, String>) -> Result<Value, String> {
+    match expr {
+        Expr::Number(n) => Ok(Value::Number(*n)),
+        Expr::Text(s) => Ok(Value::Text(s.clone())),
+        Expr::Bool(b) => Ok(Value::Bool(*b)),
+        Expr::Field(name) => lookup(name),
+        Expr::Neg(inner) => Ok(Value::Number(-eval(inner, lookup)?.number()?)),
+        Expr::Binary(op, left, right) => {
+            let left = eval(left, lookup)?;
+            let right = eval(right, lookup)?;
+            match *op {
+                "&" => Ok(Value::Text(left.text() + &right.text())),
+                // 날짜 + 일수, 날짜 - 일수, 날짜 - 날짜
+                "+" | "-" if matches!(left, Value::Date(_)) => {
+                    let date = left.date()?;
+                    if *op == "-" && matches!(right, Value::Date(_)) {
+                        return Ok(Value::Number((date - right.date()?).num_days().into()));
+                    }
+                    let days = right
+                        .number()?
+                        .round()
+                        .to_i64()
+                        .ok_or("날짜 범위를 벗어났습니다".to_string())?;
+                    let days = if *op == "-" { -days } else { days };
+                    date.checked_add_signed(chrono::Duration::days(days))
+                        .map(Value::Date)
+                        .ok_or("날짜 범위를 벗어났습니다".to_string())
+                }
+                "+" => checked(left.number()?.checked_add(right.number()?)),
+                "-" => checked(left.number()?.checked_sub(right.number()?)),
+                "*" => checked(left.number()?.checked_mul(right.number()?)),
+                "/" => {
+                    let divisor = right.number()?;
+                    if divisor.is_zero() {
+                        return Err("0으로 나눌 수 없습니다".to_string());
+                    }
+                    checked(left.number()?.checked_div(divisor))
+                }
+                op => Ok(Value::Bool(compare(op, &left, &right)?)),
+            }
+        }
+        Expr::Call(name, args) => {
+            let value = |i: usize| eval(&args[i], lookup);
+            match name.as_str() {
+                // 고르지 않은 쪽은 계산하지 않는다
+                "IF" => {
+                    if value(0)?.truthy() {
+                        value(1)
+                    } else if args.len() > 2 {
+                        value(2)
+                    } else {
+                        Ok(Value::Bool(false))
+                    }
+                }
+                "AND" => Ok(Value::Bool((0..args.len()).try_fold(true, |acc, i| {
+                    Ok::<_, String>(acc && value(i)?.truthy())
+                })?)),
+                "OR" => Ok(Value::Bool((0..args.len()).try_fold(false, |acc, i| {
+                    Ok::<_, String>(acc || value(i)?.truthy())
+                })?)),
+                "NOT" => Ok(Value::Bool(!value(0)?.truthy())),
+                "ROUND" => {
+                    let digits = if args.len() > 1 {
+                        value(1)?.number()?.round().to_i64().ok_or(OVERFLOW)?
+                    } else {
+                        0
+                    };
+                    round(value(0)?.number()?, digits).map(Value::Number)
+                }
+                "ABS" => Ok(Value::Number(value(0)?.number()?.abs())),
+                "MIN" | "MAX" => {
+                    let numbers = (0..args.len())
+                        .map(|i| value(i)?.number())
+                        .collect::<Result<Vec<Decimal>, String>>()?;
+                    let picked = if name == "MIN" {
+                        numbers.into_iter().min()
+                    } else {
+                        numbers.into_iter().max()
+                    };
+                    Ok(Value::Number(picked.unwrap_or_default()))
+                }
+                // DATEDIFF(끝, 시작): 일수. 어느 한쪽이 비어 있으면 빈 값
+                "DATEDIFF" => {
+                    let (end, start) = (value(0)?, value(1)?);
+                    if end == Value::Empty || start == Value::Empty {
+                        return Ok(Value::Empty);
+                    }
+                    Ok(Value::Number(
+                        (end.date()? - start.date()?).num_days().into(),
+                    ))
+                }
+                "TODAY" => Ok(Value::Date(Local::now().date_naive())),
+                _ => Err(format!("알 수 없는 함수입니다: {}", name)),
+            }
+        }
+    }
+}
+
+// 구조체의 수식 필드 검사: 문법, 없는 필드, 순환 참조. 필드 ID -> 오류
+pub(crate) fn check_formulas(structure: &CustomStructure) -> HashMap<String, String> {
+    let mut errors = HashMap::new();
+    let mut parsed: HashMap<&str, Vec<String>> = HashMap::new();
+    for field in structure
+        .fields
+        .iter()
+        .filter(|f| f.field_type == FieldType::Formula)
+    {
+        match parse(&field.formula) {
+            Ok(expr) => {
+                let mut names = Vec::new();
+                dependencies(&expr, &mut names);
+                match names
+                    .iter()
+                    .find(|name| !structure.fields.iter().any(|f| &f.name == *name))
+                {
+                    Some(missing) => {
+                        errors.insert(field.id.clone(), format!("없는 필드입니다: {}", missing));
+                    }
+                    None => {
+                        parsed.insert(field.name.as_str(), names);
+                    }
+                }
+            }
+            Err(e) => {
+                errors.insert(field.id.clone(), e);
+            }
+        }
+    }
+
+    // 수식 필드끼리의 순환 참조
+    fn visit<'a>(
+        name: &'a str,
+        graph: &'a HashMap<&str, Vec<String>>,
+        path: &mut Vec<&'a str>,
+    ) -> Option<Vec<&'a str>> {
+        if let Some(start) = path.iter().position(|n| *n == name) {
+            let mut cycle = path[start..].to_vec();
+            cycle.push(name);
+            return Some(cycle);
+        }
+        let deps = graph.get(name)?;
+        path.push(name);
+        for dep in deps {
+            if let Some(cycle) = visit(dep, graph, path) {
+                return Some(cycle);
+            }
+        }
+        path.pop();
+        None
+    }
+    for field in structure
+        .fields
+        .iter()
+        .filter(|f| f.field_type == FieldType::Formula)
+    {
+        if errors.contains_key(&field.id) {
+            continue;
+        }
+        if let Some(cycle) = visit(&field.name, &parsed, &mut Vec::new()) {
+            if cycle.contains(&field.name.as_str()) {
+                errors.insert(
+                    field.id.clone(),
+                    format!("순환 참조: {}", cycle.join(" → ")),
+                );
+            }
+        }
+    }
+    errors
+}
+
+// 구조체 편집기의 수식 입력과 검사 결과
+pub(crate) fn formula_editor(
+    ui: &mut egui::Ui,
+    id: impl std::hash::Hash,
+    field: &mut Field,
+    error: Option<&String>,
+) {
+    ui.horizontal(|ui| {
+        ui.label("수식:");
+        ui.add(
+            egui::TextEdit::singleline(&mut field.formula)
+                .id_salt(("field_formula", id))
+                .hint_text("예: 수량 * 단가")
+                .desired_width(300.0),
+        );
+    });
+    match error {
+        Some(error) => {
+            ui.colored_label(egui::Color32::RED, format!("⚠ {}", error));
+        }
+        None => {
+            let mut names = Vec::new();
+            if let Ok(expr) = parse(&field.formula) {
+                dependencies(&expr, &mut names);
+            }
+            if !names.is_empty() {
+                ui.weak(format!("참조 필드: {}", names.join(", ")));
+            }
+        }
+    }
+}
+
+// 구조체의 수식을 한 번 파싱하고 검사해 둔 것. 행마다 계산만 한다
+#[derive(Debug, Default)]
+pub(crate) struct Formulas {
+    fields: Vec<Field>,
+    ordered: Vec<(String, Expr)>, // 의존하는 수식이 먼저 오도록 정렬한 (필드 ID, 수식)
+    errors: HashMap<String, String>, // 문법, 없는 필드, 순환 참조
+}
+
+impl Formulas {
+    pub(crate) fn compile(structure: &CustomStructure) -> Self {
+        let errors = check_formulas(structure);
+        let mut pending: Vec<(&Field, Expr, Vec<String>)> = structure
+            .fields
+            .iter()
+            .filter(|f| f.field_type == FieldType::Formula && !errors.contains_key(&f.id))
+            .filter_map(|f| {
+                let expr = parse(&f.formula).ok()?;
+                let mut names = Vec::new();
+                dependencies(&expr, &mut names);
+                Some((f, expr, names))
+            })
+            .collect();
+
+        // 참조하는 수식 필드가 모두 앞에 오면 계산 순서에 넣는다 (순환은 위에서 걸러짐)
+        let mut ordered: Vec<(String, Expr)> = Vec::new();
+        while !pending.is_empty() {
+            let (ready, rest): (Vec<_>, Vec<_>) = pending.into_iter().partition(|(_, _, names)| {
+                names.iter().all(|name| {
+                    structure
+                        .fields
+                        .iter()
+                        .find(|f| &f.name == name)
+                        .is_some_and(|f| {
+                            f.field_type != FieldType::Formula
+                                || ordered.iter().any(|(id, _)| *id == f.id)
+                                || errors.contains_key(&f.id)
+                        })
+                })
+            });
+            if ready.is_empty() {
+                break;
+            }
+            ordered.extend(ready.into_iter().map(|(f, expr, _)| (f.id.clone(), expr)));
+            pending = rest;
+        }
+
+        Formulas {
+            fields: structure.fields.clone(),
+            ordered,
+            errors,
+        }
+    }
+
+    // 행의 수식 필드를 모두 다시 계산해 값에 넣는다. 계산하지 못한 필드는 빈 값으로 두고
+    // 필드 ID -> 오류를 돌려준다
+    pub(crate) fn apply(&self, row: &mut Row) -> HashMap<String, String> {
+        let mut errors = self.errors.clone();
+        let mut results: HashMap<&str, Value> = HashMap::new();
+        for (field_id, expr) in &self.ordered {
+            let lookup = |name: &str| -> Result<Value, String> {
+                let Some(dep) = self.fields.iter().find(|f| f.name == name) else {
+                    return Err(format!("없는 필드입니다: {}", name));
+                };
+                if dep.field_type == FieldType::Formula {
+                    return results
+                        .get(dep.id.as_str())
+                        .cloned()
+                        .ok_or(format!("{} 필드에 오류가 있습니다", name));
+                }
+                let value = row.values.get(&dep.id).map(|v| v.value.as_str());
+                Ok(field_value(dep, value.unwrap_or_default()))
+            };
+            match eval(expr, &lookup) {
+                Ok(value) => {
+                    results.insert(field_id, value);
+                }
+                Err(e) => {
+                    errors.insert(field_id.clone(), e);
+                }
+            }
+        }
+
+        for field in self
+            .fields
+            .iter()
+            .filter(|f| f.field_type == FieldType::Formula)
+        {
+            let value = results
+                .get(field.id.as_str())
+                .map(Value::text)
+                .unwrap_or_default();
+            let field_value = row.values.entry(field.id.clone()).or_default();
+            field_value.value = value;
+            field_value.field_type = FieldType::Formula;
+        }
+        errors
+    }
+}
+
+// 0부터 시작하는 열 번호를 Excel 열 이름으로 (0 -> A, 26 -> AA)
+fn column_name(mut col: usize) -> String {
+    let mut name = Vec::new();
+    loop {
+        name.push((b'A' + (col % 26) as u8) as char);
+        if col < 26 {
+            break;
+        }
+        col = col / 26 - 1;
+    }
+    name.iter().rev().collect()
+}
+
+// Excel 수식으로 옮기기. column은 필드 이름 -> 시트의 열 번호, row는 1부터 시작하는 Excel 행 번호.
+// 시트에 없는 필드를 쓰면 None
+pub(crate) fn to_excel(
+    expr: &Expr,
+    column: &dyn Fn(&str) -> Option<usize>,
+    row: u32,
+) -> Option<String> {
+    let join = |args: &[Expr]| -> Option<String> {
+        let args = args
+            .iter()
+            .map(|arg| to_excel(arg, column, row))
+            .collect::<Option<Vec<_>>>()?;
+        Some(args.join(","))
+    };
+    Some(match expr {
+        Expr::Number(n) => n.to_string(),
+        Expr::Text(s) => format!("\"{}\"", s.replace('"', "\"\"")),
+        Expr::Bool(b) => b.to_string().to_uppercase(),
+        Expr::Field(name) => format!("{}{}", column_name(column(name)?), row),
+        Expr::Neg(inner) => format!("-{}", to_excel(inner, column, row)?),
+        Expr::Binary(op, left, right) => format!(
+            "({}{}{})",
+            to_excel(left, column, row)?,
+            op,
+            to_excel(right, column, row)?
+        ),
+        // Excel의 DAYS(끝, 시작)와 같다
+        Expr::Call(name, args) if name == "DATEDIFF" => format!("DAYS({})", join(args)?),
+        // Excel의 ROUND는 자릿수를 생략할 수 없다
+        Expr::Call(name, args) if name == "ROUND" && args.len() == 1 => {
+            format!("ROUND({},0)", join(args)?)
+        }
+        Expr::Call(name, args) => format!("{}({})", name, join(args)?),
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::FieldValue;
+
+    fn number(n: &str) -> Decimal {
+        n.parse().unwrap()
+    }
+
+    fn field(name: &str, field_type: FieldType, formula: &str) -> Field {
+        Field {
+            name: name.to_string(),
+            field_type,
+            formula: formula.to_string(),
+            ..Field::new()
+        }
+    }
+
+    fn evaluate(source: &str) -> Result<Value, String> {
+        let lookup = |name: &str| -> Result<Value, String> {
+            match name {
+                "수량" => Ok(Value::Number(number("3"))),
+                "단가" => Ok(Value::Number(number("1500.5"))),
+                "시작일" => Ok(Value::Date(NaiveDate::from_ymd_opt(2024, 1, 30).unwrap())),
+                "비고" => Ok(Value::Empty),
+                _ => Err(format!("없는 필드입니다: {}", name)),
+            }
+        };
+        eval(&parse(source)?, &lookup)
+    }
+
+    #[test]
+    fn tokenize_names_strings_and_operators() {
+        assert_eq!(
+            tokenize("[단가 (원)] >= 1.5").unwrap(),
+            vec![
+                Token::Name("단가 (원)".to_string()),
+                Token::Op(">="),
+                Token::Number(number("1.5")),
+            ]
+        );
+        assert_eq!(
+            tokenize("\"검\"\"토\" & 수량_2").unwrap(),
+            vec![
+                Token::Text("검\"토".to_string()),
+                Token::Op("&"),
+                Token::Name("수량_2".to_string()),
+            ]
+        );
+        assert_eq!(
+            tokenize("IF(a<>b,1)").unwrap(),
+            vec![
+                Token::Name("IF".to_string()),
+                Token::LParen,
+                Token::Name("a".to_string()),
+                Token::Op("<>"),
+                Token::Name("b".to_string()),
+                Token::Comma,
+                Token::Number(Decimal::ONE),
+                Token::RParen,
+            ]
+        );
+        assert!(tokenize("\"닫지 않음").is_err());
+        assert!(tokenize("[닫지 않음").is_err());
+        assert!(tokenize("1.2.3").is_err());
+        assert!(tokenize("수량 # 2").is_err());
+    }
+
+    #[test]
+    fn parse_precedence_and_errors() {
+        let field = |name: &str| Box::new(Expr::Field(name.to_string()));
+        // 곱셈이 덧셈보다, 덧셈이 비교보다 먼저
+        assert_eq!(
+            parse("a + b * c > 1").unwrap(),
+            Expr::Binary(
+                ">",
+                Box::new(Expr::Binary(
+                    "+",
+                    field("a"),
+                    Box::new(Expr::Binary("*", field("b"), field("c")))
+                )),
+                Box::new(Expr::Number(Decimal::ONE))
+            )
+        );
+        assert_eq!(
+            parse("-(a - b)").unwrap(),
+            Expr::Neg(Box::new(Expr::Binary("-", field("a"), field("b"))))
+        );
+        assert_eq!(
+            parse("if(true, 1)").unwrap(),
+            Expr::Call(
+                "IF".to_string(),
+                vec![Expr::Bool(true), Expr::Number(Decimal::ONE)]
+            )
+        );
+        assert!(parse("").is_err());
+        assert!(parse("1 +").is_err());
+        assert!(parse("(1 + 2").is_err());
+        assert!(parse("1 2").is_err());
+        assert!(parse("FOO(1)").is_err());
+        assert!(parse("NOT(1, 2)").is_err());
+        assert!(parse("TODAY(1)").is_err());
+    }
+
+    #[test]
+    fn eval_functions_and_operators() {
+        assert_eq!(evaluate("수량 * 단가"), Ok(Value::Number(number("4501.5"))));
+        assert_eq!(evaluate("0.1 + 0.2"), Ok(Value::Number(number("0.3"))));
+        assert_eq!(evaluate("ROUND(2.5)"), Ok(Value::Number(number("3"))));
+        assert_eq!(evaluate("ROUND(-2.5)"), Ok(Value::Number(number("-3"))));
+        assert_eq!(
+            evaluate("ROUND(1234, -2)"),
+            Ok(Value::Number(number("1200")))
+        );
+        assert_eq!(
+            evaluate("IF(수량 * 단가 > 4000, \"검토\", \"\")"),
+            Ok(Value::Text("검토".to_string()))
+        );
+        // 고르지 않은 쪽의 오류는 나지 않는다
+        assert_eq!(
+            evaluate("IF(FALSE, 없음, 1)"),
+            Ok(Value::Number(Decimal::ONE))
+        );
+        assert_eq!(evaluate("AND(1, NOT(0))"), Ok(Value::Bool(true)));
+        assert_eq!(evaluate("OR(0, FALSE)"), Ok(Value::Bool(false)));
+        assert_eq!(evaluate("MIN(3, -1, 2)"), Ok(Value::Number(number("-1"))));
+        assert_eq!(evaluate("ABS(-수량)"), Ok(Value::Number(number("3"))));
+        assert_eq!(
+            evaluate("수량 & \"개\""),
+            Ok(Value::Text("3개".to_string()))
+        );
+        assert_eq!(
+            evaluate("시작일 + 3"),
+            Ok(Value::Date(NaiveDate::from_ymd_opt(2024, 2, 2).unwrap()))
+        );
+        assert_eq!(
+            evaluate("DATEDIFF(\"2024-03-01\", 시작일)"),
+            Ok(Value::Number(number("31")))
+        );
+        assert_eq!(evaluate("DATEDIFF(비고, 시작일)"), Ok(Value::Empty));
+        assert_eq!(evaluate("\"b\" > \"A\""), Ok(Value::Bool(true)));
+        assert!(evaluate("1 / 0").is_err());
+        assert!(evaluate("없음 + 1").is_err());
+    }
+
+    #[test]
+    fn to_excel_translates_functions() {
+        let column = |name: &str| {
+            ["수량", "단가", "금액", "종료일", "시작일"]
+                .iter()
+                .position(|n| *n == name)
+        };
+        let excel = |source: &str| to_excel(&parse(source).unwrap(), &column, 5);
+        assert_eq!(excel("수량 * 단가").as_deref(), Some("(A5*B5)"));
+        assert_eq!(
+            excel("DATEDIFF(종료일, 시작일)").as_deref(),
+            Some("DAYS(D5,E5)")
+        );
+        assert_eq!(excel("ROUND(-금액)").as_deref(), Some("ROUND(-C5,0)"));
+        assert_eq!(
+            excel("IF(금액 > 1000, \"검\"\"토\", TRUE)").as_deref(),
+            Some("IF((C5>1000),\"검\"\"토\",TRUE)")
+        );
+        assert_eq!(excel("없음 + 1"), None);
+        assert_eq!(column_name(0), "A");
+        assert_eq!(column_name(25), "Z");
+        assert_eq!(column_name(26), "AA");
+        assert_eq!(column_name(27 * 26), "AAA");
+    }
+
+    #[test]
+    fn compiled_formulas_follow_dependencies() {
+        let quantity = field("수량", FieldType::Number, "");
+        let price = field("단가", FieldType::Number, "");
+        // 뒤에 오는 필드를 참조해도 계산 순서를 맞춘다
+        let label = field("표시", FieldType::Formula, "금액 & \"원\"");
+        let amount = field("금액", FieldType::Formula, "수량 * 단가");
+        let cycle_a = field("A", FieldType::Formula, "B + 1");
+        let cycle_b = field("B", FieldType::Formula, "A + 1");
+        let after_cycle = field("C", FieldType::Formula, "A * 2");
+        let structure = CustomStructure {
+            fields: vec![
+                quantity.clone(),
+                price.clone(),
+                label.clone(),
+                amount.clone(),
+                cycle_a.clone(),
+                cycle_b.clone(),
+                after_cycle.clone(),
+            ],
+            ..CustomStructure::new()
+        };
+        let formulas = Formulas::compile(&structure);
+
+        let mut row = Row::new();
+        for (field, value) in [(&quantity, "2"), (&price, "1000")] {
+            row.values.insert(
+                field.id.clone(),
+                FieldValue {
+                    value: value.to_string(),
+                    field_type: field.field_type.clone(),
+                },
+            );
+        }
+        let errors = formulas.apply(&mut row);
+        assert_eq!(row.values[&amount.id].value, "2000");
+        assert_eq!(row.values[&label.id].value, "2000원");
+        assert!(errors[&cycle_a.id].starts_with("순환 참조"));
+        assert!(errors[&cycle_b.id].starts_with("순환 참조"));
+        assert_eq!(errors[&after_cycle.id], "A 필드에 오류가 있습니다");
+        assert_eq!(row.values[&after_cycle.id].value, "");
+
+        // 한 번 만든 것으로 다른 행도 계산
+        row.values.get_mut(&quantity.id).unwrap().value = "3".to_string();
+        formulas.apply(&mut row);
+        assert_eq!(row.values[&label.id].value, "3000원");
+    }
+}
@@ -0,0 +1,324 @@
+// 주민등록번호, 사업자등록번호, 전화번호 필드: 형식과 검증 번호를 확인해 하이픈을 넣어 저장하고,
+// 표와 내보내기에서는 뒷자리를 가린다 (👁 버튼을 눌러야 원래 값이 보인다)
+use crate::{CustomStructure, Field, FieldType, Row};
+use chrono::NaiveDate;
+use eframe::egui;
+use egui::Response;
+
+pub(crate) fn is_identifier(field_type: &FieldType) -> bool {
+    matches!(
+        field_type,
+        FieldType::ResidentNumber | FieldType::BusinessNumber | FieldType::Phone
+    )
+}
+
+// 숫자만 남긴다. 숫자, 하이픈, 공백, 점, 괄호 밖의 문자가 있으면 None
+fn digits(value: &str) -> Option<String> {
+    if value
+        .chars()
+        .any(|c| !(c.is_ascii_digit() || matches!(c, '-' | ' ' | '.' | '(' | ')')))
+    {
+        return None;
+    }
+    Some(value.chars().filter(|c| c.is_ascii_digit()).collect())
+}
+
+// 뒷자리 7자리 중 첫 자리(성별)로 세기를 정해 생년월일을 확인한다.
+// 2020년 10월부터 뒷자리가 임의 번호라 마지막 검증 번호는 보지 않는다
+fn normalize_resident(value: &str) -> Result<String, String> {
+    let digits = digits(value)
+        .filter(|d| d.len() == 13)
+        .ok_or(format!("주민등록번호는 13자리 숫자입니다: {}", value))?;
+    let century = match digits.as_bytes()[6] {
+        b'1' | b'2' | b'5' | b'6' => 1900,
+        b'3' | b'4' | b'7' | b'8' => 2000,
+        _ => 1800,
+    };
+    let number = |range: std::ops::Range<usize>| digits[range].parse::<u32>().unwrap_or(0);
+    NaiveDate::from_ymd_opt(century + number(0..2) as i32, number(2..4), number(4..6)).ok_or(
+        format!("주민등록번호의 생년월일이 올바르지 않습니다: {}", value),
+    )?;
+    Ok(format!("{}-{}", &digits[..6], &digits[6..]))
+}
+
+// 사업자등록번호 검증 번호: 앞 9자리에 1,3,7,1,3,7,1,3,5를 곱해 더하고 9번째 자리 x5의 십의 자리를 더한다
+fn business_check_digit(digits: &[u32]) -> u32 {
+    const WEIGHTS: [u32; 9] = [1, 3, 7, 1, 3, 7, 1, 3, 5];
+    let sum: u32 = digits.iter().zip(WEIGHTS).map(|(d, w)| d * w).sum::<u32>() + digits[8] * 5 / 10;
+    (10 - sum % 10) % 10
+}
+
+fn normalize_business(value: &str) -> Result<String, String> {
+    let text = digits(value)
+        .filter(|d| d.len() == 10)
+        .ok_or(format!("사업자등록번호는 10자리 숫자입니다: {}", value))?;
+    let numbers: Vec<u32> = text.chars().filter_map(|c| c.to_digit(10)).collect();
+    if business_check_digit(&numbers) != numbers[9] {
+        return Err(format!(
+            "사업자등록번호 검증 번호가 맞지 않습니다: {}",
+            value
+        ));
+    }
+    Ok(format!("{}-{}-{}", &text[..3], &text[3..5], &text[5..]))
+}
+
+// 02 지역 번호, 휴대전화/지역 번호(3자리), 0505 같은 4자리 번호, 1588 같은 대표 번호
+fn normalize_phone(value: &str) -> Result<String, String> {
+    let error = || format!("전화번호 형식이 아닙니다: {}", value);
+    let trimmed = value.trim();
+    // +82 10-1234-5678 같은 국제 형식
+    let local = match trimmed.strip_prefix("+82") {
+        Some(rest) => format!("0{}", rest.trim_start_matches([' ', '-', '(', ')', '0'])),
+        None => trimmed.to_string(),
+    };
+    let digits = digits(&local).ok_or_else(error)?;
+    let groups: &[usize] = match (digits.len(), digits.starts_with("02")) {
+        (9, true) => &[2, 3, 4],
+        (10, true) => &[2, 4, 4],
+        (10, false) if digits.starts_with('0') => &[3, 3, 4],
+        (11, false) if digits.starts_with('0') => &[3, 4, 4],
+        (12, false) if digits.starts_with('0') => &[4, 4, 4],
+        (8, false) if digits.starts_with('1') => &[4, 4],
+        _ => return Err(error()),
+    };
+    let mut parts = Vec::new();
+    let mut start = 0;
+    for len in groups {
+        parts.push(&digits[start..start + len]);
+        start += len;
+    }
+    Ok(parts.join("-"))
+}
+
+// 내보내기에서 가린 값 (900101-1****** 등)
+pub(crate) fn is_masked(value: &str) -> bool {
+    value.contains('*')
+}
+
+// 하이픈을 넣은 저장 형식. 빈 값은 그대로
+pub(crate) fn normalize(field_type: &FieldType, value: &str) -> Result<String, String> {
+    if value.trim().is_empty() {
+        return Ok(String::new());
+    }
+    if is_masked(value) {
+        return Err(format!(
+            "가려진 값은 가져올 수 없습니다 (가리지 않고 내보낸 파일을 써주세요): {}",
+            value
+        ));
+    }
+    match field_type {
+        FieldType::ResidentNumber => normalize_resident(value),
+        FieldType::BusinessNumber => normalize_business(value),
+        _ => normalize_phone(value),
+    }
+}
+
+// 가린 값: 900101-1******, 123-45-*****, 010-****-5678. 형식이 맞지 않는 값은 앞 몇 글자만 남긴다
+pub(crate) fn mask(field_type: &FieldType, value: &str) -> String {
+    let Ok(formatted) = normalize(field_type, value) else {
+        let keep = value.chars().count().min(3);
+        return value
+            .chars()
+            .enumerate()
+            .map(|(i, c)| if i < keep { c } else { '*' })
+            .collect();
+    };
+    let mut parts: Vec<String> = formatted.split('-').map(str::to_string).collect();
+    let hide = |text: &str| "*".repeat(text.len());
+    match field_type {
+        FieldType::ResidentNumber => {
+            parts[1] = format!("{}{}", &parts[1][..1], hide(&parts[1][1..]))
+        }
+        FieldType::BusinessNumber => parts[2] = hide(&parts[2]),
+        // 가운데 국번, 대표 번호는 뒷자리
+        _ => {
+            let idx = if parts.len() == 3 { 1 } else { parts.len() - 1 };
+            parts[idx] = hide(&parts[idx]);
+        }
+    }
+    parts.join("-")
+}
+
+// 화면과 내보내기에 쓰는 값: 필드 설정에서 가리지 않기로 했으면 그대로
+pub(crate) fn display(field: &Field, value: &str) -> String {
+    if field.unmasked || value.trim().is_empty() {
+        value.to_string()
+    } else {
+        mask(&field.field_type, value)
+    }
+}
+
+// 내보내기용으로 가린 행
+pub(crate) fn mask_rows(structure: &CustomStructure, rows: &[Row]) -> Vec<Row> {
+    let mut rows = rows.to_vec();
+    for field in structure
+        .fields
+        .iter()
+        .filter(|f| is_identifier(&f.field_type))
+    {
+        for row in &mut rows {
+            if let Some(field_value) = row.values.get_mut(&field.id) {
+                field_value.value = display(field, &field_value.value);
+            }
+        }
+    }
+    rows
+}
+
+// 새 구조체 타입 추정: 하이픈을 넣어 쓴 값만 (하이픈 없는 숫자는 숫자로 본다)
+pub(crate) fn infer(values: &[&str]) -> Option<FieldType> {
+    if !values.iter().all(|v| v.contains('-')) {
+        return None;
+    }
+    [
+        FieldType::ResidentNumber,
+        FieldType::BusinessNumber,
+        FieldType::Phone,
+    ]
+    .into_iter()
+    .find(|field_type| values.iter().all(|v| normalize(field_type, v).is_ok()))
+}
+
+// 구조체 편집기: 가리기 설정
+pub(crate) fn mask_editor(ui: &mut egui::Ui, field: &mut Field) {
+    let mut masked = !field.unmasked;
+    if ui
+        .checkbox(&mut masked, "표와 내보내기에서 가리기")
+        .on_hover_text("가린 값은 셀의 👁 버튼을 눌러야 보이고 고칠 수 있습니다")
+        .changed()
+    {
+        field.unmasked = !masked;
+    }
+}
+
+// 식별 번호 셀: 가린 값과 👁 버튼, 누르면 원래 값을 보여 주고 고칠 수 있다. 다 쓰면 하이픈을 넣는다
+pub(crate) fn identifier_cell(
+    ui: &mut egui::Ui,
+    id: impl std::hash::Hash,
+    field: &Field,
+    value: &mut String,
+) -> Response {
+    let id = ui.make_persistent_id(id).with("identifier_cell");
+    let revealed = ui.data(|d| d.get_temp::<bool>(id)).unwrap_or(false);
+    if !field.unmasked && !revealed && !value.trim().is_empty() {
+        return ui
+            .horizontal(|ui| {
+                let response = ui.label(mask(&field.field_type, value));
+                if ui.small_button("👁").on_hover_text("원래 값 보기").clicked() {
+                    ui.data_mut(|d| d.insert_temp(id, true));
+                }
+                response
+            })
+            .inner;
+    }
+    ui.horizontal(|ui| {
+        let hint = match field.field_type {
+            FieldType::ResidentNumber => "900101-1234567",
+            FieldType::BusinessNumber => "123-45-67890",
+            _ => "010-1234-5678",
+        };
+        let response = ui.add(
+            egui::TextEdit::singleline(value)
+                .hint_text(hint)
+                .desired_width(130.0),
+        );
+        if response.lost_focus() {
+            // 형식이 맞지 않는 값은 그대로 두어 제약 조건 위반으로 보인다
+            if let Ok(formatted) = normalize(&field.field_type, value) {
+                *value = formatted;
+            }
+        }
+        if !field.unmasked
+            && revealed
+            && ui.small_button("🙈").on_hover_text("다시 가리기").clicked()
+        {
+            ui.data_mut(|d| d.remove::<bool>(id));
+        }
+        response
+    })
+    .inner
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn digits_of(text: &str) -> Vec<u32> {
+        text.chars().filter_map(|c| c.to_digit(10)).collect()
+    }
+
+    #[test]
+    fn business_check_digit_matches_last_digit() {
+        assert_eq!(business_check_digit(&digits_of("124810099")), 8);
+        assert_eq!(business_check_digit(&digits_of("220810156")), 2);
+        // 9번째 자리 x5가 두 자리일 때 십의 자리를 더한다
+        assert_eq!(business_check_digit(&digits_of("000000009")), 1);
+        assert_eq!(business_check_digit(&digits_of("000000000")), 0);
+    }
+
+    #[test]
+    fn normalize_business_numbers() {
+        assert_eq!(normalize_business("1248100998").unwrap(), "124-81-00998");
+        assert_eq!(normalize_business("124 81 00998").unwrap(), "124-81-00998");
+        assert!(normalize_business("124-81-00999").is_err());
+        assert!(normalize_business("124-81-0099").is_err());
+    }
+
+    #[test]
+    fn normalize_resident_numbers() {
+        assert_eq!(
+            normalize_resident("9001011234567").unwrap(),
+            "900101-1234567"
+        );
+        assert_eq!(
+            normalize_resident("900101-2234567").unwrap(),
+            "900101-2234567"
+        );
+        // 성별 자리로 세기를 정해 윤년을 본다: 2000년은 윤년, 1900년은 아니다
+        assert!(normalize_resident("000229-3234567").is_ok());
+        assert!(normalize_resident("000229-1234567").is_err());
+        assert!(normalize_resident("901301-1234567").is_err());
+        assert!(normalize_resident("900132-1234567").is_err());
+        assert!(normalize_resident("900101-123456").is_err());
+        assert!(normalize_resident("900101-123456a").is_err());
+    }
+
+    #[test]
+    fn normalize_phone_numbers() {
+        assert_eq!(normalize_phone("01012345678").unwrap(), "010-1234-5678");
+        assert_eq!(normalize_phone("010.1234.5678").unwrap(), "010-1234-5678");
+        assert_eq!(
+            normalize_phone("+82 10-1234-5678").unwrap(),
+            "010-1234-5678"
+        );
+        assert_eq!(normalize_phone("+82-2-123-4567").unwrap(), "02-123-4567");
+        assert_eq!(normalize_phone("021234567").unwrap(), "02-123-4567");
+        assert_eq!(normalize_phone("0212345678").unwrap(), "02-1234-5678");
+        assert_eq!(normalize_phone("0311234567").unwrap(), "031-123-4567");
+        assert_eq!(normalize_phone("050512345678").unwrap(), "0505-1234-5678");
+        assert_eq!(normalize_phone("1588-1234").unwrap(), "1588-1234");
+        assert!(normalize_phone("12345").is_err());
+        assert!(normalize_phone("010-1234-567a").is_err());
+    }
+
+    #[test]
+    fn mask_hides_trailing_digits() {
+        assert_eq!(
+            mask(&FieldType::ResidentNumber, "9001011234567"),
+            "900101-1******"
+        );
+        assert_eq!(
+            mask(&FieldType::BusinessNumber, "124-81-00998"),
+            "124-81-*****"
+        );
+        assert_eq!(mask(&FieldType::Phone, "010-1234-5678"), "010-****-5678");
+        assert_eq!(mask(&FieldType::Phone, "02-123-4567"), "02-***-4567");
+        assert_eq!(mask(&FieldType::Phone, "1588-1234"), "1588-****");
+        // 형식이 맞지 않는 값은 앞 세 글자만
+        assert_eq!(mask(&FieldType::Phone, "123456"), "123***");
+        assert!(is_masked(&mask(
+            &FieldType::ResidentNumber,
+            "900101-1234567"
+        )));
+    }
+}
@@ -0,0 +1,71 @@
+// 이전 버전 파일 형식을 현재 형식으로 변환
+use crate::{CustomCategory, CustomStructure, ERPData, FieldValue, Row, ERP_DATA_VERSION};
+use serde::Deserialize;
+use std::collections::HashMap;
+
+// 버전 0: 구조체 이름 -> 필드 이름 -> 값
+#[derive(Deserialize)]
+struct LegacyERPData {
+    #[serde(default)]
+    structure_name: String,
+    #[serde(default)]
+    data: HashMap<String, Vec<HashMap<String, FieldValue>>>,
+}
+
+// erp_data.json 읽기. 레거시 형식이면 ID 기반으로 변환하고 true 반환
+pub(crate) fn parse_erp_data(
+    data: &str,
+    structures: &[CustomCategory],
+) -> Result<(ERPData, bool), serde_json::Error> {
+    let raw: serde_json::Value = serde_json::from_str(data)?;
+    let version = raw.get("version").and_then(|v| v.as_u64()).unwrap_or(0);
+    if version >= ERP_DATA_VERSION as u64 {
+        return Ok((serde_json::from_value(raw)?, false));
+    }
+
+    let legacy: LegacyERPData = serde_json::from_value(raw)?;
+    Ok((upgrade_erp_data(legacy, structures), true))
+}
+
+fn upgrade_erp_data(legacy: LegacyERPData, structures: &[CustomCategory]) -> ERPData {
+    let mut erp_data = ERPData {
+        version: ERP_DATA_VERSION,
+        structure_name: legacy.structure_name,
+        data: HashMap::new(),
+    };
+
+    for (structure_name, rows) in legacy.data {
+        let structure = find_by_name(structures, &structure_name);
+        // 구조체를 찾지 못하면 데이터를 버리지 않고 이름 키 그대로 보관
+        let key = structure
+            .map(|s| s.id.clone())
+            .unwrap_or_else(|| structure_name.clone());
+
+        let rows = rows
+            .into_iter()
+            .map(|old_row| {
+                let mut row = Row::new();
+                for (field_name, value) in old_row {
+                    let field_key = structure
+                        .and_then(|s| s.field_by_name(&field_name))
+                        .map(|f| f.id.clone())
+                        .unwrap_or(field_name);
+                    row.values.insert(field_key, value);
+                }
+                row
+            })
+            .collect();
+
+        erp_data.data.insert(key, rows);
+    }
+
+    erp_data
+}
+
+fn find_by_name<'a>(structures: &'a [CustomCategory], name: &str) -> Option<&'a CustomStructure> {
+    structures
+        .iter()
+        .flat_map(|c| c.subcategories.iter())
+        .flat_map(|s| s.structures.iter())
+        .find(|s| s.name == name)
+}
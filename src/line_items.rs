@@ -0,0 +1,488 @@
+// 하위 표(줄 항목): 송장, 발주서, 전표처럼 머리글 행 아래에 여러 줄이 붙는 문서.
+// 줄 목록은 상위 필드 값에 JSON으로 저장하므로 저장소 형식은 그대로
+use crate::csv_import::empty_row;
+use crate::currency::{self, currency_editor, currency_of, parse_decimal};
+use crate::formula::{check_formulas, formula_editor, Formulas};
+use crate::workspace::sanitize_file_name;
+use crate::{value_cell, CustomStructure, ERPApp, Field, FieldType, FieldValue, Row};
+use eframe::egui;
+use egui::{Context, ScrollArea, Vec2};
+use rust_decimal::{Decimal, RoundingStrategy};
+use serde::{Deserialize, Serialize};
+use std::path::{Path, PathBuf};
+
+// 내보내기에서 상위 행과 줄을 잇는 열
+const ID_FIELD: &str = "__id";
+const ID_COLUMN: &str = "ID";
+const PARENT_FIELD: &str = "__parent";
+const PARENT_COLUMN: &str = "상위 ID";
+
+// 하위 표의 필드로 쓸 수 있는 타입
+const COLUMN_TYPES: [(FieldType, &str); 8] = [
+    (FieldType::Text, "텍스트"),
+    (FieldType::Number, "숫자"),
+    (FieldType::Currency, "통화"),
+    (FieldType::Date, "날짜"),
+    (FieldType::DateTime, "날짜와 시간"),
+    (FieldType::Time, "시간"),
+    (FieldType::Boolean, "참/거짓"),
+    (FieldType::Formula, "수식"),
+];
+
+// 숫자 필드를 하위 표의 합계로 채우는 설정. column_id가 비어 있으면 줄 수
+#[derive(Clone, Default, Serialize, Deserialize, PartialEq, Debug)]
+pub(crate) struct Subtotal {
+    pub(crate) table_field_id: String,
+    #[serde(default)]
+    pub(crate) column_id: String,
+}
+
+pub(crate) fn parse_lines(value: &str) -> Result<Vec<Row>, String> {
+    if value.trim().is_empty() {
+        return Ok(Vec::new());
+    }
+    serde_json::from_str(value).map_err(|e| format!("줄 항목을 읽을 수 없습니다: {}", e))
+}
+
+fn lines_to_value(lines: &[Row]) -> String {
+    if lines.is_empty() {
+        return String::new();
+    }
+    serde_json::to_string(lines).unwrap_or_default()
+}
+
+pub(crate) fn line_count(value: &str) -> usize {
+    parse_lines(value).map_or(0, |lines| lines.len())
+}
+
+// 하위 표의 필드를 구조체처럼 다루기 위한 구조체 (ID는 상위 필드 ID)
+fn line_structure(field: &Field) -> CustomStructure {
+    CustomStructure {
+        id: field.id.clone(),
+        name: field.name.clone(),
+        fields: field.columns.clone(),
+        version: 0,
+    }
+}
+
+fn number(row: &Row, field_id: &str) -> Decimal {
+    row.values
+        .get(field_id)
+        .and_then(|v| parse_decimal(&v.value))
+        .unwrap_or_default()
+}
+
+// 합계/계산 값. 끝없는 소수(1/3)는 소수점 아래 10자리까지, 끝의 0은 뺀다
+pub(crate) fn format_total(total: Decimal) -> String {
+    total
+        .round_dp_with_strategy(10, RoundingStrategy::MidpointAwayFromZero)
+        .normalize()
+        .to_string()
+}
+
+fn column_total(lines: &[Row], column_id: &str) -> String {
+    if column_id.is_empty() {
+        return lines.len().to_string();
+    }
+    format_total(lines.iter().map(|line| number(line, column_id)).sum())
+}
+
+fn is_numeric(field_type: &FieldType) -> bool {
+    matches!(
+        field_type,
+        FieldType::Number | FieldType::Currency | FieldType::Formula
+    )
+}
+
+// 합계 필드를 하위 표 값으로 다시 계산. 바뀌었으면 true
+pub(crate) fn apply_subtotals(structure: &CustomStructure, row: &mut Row) -> bool {
+    let mut changed = false;
+    for field in &structure.fields {
+        let Some(subtotal) = &field.subtotal else {
+            continue;
+        };
+        let lines = row
+            .values
+            .get(&subtotal.table_field_id)
+            .and_then(|v| parse_lines(&v.value).ok())
+            .unwrap_or_default();
+        let mut total = column_total(&lines, &subtotal.column_id);
+        if field.field_type == FieldType::Currency {
+            total = currency::normalize_field(field, &total).unwrap_or(total);
+        }
+        let field_value = row.values.entry(field.id.clone()).or_default();
+        if field_value.value != total {
+            field_value.value = total;
+            field_value.field_type = field.field_type.clone();
+            changed = true;
+        }
+    }
+    changed
+}
+
+fn id_field(id: &str, name: &str) -> Field {
+    Field {
+        id: id.to_string(),
+        name: name.to_string(),
+        ..Default::default()
+    }
+}
+
+fn text_value(value: &str) -> FieldValue {
+    FieldValue {
+        value: value.to_string(),
+        field_type: FieldType::Text,
+    }
+}
+
+// 내보내기용으로 나눈 하위 표 하나: 상위 ID 열 + 하위 표 필드
+pub(crate) struct LineTable {
+    pub(crate) field_name: String,
+    pub(crate) structure: CustomStructure,
+    pub(crate) rows: Vec<Row>,
+}
+
+// 하위 표가 있는 구조체를 ID 열을 붙인 본 표와 하위 표별 표로 나눈다. 하위 표가 없으면 None
+pub(crate) fn split_line_tables(
+    structure: &CustomStructure,
+    rows: &[Row],
+) -> Option<(CustomStructure, Vec<Row>, Vec<LineTable>)> {
+    let table_fields: Vec<&Field> = structure
+        .fields
+        .iter()
+        .filter(|f| f.field_type == FieldType::Table)
+        .collect();
+    if table_fields.is_empty() {
+        return None;
+    }
+
+    let mut main = structure.clone();
+    main.fields.retain(|f| f.field_type != FieldType::Table);
+    main.fields.insert(0, id_field(ID_FIELD, ID_COLUMN));
+    let main_rows = rows
+        .iter()
+        .map(|row| {
+            let mut row = row.clone();
+            row.values.insert(ID_FIELD.to_string(), text_value(&row.id));
+            row
+        })
+        .collect();
+
+    let tables = table_fields
+        .into_iter()
+        .map(|field| {
+            let mut lines_structure = line_structure(field);
+            lines_structure.name = format!("{} - {}", structure.name, field.name);
+            lines_structure
+                .fields
+                .insert(0, id_field(PARENT_FIELD, PARENT_COLUMN));
+            let lines = rows
+                .iter()
+                .flat_map(|row| {
+                    let value = row.values.get(&field.id).map(|v| v.value.as_str());
+                    parse_lines(value.unwrap_or_default())
+                        .unwrap_or_default()
+                        .into_iter()
+                        .map(|mut line| {
+                            line.values
+                                .insert(PARENT_FIELD.to_string(), text_value(&row.id));
+                            line
+                        })
+                })
+                .collect();
+            LineTable {
+                field_name: field.name.clone(),
+                structure: lines_structure,
+                rows: lines,
+            }
+        })
+        .collect();
+    Some((main, main_rows, tables))
+}
+
+// CSV 내보내기에서 하위 표 파일 경로: "파일명_필드명.csv"
+pub(crate) fn table_path(path: &Path, field_name: &str) -> PathBuf {
+    let stem = path
+        .file_stem()
+        .map(|s| s.to_string_lossy().to_string())
+        .unwrap_or_default();
+    let mut name = format!("{}_{}", stem, sanitize_file_name(field_name));
+    if let Some(extension) = path.extension() {
+        name.push('.');
+        name.push_str(&extension.to_string_lossy());
+    }
+    path.with_file_name(name)
+}
+
+// 구조체 편집기의 하위 표 필드 목록
+pub(crate) fn columns_editor(ui: &mut egui::Ui, idx: usize, field: &mut Field) {
+    egui::CollapsingHeader::new(format!("하위 표 필드 ({})", field.columns.len()))
+        .id_salt(("line_columns", idx))
+        .default_open(field.columns.is_empty())
+        .show(ui, |ui| {
+            let mut move_up = None;
+            let mut remove = None;
+            let count = field.columns.len();
+            let formula_errors = check_formulas(&line_structure(field));
+            for (i, column) in field.columns.iter_mut().enumerate() {
+                ui.horizontal(|ui| {
+                    ui.add(egui::TextEdit::singleline(&mut column.name).desired_width(150.0));
+                    let selected = COLUMN_TYPES
+                        .iter()
+                        .find(|(t, _)| *t == column.field_type)
+                        .map_or("", |(_, label)| label);
+                    egui::ComboBox::from_id_salt(("line_column_type", idx, i))
+                        .selected_text(selected)
+                        .show_ui(ui, |ui| {
+                            for (field_type, label) in COLUMN_TYPES {
+                                ui.selectable_value(&mut column.field_type, field_type, label);
+                            }
+                        });
+                    if ui.button("⬆").clicked() && i > 0 {
+                        move_up = Some(i);
+                    }
+                    if ui.button("⬇").clicked() && i + 1 < count {
+                        move_up = Some(i + 1);
+                    }
+                    if ui.button("🗑️").clicked() {
+                        remove = Some(i);
+                    }
+                });
+                if column.field_type == FieldType::Formula {
+                    let error = formula_errors.get(&column.id);
+                    formula_editor(ui, ("line_formula", idx, i), column, error);
+                }
+                if column.field_type == FieldType::Currency {
+                    currency_editor(ui, ("line_currency", idx, i), column);
+                }
+            }
+            if let Some(i) = move_up {
+                field.columns.swap(i - 1, i);
+            }
+            if let Some(i) = remove {
+                field.columns.remove(i);
+            }
+            if ui.button("➕ 필드 추가").clicked() {
+                field.columns.push(Field {
+                    name: format!("필드 {}", count + 1),
+                    ..Field::new()
+                });
+            }
+        });
+}
+
+// 숫자 필드를 하위 표의 합계/줄 수로 채우는 설정
+pub(crate) fn subtotal_editor(
+    ui: &mut egui::Ui,
+    idx: usize,
+    field: &mut Field,
+    table_fields: &[Field],
+) {
+    if table_fields.is_empty() {
+        return;
+    }
+    let label = |subtotal: &Subtotal| {
+        let Some(table) = table_fields
+            .iter()
+            .find(|t| t.id == subtotal.table_field_id)
+        else {
+            return "없음".to_string();
+        };
+        match table.columns.iter().find(|c| c.id == subtotal.column_id) {
+            Some(column) => format!("{}.{} 합계", table.name, column.name),
+            None => format!("{} 줄 수", table.name),
+        }
+    };
+    ui.horizontal(|ui| {
+        ui.label("하위 표 집계:");
+        let selected = field.subtotal.as_ref().map_or("없음".to_string(), &label);
+        egui::ComboBox::from_id_salt(("field_subtotal", idx))
+            .selected_text(selected)
+            .show_ui(ui, |ui| {
+                ui.selectable_value(&mut field.subtotal, None, "없음");
+                for table in table_fields {
+                    let choices = std::iter::once(String::new()).chain(
+                        table
+                            .columns
+                            .iter()
+                            .filter(|c| is_numeric(&c.field_type))
+                            .map(|c| c.id.clone()),
+                    );
+                    for column_id in choices {
+                        let choice = Subtotal {
+                            table_field_id: table.id.clone(),
+                            column_id,
+                        };
+                        let text = label(&choice);
+                        ui.selectable_value(&mut field.subtotal, Some(choice), text);
+                    }
+                }
+            });
+    });
+}
+
+// 열려 있는 줄 항목 편집기
+pub(crate) struct LineEditor {
+    pub(crate) structure_id: String,
+    pub(crate) row_id: String,
+    pub(crate) field_id: String,
+}
+
+impl ERPApp {
+    pub(crate) fn render_line_editor(&mut self, ctx: &Context) {
+        let Some(editor) = &self.line_editor else {
+            return;
+        };
+        let Some(structure) = self.find_structure(&editor.structure_id) else {
+            self.line_editor = None;
+            return;
+        };
+        let Some(field) = structure
+            .fields
+            .iter()
+            .find(|f| f.id == editor.field_id && f.field_type == FieldType::Table)
+            .cloned()
+        else {
+            self.line_editor = None;
+            return;
+        };
+        let Some(mut row) = self
+            .erp_data
+            .data
+            .get(&structure.id)
+            .and_then(|rows| rows.iter().find(|r| r.id == editor.row_id))
+            .cloned()
+        else {
+            self.line_editor = None;
+            return;
+        };
+
+        let value = row
+            .values
+            .get(&field.id)
+            .map(|v| v.value.clone())
+            .unwrap_or_default();
+        let (mut lines, error) = match parse_lines(&value) {
+            Ok(lines) => (lines, None),
+            Err(e) => (Vec::new(), Some(e)),
+        };
+        let lines_structure = line_structure(&field);
+        let formulas = Formulas::compile(&lines_structure);
+        let before = lines.clone();
+        let mut open = true;
+
+        egui::Window::new(format!("{} - {}", structure.name, field.name))
+            .id(egui::Id::new("line_editor"))
+            .open(&mut open)
+            .resizable(true)
+            .default_width(600.0)
+            .show(ctx, |ui| {
+                if let Some(error) = &error {
+                    // 손상된 값은 덮어쓰지 않도록 편집을 막는다
+                    ui.colored_label(egui::Color32::RED, error);
+                    return;
+                }
+                if field.columns.is_empty() {
+                    ui.label("구조체 편집기에서 하위 표 필드를 먼저 추가해주세요.");
+                    return;
+                }
+
+                ScrollArea::vertical().max_height(400.0).show(ui, |ui| {
+                    egui::Grid::new("line_items_table")
+                        .striped(true)
+                        .spacing(Vec2::new(10.0, 4.0))
+                        .show(ui, |ui| {
+                            ui.label("No.");
+                            for column in &field.columns {
+                                ui.label(&column.name);
+                            }
+                            ui.label("관리");
+                            ui.end_row();
+
+                            let mut line_to_remove = None;
+                            for (line_idx, line) in lines.iter_mut().enumerate() {
+                                ui.label((line_idx + 1).to_string());
+                                let formula_errors = formulas.apply(line);
+                                for column in &field.columns {
+                                    if let Some(error) = formula_errors.get(&column.id) {
+                                        ui.colored_label(egui::Color32::RED, "#오류")
+                                            .on_hover_text(error);
+                                        continue;
+                                    }
+                                    let field_value = line
+                                        .values
+                                        .entry(column.id.clone())
+                                        .or_insert_with(|| FieldValue {
+                                            value: String::new(),
+                                            field_type: column.field_type.clone(),
+                                        });
+                                    value_cell(
+                                        ui,
+                                        ("line_cell", &line.id, &column.id),
+                                        column,
+                                        field_value,
+                                    );
+                                }
+                                if ui.button("🗑️").clicked() {
+                                    line_to_remove = Some(line_idx);
+                                }
+                                ui.end_row();
+                            }
+                            if let Some(idx) = line_to_remove {
+                                lines.remove(idx);
+                            }
+
+                            // 숫자 필드의 합계 행
+                            if !lines.is_empty() {
+                                ui.strong("합계");
+                                for column in &field.columns {
+                                    if column.field_type == FieldType::Currency {
+                                        let total = column_total(&lines, &column.id);
+                                        let currency = currency_of(column);
+                                        ui.strong(currency::display(&currency, &total));
+                                    } else if is_numeric(&column.field_type) {
+                                        ui.strong(column_total(&lines, &column.id));
+                                    } else {
+                                        ui.label("");
+                                    }
+                                }
+                                ui.end_row();
+                            }
+                        });
+                });
+
+                ui.separator();
+                if ui.button("➕ 줄 추가").clicked() {
+                    lines.push(empty_row(&lines_structure));
+                }
+            });
+
+        if lines != before {
+            if let Some(field_value) = row.values.get_mut(&field.id) {
+                field_value.value = lines_to_value(&lines);
+            } else {
+                row.values.insert(
+                    field.id.clone(),
+                    FieldValue {
+                        value: lines_to_value(&lines),
+                        field_type: FieldType::Table,
+                    },
+                );
+            }
+            apply_subtotals(&structure, &mut row);
+            self.save_row(&structure, &row);
+            if let Some(stored) = self
+                .erp_data
+                .data
+                .get_mut(&structure.id)
+                .and_then(|rows| rows.iter_mut().find(|r| r.id == row.id))
+            {
+                *stored = row;
+            }
+        }
+        if !open {
+            self.line_editor = None;
+            self.save_to_csv(&structure.id);
+        }
+    }
+}
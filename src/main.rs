@@ -1,35 +1,96 @@
-use calamine::open_workbook;
-use calamine::DataType;
-use calamine::Reader;
-use calamine::Xlsx;
 use eframe::egui;
 use egui::{Context, FontData, FontDefinitions, FontFamily, ScrollArea, Ui, Vec2};
-use rfd::FileDialog;
 use serde::{Deserialize, Serialize};
-use serde_json;
-use std::collections::HashMap;
+use std::collections::{HashMap, HashSet};
 use std::error::Error;
-use std::fs;
+use std::path::{Path, PathBuf};
+use std::rc::Rc;
+use std::time::Instant;
+
+mod attachments;
+mod category_workbook;
+mod csv_format;
+mod csv_import;
+mod currency;
+mod dates;
+mod defaults;
+mod excel_export;
+mod excel_import;
+mod exchange;
+mod formula;
+mod korean_id;
+mod legacy;
+mod line_items;
+mod reconcile;
+mod reference;
+mod rollup;
+mod row_view;
+mod safe_file;
+mod schema;
+mod schema_inference;
+mod select;
+mod storage;
+mod validation;
+mod workspace;
+
+use safe_file::CorruptFile;
+use storage::{AppSettings, Storage, StorageKind};
+use workspace::{RecentWorkspaces, Workspace};
 
-use xlsxwriter::Workbook;
 // 데이터 저장을 위한 구조체 수정
 #[derive(Clone, Default, Serialize, Deserialize, PartialEq)]
 struct FieldValue {
     value: String,
     field_type: FieldType,
 }
+
+// 한 행의 데이터: 고유 ID와 필드 ID -> 값
+#[derive(Clone, Default, Serialize, Deserialize, PartialEq)]
+struct Row {
+    id: String,
+    values: HashMap<String, FieldValue>, // field_id -> value
+}
+
+impl Row {
+    fn new() -> Self {
+        Row {
+            id: new_id(),
+            values: HashMap::new(),
+        }
+    }
+}
+
 #[derive(Clone, Default, Serialize, Deserialize)]
 struct ERPData {
+    #[serde(default)]
+    version: u32,
     structure_name: String,
-    data: HashMap<String, Vec<HashMap<String, FieldValue>>>, // structure_name -> rows
+    data: HashMap<String, Vec<Row>>, // structure_id -> rows
 }
 
+// erp_data.json 형식 버전 (0: 이름 기반 레거시 형식)
+const ERP_DATA_VERSION: u32 = 1;
+
 #[derive(Clone, Serialize, Deserialize, PartialEq, Debug)]
 enum FieldType {
     Text,
     Number,
-    Date,
+    Currency, // 고정 소수 금액 (통화 코드와 소수 자릿수는 필드 설정)
+    Date,     // YYYY-MM-DD
+    DateTime, // 시간대를 포함한 RFC 3339 (YYYY-MM-DDTHH:MM:SS+09:00)
+    Time,     // HH:MM:SS
     Boolean,
+    Select,         // 항목 목록 중 하나
+    MultiSelect,    // 항목 목록 중 여러 개
+    Reference,      // 다른 구조체의 행 (값은 행 ID)
+    Table,          // 하위 표의 줄 목록 (값은 JSON)
+    Formula,        // 같은 행의 다른 필드로 계산 (값은 계산 결과)
+    Rollup,         // 다른 구조체의 관련 행 집계
+    Lookup,         // 참조한 행의 필드 값
+    Attachment,     // 첨부 파일 목록 (값은 JSON, 파일은 워크스페이스 attachments 폴더)
+    ResidentNumber, // 주민등록번호 (900101-1234567)
+    BusinessNumber, // 사업자등록번호 (123-45-67890)
+    Phone,          // 전화번호 (010-1234-5678)
 }
 
 impl Default for FieldType {
@@ -40,14 +101,92 @@ impl Default for FieldType {
 
 #[derive(Clone, Default, Serialize, Deserialize, PartialEq, Debug)]
 struct Field {
+    #[serde(default)]
+    id: String,
     name: String,
     field_type: FieldType,
+    #[serde(
+        default,
+        skip_serializing_if = "validation::FieldConstraints::is_empty"
+    )]
+    constraints: validation::FieldConstraints,
+    #[serde(default, skip_serializing_if = "defaults::DefaultValue::is_none")]
+    default: defaults::DefaultValue,
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    options: Vec<select::SelectOption>, // 선택/다중 선택 필드의 항목
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    reference: Option<reference::ReferenceTarget>, // 참조 필드의 대상 구조체
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    columns: Vec<Field>, // 하위 표 필드의 줄 항목 필드
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    subtotal: Option<line_items::Subtotal>, // 하위 표 합계로 채우는 숫자 필드
+    #[serde(default, skip_serializing_if = "String::is_empty")]
+    formula: String, // 수식 필드의 식
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    rollup: Option<rollup::Rollup>, // 집계 필드 설정
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    lookup: Option<rollup::Lookup>, // 조회 필드 설정
+    #[serde(default, skip_serializing_if = "dates::DateFormat::is_default")]
+    date_format: dates::DateFormat, // 날짜/날짜와 시간 필드의 표시 형식
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    currency: Option<currency::Currency>, // 통화 필드의 통화와 소수 자릿수
+    #[serde(default, skip_serializing_if = "std::ops::Not::not")]
+    unmasked: bool, // 식별 번호 필드를 가리지 않고 표시
+}
+
+impl Field {
+    fn new() -> Self {
+        Field {
+            id: new_id(),
+            ..Default::default()
+        }
+    }
 }
 
 #[derive(Clone, Default, Serialize, Deserialize, PartialEq, Debug)]
 struct CustomStructure {
+    #[serde(default)]
+    id: String,
     name: String,
     fields: Vec<Field>,
+    #[serde(default)]
+    version: u32, // 필드 변경 시마다 증가
+}
+
+impl CustomStructure {
+    fn new() -> Self {
+        CustomStructure {
+            id: new_id(),
+            ..Default::default()
+        }
+    }
+
+    // ID가 없는 구조체/필드에 새 ID 부여, 변경 여부 반환
+    fn ensure_ids(&mut self) -> bool {
+        let mut changed = false;
+        if self.id.is_empty() {
+            self.id = new_id();
+            changed = true;
+        }
+        for field in &mut self.fields {
+            if field.id.is_empty() {
+                field.id = new_id();
+                changed = true;
+            }
+            // 하위 표의 줄 항목 필드
+            for column in &mut field.columns {
+                if column.id.is_empty() {
+                    column.id = new_id();
+                    changed = true;
+                }
+            }
+        }
+        changed
+    }
+
+    fn field_by_name(&self, name: &str) -> Option<&Field> {
+        self.fields.iter().find(|f| f.name == name)
+    }
 }
 
 #[derive(Clone, Default, Serialize, Deserialize, PartialEq, Debug)]
@@ -62,6 +201,69 @@ struct CustomCategory {
     subcategories: Vec<SubCategory>,
 }
 
+fn new_id() -> String {
+    uuid::Uuid::new_v4().to_string()
+}
+
+// 표의 기본 셀 편집 (본 표와 줄 항목 편집기에서 공용)
+fn value_cell(
+    ui: &mut Ui,
+    id: impl std::hash::Hash,
+    field: &Field,
+    field_value: &mut FieldValue,
+) -> egui::Response {
+    match field_value.field_type {
+        FieldType::Text => {
+            let mut value = field_value.value.clone();
+            let mut edit = egui::TextEdit::singleline(&mut value);
+            if let Some(max) = field.constraints.max_length {
+                edit = edit.char_limit(max);
+            }
+            let response = ui.add(edit);
+            if response.changed() {
+                field_value.value = value;
+            }
+            response
+        }
+        FieldType::Number => {
+            let mut value = field_value.value.parse::<f64>().unwrap_or(0.0);
+            let response = ui.add(egui::DragValue::new(&mut value));
+            if response.changed() {
+                field_value.value = value.to_string();
+            }
+            response
+        }
+        FieldType::Currency => currency::currency_cell(ui, id, field, &mut field_value.value),
+        FieldType::Date => dates::date_cell(ui, id, field, &mut field_value.value),
+        FieldType::DateTime => dates::datetime_cell(ui, id, field, &mut field_value.value),
+        FieldType::Time => dates::time_cell(ui, &mut field_value.value),
+        FieldType::Boolean => {
+            let mut value = field_value.value == "true";
+            let response = ui.checkbox(&mut value, "");
+            if response.changed() {
+                field_value.value = value.to_string();
+            }
+            response
+        }
+        FieldType::Select => {
+            select::select_cell(ui, ("select_cell", id), field, &mut field_value.value)
+        }
+        FieldType::MultiSelect => {
+            select::multi_select_cell(ui, ("multi_select_cell", id), field, &mut field_value.value)
+        }
+        FieldType::ResidentNumber | FieldType::BusinessNumber | FieldType::Phone => {
+            korean_id::identifier_cell(ui, id, field, &mut field_value.value)
+        }
+        // 본 표에서 따로 그리는 타입과 읽기 전용인 수식
+        FieldType::Reference
+        | FieldType::Table
+        | FieldType::Attachment
+        | FieldType::Formula
+        | FieldType::Rollup
+        | FieldType::Lookup => ui.label(&field_value.value),
+    }
+}
+
 #[derive(Default)]
 struct ERPApp {
     custom_structures: Vec<CustomCategory>,
@@ -70,12 +272,33 @@ struct ERPApp {
     show_setting_panel: bool,
     show_structure_editor: bool,
     erp_data: ERPData,
-    selected_structure: Option<String>,
+    selected_structure: Option<String>, // 선택된 구조체 ID
     selected_category: Option<String>,
     expanded_categories: HashMap<String, bool>,
     expanded_subcategories: HashMap<String, bool>, // 서브카테고리 확장 상태
     selected_tab: String,
     show_category_selector: bool, // 카테고리 선택 팝업 표시 여부
+    pending_migration: Option<schema::PendingMigration>, // 확인 대기 중인 구조체 변경
+    storage: Box<dyn Storage>,
+    settings: AppSettings,
+    recovery: Vec<CorruptFile>, // 복구 여부를 묻는 손상 파일, 남아 있는 동안 저장 중지
+    workspace: Option<Workspace>, // 열린 워크스페이스, 없으면 선택 화면 표시
+    recent_workspaces: RecentWorkspaces,
+    backup_mismatches: Vec<reconcile::BackupMismatch>, // 저장소와 내용이 다른 CSV 백업
+    csv_import: Option<csv_import::CsvImport>,         // 열 매핑 확인 중인 CSV 가져오기
+    csv_export: Option<csv_format::CsvExport>,
+    excel_import: Option<excel_import::ExcelImport>, // 시트/매핑/방식 확인 중인 Excel 불러오기
+    category_import: Option<category_workbook::CategoryImport>, // 확인 중인 카테고리 통합 문서 가져오기
+    structure_source: Option<schema_inference::StructureSource>, // 편집 중인 새 구조체를 만든 파일
+    delete_error: Option<String>, // 참조 규칙 때문에 삭제하지 못한 이유
+    focus_row: Option<String>,    // 참조를 따라 이동한 행 (강조 표시)
+    scroll_to_focus: bool,
+    line_editor: Option<line_items::LineEditor>, // 열려 있는 줄 항목 편집기
+    related_dirty: HashSet<String>,              // 행이 바뀌어 집계/조회 필드를 다시 계산할 구조체
+    grid_views: HashMap<String, row_view::GridView>, // 구조체별 표 정렬과 필터
+    backup_dirty: HashMap<String, Instant>, // 표에서 고쳐 CSV 백업을 다시 써야 하는 구조체 (마지막 수정 시각)
+    formulas: HashMap<String, Rc<formula::Formulas>>, // 구조체별로 파싱해 둔 수식, 구조체를 저장하면 다시 만든다
+    constraints: HashMap<String, Rc<validation::CompiledConstraints>>, // 구조체별로 컴파일해 둔 제약 조건
 }
 
 impl ERPApp {
@@ -109,13 +332,52 @@ impl ERPApp {
         }
     }
 
-    fn find_structure(&self, structure_name: &str) -> Option<CustomStructure> {
+    fn render_recovery_prompt(&mut self, ctx: &Context) {
+        let Some(corrupt) = self.recovery.first().cloned() else {
+            return;
+        };
+
+        egui::Window::new("데이터 복구")
+            .collapsible(false)
+            .resizable(false)
+            .show(ctx, |ui| {
+                ui.label(format!(
+                    "{} 파일을 읽을 수 없습니다.",
+                    corrupt.path.display()
+                ));
+                ui.label(format!("오류: {}", corrupt.error));
+                if safe_file::corrupt_path(&corrupt.path).exists() {
+                    ui.label(format!(
+                        "손상된 파일은 {} 로 보관했습니다.",
+                        safe_file::corrupt_path(&corrupt.path).display()
+                    ));
+                }
+                ui.separator();
+
+                ui.horizontal(|ui| {
+                    if corrupt.has_backup && ui.button("🔄 마지막 정상 백업으로 복구").clicked()
+                    {
+                        match safe_file::restore_backup(&corrupt.path) {
+                            Ok(()) => println!("백업에서 복구했습니다: {}", corrupt.path.display()),
+                            Err(e) => eprintln!("백업 복구 실패: {}", e),
+                        }
+                        self.load_all();
+                    }
+                    if ui.button("빈 상태로 시작").clicked() {
+                        if let Err(e) = safe_file::reset(&corrupt.path, &corrupt.empty_contents) {
+                            eprintln!("파일 초기화 실패: {}", e);
+                        }
+                        self.load_all();
+                    }
+                });
+            });
+    }
+
+    fn find_structure(&self, structure_id: &str) -> Option<CustomStructure> {
         for category in &self.custom_structures {
             for subcategory in &category.subcategories {
-                if let Some(structure) = subcategory
-                    .structures
-                    .iter()
-                    .find(|s| s.name == structure_name)
+                if let Some(structure) =
+                    subcategory.structures.iter().find(|s| s.id == structure_id)
                 {
                     return Some(structure.clone());
                 }
@@ -124,213 +386,235 @@ impl ERPApp {
         None
     }
 
-    fn load_structure_data(&mut self, structure_name: &str) {
-        if let Ok(mut rdr) = csv::Reader::from_path(format!("{}.csv", structure_name)) {
-            let mut rows = Vec::new();
-
-            for result in rdr.records() {
-                if let Ok(record) = result {
-                    if let Some(structure) = self.find_structure(structure_name) {
-                        let mut row_data = HashMap::new();
-
-                        for (idx, field) in structure.fields.iter().enumerate() {
-                            let value = record.get(idx).unwrap_or_default().to_string();
-                            row_data.insert(
-                                field.name.clone(),
-                                FieldValue {
-                                    value,
-                                    field_type: field.field_type.clone(),
-                                },
-                            );
-                        }
-
-                        rows.push(row_data);
-                    }
+    // 구조체가 들어 있는 카테고리와 서브카테고리 이름
+    fn structure_location(&self, structure_id: &str) -> Option<(String, String)> {
+        for category in &self.custom_structures {
+            for subcategory in &category.subcategories {
+                if subcategory.structures.iter().any(|s| s.id == structure_id) {
+                    return Some((category.name.clone(), subcategory.name.clone()));
                 }
             }
-
-            self.erp_data.data.insert(structure_name.to_string(), rows);
-            self.save_erp_data();
-        } else {
-            self.erp_data
-                .data
-                .insert(structure_name.to_string(), Vec::new());
-            self.save_erp_data();
         }
+        None
+    }
+
+    fn load_structure_data(&mut self, structure_id: &str) {
+        let Some(structure) = self.find_structure(structure_id) else {
+            return;
+        };
+        let rows =
+            csv_import::read_csv_rows(&structure, &self.csv_path(&structure)).unwrap_or_default();
+        self.erp_data.data.insert(structure.id.clone(), rows);
+        self.save_structure_rows(&structure.id);
     }
     fn load_erp_data(&mut self) {
-        match fs::read_to_string("erp_data.json") {
-            Ok(data) => {
-                match serde_json::from_str(&data) {
-                    Ok(loaded_data) => {
-                        self.erp_data = loaded_data;
-                    }
-                    Err(e) => {
-                        eprintln!("Failed to parse ERP data: {}", e);
-                        // 파일이 손상된 경우 새로운 데이터로 초기화
-                        self.erp_data = ERPData::default();
-                    }
-                }
+        match self.storage.load_data(&self.custom_structures) {
+            Ok(loaded_data) => {
+                self.erp_data = loaded_data;
             }
-            Err(_) => {
-                // 파일이 없는 경우 새로운 데이터로 초기화
+            Err(e) => {
+                eprintln!("Failed to load ERP data: {}", e);
                 self.erp_data = ERPData::default();
-                self.save_erp_data(); // 빈 데이터 파일 생성
+                // 파일이 손상된 경우 빈 데이터로 덮어쓰지 않고 복구 여부를 묻는다
+                if let Some(corrupt) = e.downcast_ref::<CorruptFile>() {
+                    self.recovery.push(corrupt.clone());
+                }
             }
         }
     }
-    // Excel 내보내기 (파일 선택 대화상자 사용)
-    fn export_to_excel(
-        &self,
-        structure: &CustomStructure,
-    ) -> Result<(), Box<dyn std::error::Error>> {
-        if let Some(path) = rfd::FileDialog::new()
-            .add_filter("Excel Files", &["xlsx"])
-            .set_file_name(&format!("{}.xlsx", structure.name))
-            .save_file()
-        {
-            let workbook = Workbook::new(path.to_str().unwrap())?;
-            let mut sheet = workbook.add_worksheet(None)?;
 
-            // 헤더 작성
-            for (col, field) in structure.fields.iter().enumerate() {
-                sheet.write_string(0, col as u16, &field.name, None)?;
-            }
+    // 구조체 정의와 데이터를 다시 읽기. 구조체 파일이 손상되었으면 데이터는 복구 후에 읽는다
+    fn load_all(&mut self) {
+        self.recovery.clear();
+        self.backup_mismatches.clear();
+        self.formulas.clear();
+        self.constraints.clear();
+        self.load_custom_structures();
+        if self.recovery.is_empty() {
+            self.load_erp_data();
+        }
+        if self.recovery.is_empty() {
+            // 백업과 비교한 뒤에 값을 고쳐야 달라진 백업을 덮어쓰지 않는다
+            self.reconcile_backups();
+            self.normalize_temporal_values();
+            self.mark_all_related();
+        }
+    }
 
-            // 데이터 작성
-            if let Some(rows) = self.erp_data.data.get(&structure.name) {
-                for (row_idx, row_data) in rows.iter().enumerate() {
-                    for (col, field) in structure.fields.iter().enumerate() {
-                        if let Some(field_value) = row_data.get(&field.name) {
-                            match field_value.field_type {
-                                FieldType::Number => {
-                                    if let Ok(num) = field_value.value.parse::<f64>() {
-                                        sheet.write_number(
-                                            row_idx as u32 + 1,
-                                            col as u16,
-                                            num,
-                                            None,
-                                        )?;
-                                    } else {
-                                        sheet.write_string(
-                                            row_idx as u32 + 1,
-                                            col as u16,
-                                            &field_value.value,
-                                            None,
-                                        )?;
-                                    }
-                                }
-                                FieldType::Boolean => {
-                                    if let Ok(bool_val) = field_value.value.parse::<bool>() {
-                                        sheet.write_boolean(
-                                            row_idx as u32 + 1,
-                                            col as u16,
-                                            bool_val,
-                                            None,
-                                        )?;
-                                    } else {
-                                        sheet.write_string(
-                                            row_idx as u32 + 1,
-                                            col as u16,
-                                            &field_value.value,
-                                            None,
-                                        )?;
-                                    }
-                                }
-                                _ => {
-                                    sheet.write_string(
-                                        row_idx as u32 + 1,
-                                        col as u16,
-                                        &field_value.value,
-                                        None,
-                                    )?;
-                                }
-                            }
+    fn writes_blocked(&self) -> bool {
+        if self.workspace.is_none() {
+            eprintln!("열린 워크스페이스가 없어 저장하지 않습니다");
+            return true;
+        }
+        if !self.recovery.is_empty() {
+            eprintln!("손상된 파일 복구를 기다리는 중이라 저장하지 않습니다");
+            return true;
+        }
+        false
+    }
+    // 구조체 하나의 행 전체를 저장소에 기록
+    fn save_structure_rows(&mut self, structure_id: &str) {
+        if self.writes_blocked() {
+            return;
+        }
+        let Some(structure) = self.find_structure(structure_id) else {
+            return;
+        };
+        self.related_dirty.insert(structure.id.clone());
+        let rows = self
+            .erp_data
+            .data
+            .get(structure_id)
+            .cloned()
+            .unwrap_or_default();
+        if let Err(e) = self.storage.replace_rows(&structure, &rows) {
+            eprintln!("Failed to save rows of {}: {}", structure.name, e);
+        }
+    }
+
+    fn load_custom_structures(&mut self) {
+        match self.storage.load_structures() {
+            Ok(loaded_structures) => {
+                self.custom_structures = loaded_structures;
+
+                // ID가 없는 이전 형식이면 ID를 부여하고 바로 저장
+                let mut upgraded = false;
+                for category in &mut self.custom_structures {
+                    for subcategory in &mut category.subcategories {
+                        for structure in &mut subcategory.structures {
+                            upgraded |= structure.ensure_ids();
                         }
                     }
                 }
+                upgraded |= self.ensure_exchange_rates();
+                if upgraded {
+                    self.save_custom_structures();
+                } else {
+                    self.assign_csv_files();
+                }
+            }
+            Err(e) => {
+                eprintln!("Failed to load structures: {}", e);
+                if let Some(corrupt) = e.downcast_ref::<CorruptFile>() {
+                    self.recovery.push(corrupt.clone());
+                }
             }
+        }
+    }
+
+    fn compiled_formulas(&mut self, structure: &CustomStructure) -> Rc<formula::Formulas> {
+        self.formulas
+            .entry(structure.id.clone())
+            .or_insert_with(|| Rc::new(formula::Formulas::compile(structure)))
+            .clone()
+    }
 
-            workbook.close()?;
-            println!("Excel 파일 저장 완료!");
+    fn compiled_constraints(
+        &mut self,
+        structure: &CustomStructure,
+    ) -> Rc<validation::CompiledConstraints> {
+        self.constraints
+            .entry(structure.id.clone())
+            .or_insert_with(|| Rc::new(validation::CompiledConstraints::compile(structure)))
+            .clone()
+    }
+
+    // 셀 편집, 행 추가 등 한 행만 저장
+    fn save_row(&mut self, structure: &CustomStructure, row: &Row) {
+        if self.writes_blocked() {
+            return;
+        }
+        self.related_dirty.insert(structure.id.clone());
+        self.backup_dirty
+            .insert(structure.id.clone(), Instant::now());
+        if let Err(e) = self.storage.upsert_row(structure, row) {
+            eprintln!("행 저장 실패: {}", e);
         }
-        Ok(())
     }
-    fn import_from_excel(&mut self, structure: &CustomStructure) -> Result<(), Box<dyn Error>> {
-        // 파일 선택 대화상자
-        if let Some(path) = FileDialog::new()
-            .add_filter("Excel Files", &["xlsx"])
-            .pick_file()
-        {
-            let mut workbook: Xlsx<_> = open_workbook(path)?;
-            let range = match workbook.worksheet_range_at(0) {
-                Some(Ok(range)) => range,
-                Some(Err(e)) => return Err(e.into()),
-                None => return Err("시트가 비어있습니다".into()),
-            };
-
-            let mut rows = Vec::new();
-            for row_idx in 1..range.height() {
-                let mut row_data = HashMap::new();
-                for (col_idx, field) in structure.fields.iter().enumerate() {
-                    let value = match range.get_value((row_idx as u32, col_idx as u32)) {
-                        Some(DataType::Int(i)) => i.to_string(),
-                        Some(DataType::Float(f)) => f.to_string(),
-                        Some(DataType::String(s)) => s.to_string(),
-                        Some(DataType::Bool(b)) => b.to_string(),
-                        _ => String::new(),
-                    };
-                    row_data.insert(
-                        field.name.clone(),
-                        FieldValue {
-                            value,
-                            field_type: field.field_type.clone(),
-                        },
-                    );
-                }
-                rows.push(row_data);
-            }
 
-            // 데이터 저장 및 CSV 자동 백업
-            self.erp_data.data.insert(structure.name.clone(), rows);
-            self.save_to_csv(&structure.name);
-            println!("Excel 파일 불러오기 완료!");
+    fn remove_row(&mut self, structure: &CustomStructure, row_id: &str) {
+        if self.writes_blocked() {
+            return;
+        }
+        self.related_dirty.insert(structure.id.clone());
+        if let Err(e) = self.storage.delete_row(structure, row_id) {
+            eprintln!("행 삭제 실패: {}", e);
         }
-        Ok(())
     }
-    fn save_erp_data(&self) {
-        if let Ok(json_data) = serde_json::to_string_pretty(&self.erp_data) {
-            if let Err(e) = fs::write("erp_data.json", json_data) {
-                eprintln!("Failed to save ERP data: {}", e);
-            }
+
+    fn save_custom_structures(&mut self) {
+        if self.writes_blocked() {
+            return;
+        }
+        // 지운 기본 제공 환율표는 다시 만든다
+        self.ensure_exchange_rates();
+        println!("Saving structures to file...");
+        if let Err(e) = self.storage.save_structures(&self.custom_structures) {
+            println!("Failed to save structures: {}", e);
+        } else {
+            println!("Structures saved successfully");
+            self.assign_csv_files();
+            self.mark_all_related();
         }
     }
 
-    fn load_custom_structures(&mut self) {
-        if let Ok(data) = fs::read_to_string("custom_structures.json") {
-            if let Ok(loaded_structures) = serde_json::from_str(&data) {
-                self.custom_structures = loaded_structures;
+    // 새 구조체에 CSV 파일 이름을 정하고 이름 기반 이전 파일을 옮긴다
+    fn assign_csv_files(&mut self) {
+        if let Some(workspace) = &mut self.workspace {
+            if let Err(e) = workspace.assign_csv_files(&self.custom_structures) {
+                eprintln!("CSV 파일 이름 기록 실패: {}", e);
             }
         }
     }
 
-    fn save_custom_structures(&self) {
-        println!("Saving structures to file...");
-        if let Ok(json_data) = serde_json::to_string_pretty(&self.custom_structures) {
-            if let Err(e) = fs::write("custom_structures.json", json_data) {
-                println!("Failed to save structures: {}", e);
-            } else {
-                println!("Structures saved successfully");
+    // 설정에 저장된 저장소 열기
+    fn open_storage(&mut self) {
+        let Some(workspace) = &self.workspace else {
+            return;
+        };
+        self.settings = AppSettings::load(workspace);
+        match self.settings.storage.open(workspace) {
+            Ok(storage) => self.storage = storage,
+            Err(e) => eprintln!(
+                "저장소를 열 수 없습니다 ({}): {}",
+                self.settings.storage.label(),
+                e
+            ),
+        }
+    }
+
+    // 현재 구조체와 데이터를 새 저장소로 복사한 뒤 전환
+    fn switch_storage(&mut self, kind: StorageKind) {
+        let Some(workspace) = self.workspace.clone() else {
+            return;
+        };
+        let result = kind.open(&workspace).and_then(|mut storage| {
+            storage.save_structures(&self.custom_structures)?;
+            storage.save_data(&self.custom_structures, &self.erp_data)?;
+            Ok(storage)
+        });
+        match result {
+            Ok(storage) => {
+                self.storage = storage;
+                self.settings.storage = kind;
+                if let Err(e) = self.settings.save(&workspace) {
+                    eprintln!("설정 저장 실패: {}", e);
+                }
+                println!("저장소를 {}(으)로 전환했습니다", kind.label());
             }
-        } else {
-            println!("Failed to serialize structures");
+            Err(e) => eprintln!("저장소 전환 실패: {}", e),
         }
     }
 
-    fn new() -> Self {
-        let mut app = Self::default();
-        app.load_custom_structures();
-        app.load_erp_data(); // 시작할 때 ERP 데이터도 로드
+    // 워크스페이스가 주어지면 바로 열고, 아니면 선택 화면부터 시작
+    fn new(workspace: Option<PathBuf>) -> Self {
+        let mut app = Self {
+            recent_workspaces: RecentWorkspaces::load(),
+            ..Default::default()
+        };
+        if let Some(path) = workspace {
+            app.open_workspace(&path); // 시작할 때 ERP 데이터도 로드
+        }
         app
     }
     fn render_setting_panel(&mut self, ui: &mut Ui) {
@@ -345,11 +629,77 @@ impl ERPApp {
             if ui.button("💾 저장하기").clicked() {
                 self.save_custom_structures();
             }
+            if ui.button("📤 통합 문서 가져오기").clicked() {
+                self.start_category_import();
+            }
         });
+        ui.horizontal(|ui| {
+            ui.label("저장소:");
+            let current = self.storage.kind();
+            for kind in [StorageKind::Json, StorageKind::Sqlite] {
+                if ui.selectable_label(current == kind, kind.label()).clicked() && current != kind {
+                    self.switch_storage(kind);
+                }
+            }
+        });
+        ui.horizontal(|ui| {
+            ui.label("CSV 백업 형식:");
+            if csv_format::format_picker(ui, "csv_backup", &mut self.settings.csv_backup) {
+                if let Some(workspace) = &self.workspace {
+                    if let Err(e) = self.settings.save(workspace) {
+                        eprintln!("설정 저장 실패: {}", e);
+                    }
+                }
+            }
+        });
+        if excel_export::options_editor(ui, &mut self.settings.excel_export) {
+            if let Some(workspace) = &self.workspace {
+                if let Err(e) = self.settings.save(workspace) {
+                    eprintln!("설정 저장 실패: {}", e);
+                }
+            }
+        }
+        ui.horizontal(|ui| {
+            ui.label("사용자 이름:");
+            let response = ui.add(
+                egui::TextEdit::singleline(&mut self.settings.user_name)
+                    .hint_text(defaults::system_user_name()),
+            );
+            if response.lost_focus() {
+                if let Some(workspace) = &self.workspace {
+                    if let Err(e) = self.settings.save(workspace) {
+                        eprintln!("설정 저장 실패: {}", e);
+                    }
+                }
+            }
+        });
+        if let Some(workspace) = &self.workspace {
+            let root = workspace.root().display().to_string();
+            ui.horizontal(|ui| {
+                ui.label(format!("워크스페이스: {}", root));
+                if ui.button("🗂 워크스페이스 변경").clicked() {
+                    self.workspace = None;
+                }
+                if ui
+                    .button("🧹 첨부 파일 정리")
+                    .on_hover_text("어느 행에서도 쓰지 않는 첨부 파일을 지웁니다")
+                    .clicked()
+                {
+                    match self.clean_orphan_attachments() {
+                        Ok(removed) => {
+                            println!("사용하지 않는 첨부 파일 {}개를 지웠습니다", removed)
+                        }
+                        Err(e) => eprintln!("첨부 파일 정리 실패: {}", e),
+                    }
+                }
+            });
+        }
         ui.separator();
 
         // 카테고리 목록
         let mut category_to_remove = None;
+        let mut category_to_export = None;
+        let mut structure_from_file = None;
         for (cat_idx, category) in self.custom_structures.iter_mut().enumerate() {
             ui.group(|ui| {
                 ui.horizontal(|ui| {
@@ -357,6 +707,9 @@ impl ERPApp {
                     if ui.button("🗑️").clicked() {
                         category_to_remove = Some(cat_idx);
                     }
+                    if ui.button("📥 통합 문서 내보내기").clicked() {
+                        category_to_export = Some(cat_idx);
+                    }
                     if ui.button("➕ 새 서브카테고리").clicked() {
                         category.subcategories.push(SubCategory {
                             name: "새 서브카테고리".to_string(),
@@ -375,10 +728,15 @@ impl ERPApp {
                                 subcategory_to_remove = Some(sub_idx);
                             }
                             if ui.button("➕ 새 구조체").clicked() {
-                                self.current_structure = CustomStructure::default();
+                                self.current_structure = CustomStructure::new();
                                 self.selected_category = Some(category.name.clone());
                                 self.current_subcategory = Some(subcategory.name.clone());
                                 self.show_structure_editor = true;
+                                self.structure_source = None;
+                            }
+                            if ui.button("📄 파일에서 새 구조체").clicked() {
+                                structure_from_file =
+                                    Some((category.name.clone(), subcategory.name.clone()));
                             }
                         });
 
@@ -391,6 +749,7 @@ impl ERPApp {
                                     self.selected_category = Some(category.name.clone());
                                     self.current_subcategory = Some(subcategory.name.clone());
                                     self.show_structure_editor = true;
+                                    self.structure_source = None;
                                 }
                             });
                         }
@@ -408,56 +767,118 @@ impl ERPApp {
             self.custom_structures.remove(idx);
         }
 
+        if let Some((category, subcategory)) = structure_from_file {
+            self.start_structure_from_file(category, subcategory);
+        }
+
+        if let Some(category) = category_to_export.and_then(|idx| self.custom_structures.get(idx)) {
+            if let Err(e) = self.export_category_workbook(category) {
+                eprintln!("통합 문서 내보내기 실패: {}", e);
+            }
+        }
+
         // 구조체 편집기
         if self.show_structure_editor {
             self.render_structure_editor(ui);
         }
     }
-    fn save_to_csv(&self, structure_name: &str) {
-        if let Some(structure) = self.find_structure(structure_name) {
-            if let Some(rows) = self.erp_data.data.get(structure_name) {
-                match csv::Writer::from_path(format!("{}.csv", structure_name)) {
-                    Ok(mut writer) => {
-                        // 헤더 작성
-                        let headers: Vec<String> = structure
-                            .fields
-                            .iter()
-                            .map(|field| field.name.clone())
-                            .collect();
-                        if let Err(e) = writer.write_record(&headers) {
-                            eprintln!("헤더 저장 실패: {}", e);
-                            return;
-                        }
+    fn save_to_csv(&self, structure_id: &str) {
+        if self.writes_blocked() {
+            return;
+        }
+        if self.backup_unresolved(structure_id) {
+            eprintln!("CSV 백업과의 불일치를 확인하기 전이라 CSV를 쓰지 않습니다");
+            return;
+        }
+        if let Some(structure) = self.find_structure(structure_id) {
+            if self.erp_data.data.contains_key(structure_id) {
+                let path = self.csv_path(&structure);
+                match self.write_csv(&structure, &path, &self.settings.csv_backup) {
+                    Ok(()) => println!("CSV 파일 저장 완료: {}", path.display()),
+                    Err(e) => eprintln!("CSV 파일 저장 실패: {}", e),
+                }
+            }
+        }
+    }
 
-                        // 데이터 작성
-                        for row in rows {
-                            let record: Vec<String> = structure
-                                .fields
-                                .iter()
-                                .map(|field| {
-                                    row.get(&field.name)
-                                        .map(|fv| fv.value.clone())
-                                        .unwrap_or_default()
-                                })
-                                .collect();
-                            if let Err(e) = writer.write_record(&record) {
-                                eprintln!("데이터 저장 실패: {}", e);
-                                return;
-                            }
-                        }
+    // 워크스페이스 backups 폴더의 구조체 CSV 백업 경로 (csv_files.json에 기록된 이름)
+    fn csv_path(&self, structure: &CustomStructure) -> PathBuf {
+        match &self.workspace {
+            Some(workspace) => workspace.csv_path(structure),
+            None => PathBuf::from(format!(
+                "{}.csv",
+                workspace::sanitize_file_name(&structure.name)
+            )),
+        }
+    }
 
-                        if let Err(e) = writer.flush() {
-                            eprintln!("파일 저장 실패: {}", e);
-                            return;
-                        }
-                        println!("CSV 파일 저장 완료: {}.csv", structure_name);
-                    }
-                    Err(e) => {
-                        eprintln!("CSV 파일 생성 실패: {}", e);
-                    }
-                }
+    fn write_csv(
+        &self,
+        structure: &CustomStructure,
+        path: &Path,
+        format: &csv_format::CsvFormat,
+    ) -> Result<(), Box<dyn Error>> {
+        let rows = self
+            .erp_data
+            .data
+            .get(&structure.id)
+            .map(Vec::as_slice)
+            .unwrap_or_default();
+        Self::write_csv_rows(structure, rows, path, format, true)
+    }
+
+    // 헤더와 데이터를 메모리에 작성한 뒤 원자적으로 교체.
+    // 백업은 복원할 때 같은 행 ID를 쓰도록 맨 앞에 행 ID 열을 둔다
+    fn write_csv_rows(
+        structure: &CustomStructure,
+        rows: &[Row],
+        path: &Path,
+        format: &csv_format::CsvFormat,
+        row_ids: bool,
+    ) -> Result<(), Box<dyn Error>> {
+        let mut writer = csv::WriterBuilder::new()
+            .delimiter(format.delimiter.byte())
+            .from_writer(Vec::new());
+
+        // 헤더 작성
+        let mut headers: Vec<String> = structure
+            .fields
+            .iter()
+            .map(|field| field.name.clone())
+            .collect();
+        if row_ids {
+            headers.insert(0, csv_import::ROW_ID_COLUMN.to_string());
+        }
+        writer.write_record(&headers)?;
+
+        // 데이터 작성
+        for row in rows {
+            let mut record: Vec<String> = structure
+                .fields
+                .iter()
+                .map(|field| {
+                    row.values
+                        .get(&field.id)
+                        .map(|fv| fv.value.clone())
+                        .unwrap_or_default()
+                })
+                .collect();
+            if row_ids {
+                record.insert(0, row.id.clone());
             }
+            writer.write_record(&record)?;
+        }
+
+        let text = String::from_utf8(writer.into_inner().map_err(|e| e.to_string())?)?;
+        let (bytes, unmappable) = csv_format::encode(&text, format.encoding);
+        if unmappable {
+            eprintln!(
+                "{}: CP949로 표현할 수 없는 문자가 있어 숫자 참조로 저장했습니다",
+                path.display()
+            );
         }
+        safe_file::atomic_write(path, &bytes)?;
+        Ok(())
     }
 
     fn render_structure_editor(&mut self, ui: &mut Ui) {
@@ -473,12 +894,41 @@ impl ERPApp {
             ui.horizontal(|ui| {
                 ui.heading("필드 목록");
                 if ui.button("➕ 필드 추가").clicked() {
-                    self.current_structure.fields.push(Field::default());
+                    self.current_structure.fields.push(Field::new());
                 }
             });
 
             // 필드 목록 표시
             let mut fields_to_remove = Vec::new();
+            let mut field_to_move_up = None;
+            // 참조 필드의 대상으로 고를 수 있는 구조체 (편집 중인 구조체 포함)
+            let mut structures: Vec<CustomStructure> = self
+                .custom_structures
+                .iter()
+                .flat_map(|c| c.subcategories.iter())
+                .flat_map(|s| s.structures.iter())
+                .filter(|s| s.id != self.current_structure.id)
+                .cloned()
+                .collect();
+            let current = self.current_structure.clone();
+            structures.push(current.clone());
+            let formula_errors = formula::check_formulas(&self.current_structure);
+            // 합계 필드가 집계할 수 있는 하위 표
+            let table_fields: Vec<Field> = self
+                .current_structure
+                .fields
+                .iter()
+                .filter(|f| f.field_type == FieldType::Table)
+                .cloned()
+                .collect();
+            // 거래 통화 금액의 환산 기준일로 고를 수 있는 필드
+            let date_fields: Vec<Field> = self
+                .current_structure
+                .fields
+                .iter()
+                .filter(|f| matches!(f.field_type, FieldType::Date | FieldType::DateTime))
+                .cloned()
+                .collect();
             ScrollArea::vertical()
                 .id_source("fields_list")
                 .show(ui, |ui| {
@@ -505,31 +955,164 @@ impl ERPApp {
                                             FieldType::Number,
                                             "숫자",
                                         );
+                                        ui.selectable_value(
+                                            &mut field.field_type,
+                                            FieldType::Currency,
+                                            "통화",
+                                        );
                                         ui.selectable_value(
                                             &mut field.field_type,
                                             FieldType::Date,
                                             "날짜",
                                         );
+                                        ui.selectable_value(
+                                            &mut field.field_type,
+                                            FieldType::DateTime,
+                                            "날짜와 시간",
+                                        );
+                                        ui.selectable_value(
+                                            &mut field.field_type,
+                                            FieldType::Time,
+                                            "시간",
+                                        );
                                         ui.selectable_value(
                                             &mut field.field_type,
                                             FieldType::Boolean,
                                             "참/거짓",
                                         );
+                                        ui.selectable_value(
+                                            &mut field.field_type,
+                                            FieldType::Select,
+                                            "선택",
+                                        );
+                                        ui.selectable_value(
+                                            &mut field.field_type,
+                                            FieldType::MultiSelect,
+                                            "다중 선택",
+                                        );
+                                        ui.selectable_value(
+                                            &mut field.field_type,
+                                            FieldType::Reference,
+                                            "참조",
+                                        );
+                                        ui.selectable_value(
+                                            &mut field.field_type,
+                                            FieldType::Table,
+                                            "하위 표",
+                                        );
+                                        ui.selectable_value(
+                                            &mut field.field_type,
+                                            FieldType::Formula,
+                                            "수식",
+                                        );
+                                        ui.selectable_value(
+                                            &mut field.field_type,
+                                            FieldType::Rollup,
+                                            "집계",
+                                        );
+                                        ui.selectable_value(
+                                            &mut field.field_type,
+                                            FieldType::Lookup,
+                                            "조회",
+                                        );
+                                        ui.selectable_value(
+                                            &mut field.field_type,
+                                            FieldType::Attachment,
+                                            "첨부 파일",
+                                        );
+                                        ui.selectable_value(
+                                            &mut field.field_type,
+                                            FieldType::ResidentNumber,
+                                            "주민등록번호",
+                                        );
+                                        ui.selectable_value(
+                                            &mut field.field_type,
+                                            FieldType::BusinessNumber,
+                                            "사업자등록번호",
+                                        );
+                                        ui.selectable_value(
+                                            &mut field.field_type,
+                                            FieldType::Phone,
+                                            "전화번호",
+                                        );
                                     });
 
+                                if ui.button("⬆").clicked() && idx > 0 {
+                                    field_to_move_up = Some(idx);
+                                }
+                                if ui.button("⬇").clicked() {
+                                    field_to_move_up = Some(idx + 1);
+                                }
                                 if ui.button("🗑️ 삭제").clicked() {
                                     fields_to_remove.push(idx);
                                 }
                             });
+                            if select::is_select(&field.field_type) {
+                                select::options_editor(ui, idx, field);
+                            }
+                            if field.field_type == FieldType::Reference {
+                                reference::reference_editor(ui, idx, field, &structures);
+                            }
+                            if field.field_type == FieldType::Table {
+                                line_items::columns_editor(ui, idx, field);
+                            }
+                            if field.field_type == FieldType::Formula {
+                                let error = formula_errors.get(&field.id);
+                                formula::formula_editor(ui, idx, field, error);
+                            }
+                            if matches!(field.field_type, FieldType::Date | FieldType::DateTime) {
+                                dates::format_editor(ui, idx, field);
+                            }
+                            if field.field_type == FieldType::Rollup {
+                                rollup::rollup_editor(ui, idx, field, &current, &structures);
+                            }
+                            if field.field_type == FieldType::Lookup {
+                                rollup::lookup_editor(ui, idx, field, &current, &structures);
+                            }
+                            if field.field_type == FieldType::Currency {
+                                currency::currency_editor(ui, ("field_currency", idx), field);
+                                currency::transaction_editor(ui, idx, field, &date_fields);
+                            }
+                            if korean_id::is_identifier(&field.field_type) {
+                                korean_id::mask_editor(ui, field);
+                            }
+                            if matches!(field.field_type, FieldType::Number | FieldType::Currency) {
+                                line_items::subtotal_editor(ui, idx, field, &table_fields);
+                            }
+                            defaults::default_editor(ui, idx, field);
+                            validation::constraints_editor(ui, idx, field);
                         });
                     }
                 });
 
+            // 필드 순서 변경 처리
+            if let Some(idx) = field_to_move_up {
+                if idx < self.current_structure.fields.len() {
+                    self.current_structure.fields.swap(idx - 1, idx);
+                }
+            }
+
             // 필드 삭제 처리
             for idx in fields_to_remove.iter().rev() {
                 self.current_structure.fields.remove(*idx);
             }
 
+            // 파일에서 만든 구조체면 저장과 함께 행 가져오기
+            if let Some(source) = self
+                .structure_source
+                .as_mut()
+                .filter(|s| s.structure_id == self.current_structure.id)
+            {
+                ui.checkbox(
+                    &mut source.import_rows,
+                    format!(
+                        "저장 후 {}의 {}행 가져오기",
+                        source.file_name,
+                        source.records.len()
+                    ),
+                );
+            }
+
             ui.add_space(20.0);
             ui.separator();
             ui.add_space(10.0);
@@ -538,57 +1121,15 @@ impl ERPApp {
             ui.horizontal(|ui| {
                 if ui.button("💾 구조체 저장").clicked() {
                     if !self.current_structure.name.is_empty() {
+                        self.current_structure.ensure_ids();
                         let category_name = self.selected_category.clone();
                         let subcategory_name = self.current_subcategory.clone();
-                        let structure_name = self.current_structure.name.clone();
 
                         if let (Some(cat_name), Some(subcat_name)) =
                             (category_name, subcategory_name)
                         {
-                            if let Some(category) = self
-                                .custom_structures
-                                .iter_mut()
-                                .find(|c| c.name == cat_name)
-                            {
-                                if let Some(subcategory) = category
-                                    .subcategories
-                                    .iter_mut()
-                                    .find(|s| s.name == subcat_name)
-                                {
-                                    let is_new = !subcategory
-                                        .structures
-                                        .iter()
-                                        .any(|s| s.name == self.current_structure.name);
-
-                                    // 구조체 저장
-                                    if let Some(idx) = subcategory
-                                        .structures
-                                        .iter()
-                                        .position(|s| s.name == self.current_structure.name)
-                                    {
-                                        subcategory.structures[idx] =
-                                            self.current_structure.clone();
-                                    } else {
-                                        subcategory.structures.push(self.current_structure.clone());
-                                    }
-
-                                    // 새 구조체인 경우 빈 데이터 초기화
-                                    if is_new {
-                                        self.erp_data.data.insert(structure_name, Vec::new());
-                                        self.save_erp_data();
-                                    }
-
-                                    self.save_custom_structures();
-                                    self.show_structure_editor = false;
-
-                                    // 성공 메시지 출력
-                                    println!("구조체가 성공적으로 저장되었습니다!");
-                                } else {
-                                    println!("서브카테고리를 찾을 수 없습니다: {}", subcat_name);
-                                }
-                            } else {
-                                println!("카테고리를 찾을 수 없습니다: {}", cat_name);
-                            }
+                            self.request_structure_save(cat_name, subcat_name);
+                            self.import_structure_source();
                         } else {
                             println!("카테고리 또는 서브카테고리가 선택되지 않았습니다.");
                         }
@@ -599,6 +1140,7 @@ impl ERPApp {
 
                 if ui.button("❌ 취소").clicked() {
                     self.show_structure_editor = false;
+                    self.structure_source = None;
                 }
 
                 // 현재 선택된 카테고리와 서브카테고리 정보 표시
@@ -616,6 +1158,110 @@ impl ERPApp {
             });
         });
     }
+    // 기존 구조체의 필드가 바뀌었으면 마이그레이션 미리보기를 띄우고, 아니면 바로 저장
+    fn request_structure_save(&mut self, cat_name: String, subcat_name: String) {
+        let structure = self.current_structure.clone();
+        let Some(old) = self.find_structure(&structure.id) else {
+            self.commit_structure(&cat_name, &subcat_name, structure, false);
+            return;
+        };
+
+        if !self.erp_data.data.contains_key(&structure.id) {
+            self.load_structure_data(&structure.id);
+        }
+        let rows = self
+            .erp_data
+            .data
+            .get(&structure.id)
+            .cloned()
+            .unwrap_or_default();
+        let preview = schema::preview_migration(&old, &structure, &rows, &self.current_user_name());
+
+        // 바뀌는 행이 없으면 바로 저장, 필드 변경 없이 계산 값만 달라지면 확인 없이 다시 맞춘다
+        if preview.affected_rows == 0 {
+            let migrate = !preview.diff.is_empty();
+            self.commit_structure(&cat_name, &subcat_name, structure, migrate);
+        } else if preview.diff.is_empty() {
+            self.commit_structure(&cat_name, &subcat_name, structure, true);
+        } else {
+            self.pending_migration = Some(schema::PendingMigration {
+                structure,
+                category: cat_name,
+                subcategory: subcat_name,
+                preview,
+            });
+        }
+    }
+
+    fn commit_structure(
+        &mut self,
+        cat_name: &str,
+        subcat_name: &str,
+        mut structure: CustomStructure,
+        migrate: bool,
+    ) {
+        let structure_id = structure.id.clone();
+        if migrate {
+            if let Some(old) = self.find_structure(&structure_id) {
+                structure.version = old.version + 1;
+            }
+        }
+
+        let Some(category) = self
+            .custom_structures
+            .iter_mut()
+            .find(|c| c.name == cat_name)
+        else {
+            println!("카테고리를 찾을 수 없습니다: {}", cat_name);
+            return;
+        };
+        let Some(subcategory) = category
+            .subcategories
+            .iter_mut()
+            .find(|s| s.name == subcat_name)
+        else {
+            println!("서브카테고리를 찾을 수 없습니다: {}", subcat_name);
+            return;
+        };
+
+        // 구조체 저장 (ID 기준이므로 이름 변경 시에도 같은 구조체 갱신)
+        let is_new = if let Some(idx) = subcategory
+            .structures
+            .iter()
+            .position(|s| s.id == structure_id)
+        {
+            subcategory.structures[idx] = structure.clone();
+            false
+        } else {
+            subcategory.structures.push(structure.clone());
+            true
+        };
+
+        self.current_structure = structure.clone();
+        self.formulas.remove(&structure_id);
+        self.constraints.remove(&structure_id);
+        self.save_custom_structures();
+
+        if is_new {
+            // 새 구조체인 경우 빈 데이터 초기화
+            self.erp_data.data.insert(structure_id.clone(), Vec::new());
+            self.save_structure_rows(&structure_id);
+        } else if migrate {
+            // 기존 행에 변경 적용 후 CSV 백업도 새 필드 순서로 다시 작성
+            let user_name = self.current_user_name();
+            if let Some(rows) = self.erp_data.data.get_mut(&structure_id) {
+                schema::apply_migration(&structure, rows, &user_name);
+            }
+            self.save_structure_rows(&structure_id);
+            self.save_to_csv(&structure_id);
+        }
+
+        self.show_structure_editor = false;
+
+        // 성공 메시지 출력
+        println!("구조체가 성공적으로 저장되었습니다!");
+    }
+
     fn render_custom_structures_list(&mut self, ui: &mut Ui) {
         let mut custom_structures = self.custom_structures.clone();
 
@@ -698,7 +1344,7 @@ impl ERPApp {
 
         ui.horizontal(|ui| {
             if ui.button("➕ 필드 추가").clicked() {
-                self.current_structure.fields.push(Field::default());
+                self.current_structure.fields.push(Field::new());
             }
 
             // if ui.button("💾 저장").clicked() && !self.current_structure.name.is_empty() {
@@ -738,12 +1384,78 @@ impl ERPApp {
                                     FieldType::Number,
                                     "Number",
                                 );
+                                ui.selectable_value(
+                                    &mut field.field_type,
+                                    FieldType::Currency,
+                                    "Currency",
+                                );
                                 ui.selectable_value(&mut field.field_type, FieldType::Date, "Date");
+                                ui.selectable_value(
+                                    &mut field.field_type,
+                                    FieldType::DateTime,
+                                    "DateTime",
+                                );
+                                ui.selectable_value(&mut field.field_type, FieldType::Time, "Time");
                                 ui.selectable_value(
                                     &mut field.field_type,
                                     FieldType::Boolean,
                                     "Boolean",
                                 );
+                                ui.selectable_value(
+                                    &mut field.field_type,
+                                    FieldType::Select,
+                                    "Select",
+                                );
+                                ui.selectable_value(
+                                    &mut field.field_type,
+                                    FieldType::MultiSelect,
+                                    "MultiSelect",
+                                );
+                                ui.selectable_value(
+                                    &mut field.field_type,
+                                    FieldType::Reference,
+                                    "Reference",
+                                );
+                                ui.selectable_value(
+                                    &mut field.field_type,
+                                    FieldType::Table,
+                                    "Table",
+                                );
+                                ui.selectable_value(
+                                    &mut field.field_type,
+                                    FieldType::Formula,
+                                    "Formula",
+                                );
+                                ui.selectable_value(
+                                    &mut field.field_type,
+                                    FieldType::Rollup,
+                                    "Rollup",
+                                );
+                                ui.selectable_value(
+                                    &mut field.field_type,
+                                    FieldType::Lookup,
+                                    "Lookup",
+                                );
+                                ui.selectable_value(
+                                    &mut field.field_type,
+                                    FieldType::Attachment,
+                                    "Attachment",
+                                );
+                                ui.selectable_value(
+                                    &mut field.field_type,
+                                    FieldType::ResidentNumber,
+                                    "ResidentNumber",
+                                );
+                                ui.selectable_value(
+                                    &mut field.field_type,
+                                    FieldType::BusinessNumber,
+                                    "BusinessNumber",
+                                );
+                                ui.selectable_value(
+                                    &mut field.field_type,
+                                    FieldType::Phone,
+                                    "Phone",
+                                );
                             });
 
                         if ui.button("🗑️").clicked() {
@@ -760,25 +1472,66 @@ impl ERPApp {
     fn render_erp_panel(&mut self, ui: &mut Ui) {
         if let Some(selected_structure_name) = &self.selected_structure.clone() {
             // 선택된 구조체 찾기
-            let selected_structure = self
-                .custom_structures
-                .iter()
-                .find_map(|category| {
-                    category.subcategories.iter().find_map(|subcategory| {
-                        subcategory
-                            .structures
-                            .iter()
-                            .find(|structure| &structure.name == selected_structure_name)
-                    })
-                })
-                .cloned();
+            let selected_structure = self.find_structure(selected_structure_name);
 
             if let Some(structure) = selected_structure {
+                // 제약 조건 위반 (셀 강조와 툴바 표시)
+                let constraints = self.compiled_constraints(&structure);
+                let violations = validation::validate_rows(
+                    &constraints,
+                    self.erp_data
+                        .data
+                        .get(&structure.id)
+                        .map(Vec::as_slice)
+                        .unwrap_or_default(),
+                );
+                // 거래 통화 필드가 있을 때만 환율표를 읽는다
+                let rates = structure
+                    .fields
+                    .iter()
+                    .any(|f| {
+                        f.field_type == FieldType::Currency && currency::currency_of(f).per_row
+                    })
+                    .then(|| self.exchange_rates());
+                let mut view = self
+                    .grid_views
+                    .get(&structure.id)
+                    .cloned()
+                    .unwrap_or_default();
+
                 // 상단 툴바
                 ui.horizontal(|ui| {
                     ui.heading(&structure.name);
+                    if let Some(error) = self.delete_error.clone() {
+                        ui.colored_label(egui::Color32::RED, format!("⛔ {}", error));
+                        if ui.small_button("✖").clicked() {
+                            self.delete_error = None;
+                        }
+                    }
+                    if !violations.is_empty() {
+                        ui.colored_label(
+                            egui::Color32::RED,
+                            format!("⚠ 제약 조건 위반 {}건", violations.len()),
+                        );
+                    }
+                    ui.add(
+                        egui::TextEdit::singleline(&mut view.filter)
+                            .hint_text("🔍 필터")
+                            .desired_width(200.0),
+                    )
+                    .on_hover_text(
+                        "모든 필드에서 찾거나 \"필드 이름:조건\"으로 한 필드만 찾습니다.\n\
+                         날짜는 2024, 2024-01, 2024년 1월처럼 기간으로 쓰거나\n\
+                         >= 2024-01-01, < 2024년 3월 1일처럼 비교할 수 있습니다.",
+                    );
                     let structure_clone = structure.clone();
                     ui.with_layout(egui::Layout::right_to_left(egui::Align::Center), |ui| {
+                        if structure_clone.id == exchange::RATES_STRUCTURE_ID
+                            && ui.button("📤 환율 CSV 가져오기").clicked()
+                        {
+                            self.import_exchange_rates();
+                        }
+
                         if ui.button("📥 Excel 내보내기").clicked() {
                             if let Err(e) = self.export_to_excel(&structure_clone) {
                                 eprintln!("Excel 내보내기 실패: {}", e);
@@ -786,35 +1539,43 @@ impl ERPApp {
                         }
 
                         if ui.button("📤 Excel 불러오기").clicked() {
-                            if let Err(e) = self.import_from_excel(&structure_clone) {
-                                eprintln!("Excel 불러오기 실패: {}", e);
-                            }
+                            self.start_excel_import(&structure_clone);
+                        }
+
+                        if ui.button("📥 CSV 내보내기").clicked() {
+                            self.start_csv_export(&structure_clone);
+                        }
+
+                        if ui.button("📤 CSV 가져오기").clicked() {
+                            self.start_csv_import(&structure_clone);
                         }
 
                         if ui.button("➕ 새 데이터").clicked() {
-                            let mut new_row = HashMap::new();
-                            for field in &structure_clone.fields {
-                                new_row.insert(
-                                    field.name.clone(),
-                                    FieldValue {
-                                        value: String::new(),
-                                        field_type: field.field_type.clone(),
-                                    },
-                                );
-                            }
+                            let new_row = self.new_row(&structure_clone);
 
+                            self.save_row(&structure_clone, &new_row);
                             self.erp_data
                                 .data
-                                .entry(structure_clone.name.clone())
+                                .entry(structure_clone.id.clone())
                                 .or_default()
                                 .push(new_row);
 
-                            self.save_to_csv(&structure_clone.name);
+                            self.save_to_csv(&structure_clone.id);
                         }
                     });
                 });
                 ui.separator();
 
+                // 참조 필드별 선택 목록
+                let reference_options: HashMap<String, Vec<(String, String)>> = structure
+                    .fields
+                    .iter()
+                    .filter(|f| f.field_type == FieldType::Reference)
+                    .map(|f| (f.id.clone(), self.reference_options(f)))
+                    .collect();
+                let mut navigate = None;
+                let mut open_lines = None;
+
                 // 테이블 그리기
                 ScrollArea::vertical().show(ui, |ui| {
                     egui::Grid::new("erp_table")
@@ -824,80 +1585,194 @@ impl ERPApp {
                             // 헤더 행
                             ui.label("No.");
                             for field in &structure.fields {
-                                ui.label(&field.name);
+                                let title =
+                                    format!("{}{}", field.name, view.sort_marker(&field.id));
+                                if ui
+                                    .add(egui::Button::new(title).frame(false))
+                                    .on_hover_text("눌러서 정렬")
+                                    .clicked()
+                                {
+                                    view.toggle_sort(&field.id);
+                                }
                             }
                             ui.label("관리");
                             ui.end_row();
 
-                            let structure_name = structure.name.clone();
+                            let structure_id = structure.id.clone();
                             let mut rows_data = self
                                 .erp_data
                                 .data
-                                .get_mut(&structure_name)
+                                .get_mut(&structure_id)
                                 .cloned()
                                 .unwrap_or_default();
 
                             let mut row_to_remove = None;
+                            let attachments_dir = self.attachments_dir();
+                            let formulas = self.compiled_formulas(&structure);
+
+                            let order = row_view::visible_order(
+                                &structure,
+                                &rows_data,
+                                &view,
+                                &reference_options,
+                            );
+                            for (row_idx, &i) in order.iter().enumerate() {
+                                let row_data = &mut rows_data[i];
+                                let focused = self.focus_row.as_ref() == Some(&row_data.id);
+                                let number = egui::RichText::new((row_idx + 1).to_string());
+                                let number = if focused {
+                                    number.strong().color(egui::Color32::from_rgb(255, 140, 0))
+                                } else {
+                                    number
+                                };
+                                let number = ui.label(number);
+                                if focused && self.scroll_to_focus {
+                                    number.scroll_to_me(Some(egui::Align::Center));
+                                    self.scroll_to_focus = false;
+                                }
 
-                            for (row_idx, row_data) in rows_data.iter_mut().enumerate() {
-                                ui.label((row_idx + 1).to_string());
+                                // 수식 필드는 그릴 때마다 다시 계산 (값이 바뀌면 아래에서 저장)
+                                let formula_errors = formulas.apply(row_data);
 
                                 for field in &structure.fields {
                                     let field_value = row_data
-                                        .entry(field.name.clone())
+                                        .values
+                                        .entry(field.id.clone())
                                         .or_insert_with(|| FieldValue {
                                             value: String::new(),
                                             field_type: field.field_type.clone(),
                                         });
 
-                                    match field_value.field_type {
-                                        FieldType::Text => {
-                                            let mut value = field_value.value.clone();
-                                            if ui.text_edit_singleline(&mut value).changed() {
-                                                field_value.value = value;
-                                            }
-                                        }
-                                        FieldType::Number => {
-                                            let mut value =
-                                                field_value.value.parse::<f64>().unwrap_or(0.0);
-                                            if ui.add(egui::DragValue::new(&mut value)).changed() {
-                                                field_value.value = value.to_string();
-                                            }
+                                    let response = match field_value.field_type {
+                                        FieldType::Formula => match formula_errors.get(&field.id) {
+                                            Some(error) => ui
+                                                .colored_label(egui::Color32::RED, "#오류")
+                                                .on_hover_text(error),
+                                            None => ui.label(&field_value.value),
+                                        },
+                                        // 하위 표 합계는 줄 항목에서 계산하므로 읽기 전용
+                                        _ if field.subtotal.is_some() => {
+                                            ui.label(&field_value.value)
                                         }
-                                        FieldType::Date => {
-                                            let mut value = field_value.value.clone();
-                                            if ui.text_edit_singleline(&mut value).changed() {
-                                                field_value.value = value;
+                                        FieldType::Table => {
+                                            let count = line_items::line_count(&field_value.value);
+                                            let response = ui.button(format!("📋 {}줄", count));
+                                            if response.clicked() {
+                                                open_lines =
+                                                    Some((row_data.id.clone(), field.id.clone()));
                                             }
+                                            response
                                         }
-                                        FieldType::Boolean => {
-                                            let mut value = field_value.value == "true";
-                                            if ui.checkbox(&mut value, "").changed() {
-                                                field_value.value = value.to_string();
+                                        FieldType::Attachment => attachments::attachment_cell(
+                                            ui,
+                                            ("attachment_cell", &row_data.id, &field.id),
+                                            attachments_dir.as_deref(),
+                                            &mut field_value.value,
+                                        ),
+                                        FieldType::Reference => {
+                                            let (response, go) = reference::reference_cell(
+                                                ui,
+                                                ("reference_cell", &row_data.id, &field.id),
+                                                reference_options
+                                                    .get(&field.id)
+                                                    .map(Vec::as_slice)
+                                                    .unwrap_or_default(),
+                                                &mut field_value.value,
+                                            );
+                                            if let Some(target) =
+                                                field.reference.as_ref().filter(|_| go)
+                                            {
+                                                navigate = Some((
+                                                    target.structure_id.clone(),
+                                                    field_value.value.clone(),
+                                                ));
                                             }
+                                            response
                                         }
+                                        _ => value_cell(
+                                            ui,
+                                            (&row_data.id, &field.id),
+                                            field,
+                                            field_value,
+                                        ),
+                                    };
+
+                                    // 거래 통화 금액에는 거래일 환율로 환산한 원화 금액
+                                    let response = match rates.as_ref().and_then(|rates| {
+                                        exchange::base_hint(rates, &structure, field, row_data)
+                                    }) {
+                                        Some(hint) => response.on_hover_text(hint),
+                                        None => response,
+                                    };
+
+                                    // 위반한 셀은 빨간 테두리와 안내 문구
+                                    if let Some(message) =
+                                        violations.get(&(row_data.id.clone(), field.id.clone()))
+                                    {
+                                        ui.painter().rect_stroke(
+                                            response.rect.expand(2.0),
+                                            2.0,
+                                            egui::Stroke::new(1.5, egui::Color32::RED),
+                                        );
+                                        response.on_hover_text(message);
                                     }
                                 }
 
                                 if ui.button("🗑️").clicked() {
-                                    row_to_remove = Some(row_idx);
+                                    row_to_remove = Some(row_data.id.clone());
                                 }
 
                                 ui.end_row();
                             }
 
-                            // 변경사항 처리
-                            if let Some(idx) = row_to_remove {
-                                rows_data.remove(idx);
+                            // 바뀐 행만 저장소에 기록
+                            let stored = self.erp_data.data.get(&structure_id).cloned();
+                            if stored.as_ref() != Some(&rows_data) {
+                                for row in &rows_data {
+                                    let unchanged = stored
+                                        .as_ref()
+                                        .and_then(|rows| rows.iter().find(|r| r.id == row.id))
+                                        == Some(row);
+                                    if !unchanged {
+                                        self.save_row(&structure, row);
+                                    }
+                                }
+                                self.erp_data.data.insert(structure_id.clone(), rows_data);
                             }
 
-                            // 데이터를 한 번에 업데이트하고 저장
-                            if self.erp_data.data.get(&structure_name) != Some(&rows_data) {
-                                self.erp_data.data.insert(structure_name, rows_data);
-                                self.save_erp_data();
+                            // 참조 규칙에 따라 삭제 (참조하는 다른 행도 바뀔 수 있음)
+                            if let Some(row_id) = row_to_remove {
+                                self.delete_error = self
+                                    .delete_row_with_references(&structure_id, &row_id)
+                                    .err();
                             }
                         });
                 });
+
+                self.grid_views.insert(structure.id.clone(), view);
+
+                if let Some((row_id, field_id)) = open_lines {
+                    self.line_editor = Some(line_items::LineEditor {
+                        structure_id: structure.id.clone(),
+                        row_id,
+                        field_id,
+                    });
+                }
+
+                // 참조한 행이 있는 구조체로 이동
+                if let Some((structure_id, row_id)) = navigate {
+                    if !self.erp_data.data.contains_key(&structure_id) {
+                        self.load_structure_data(&structure_id);
+                    }
+                    // 이동한 행이 필터에 가려지지 않도록
+                    if let Some(view) = self.grid_views.get_mut(&structure_id) {
+                        view.filter.clear();
+                    }
+                    self.selected_structure = Some(structure_id);
+                    self.focus_row = Some(row_id);
+                    self.scroll_to_focus = true;
+                    self.delete_error = None;
+                }
             }
         } else {
             ui.vertical_centered(|ui| {
@@ -907,64 +1782,20 @@ impl ERPApp {
         }
     }
     fn save_as_csv(&self, structure: &CustomStructure) -> Result<(), Box<dyn std::error::Error>> {
-        let mut wtr = csv::Writer::from_path(format!("{}.csv", structure.name))?;
-
-        // Write headers
-        let headers: Vec<String> = structure
-            .fields
-            .iter()
-            .map(|field| field.name.clone())
-            .collect();
-        wtr.write_record(&headers)?;
-
-        // Write data
-        if let Some(rows) = self.erp_data.data.get(&structure.name) {
-            for row_data in rows {
-                let record: Vec<String> = structure
-                    .fields
-                    .iter()
-                    .map(|field| {
-                        row_data
-                            .get(&field.name)
-                            .map(|fv| fv.value.clone())
-                            .unwrap_or_default()
-                    })
-                    .collect();
-                wtr.write_record(&record)?;
-            }
-        }
-
-        wtr.flush()?;
-        Ok(())
+        self.write_csv(
+            structure,
+            &self.csv_path(structure),
+            &self.settings.csv_backup,
+        )
     }
 
     fn load_from_csv(
         &mut self,
         structure: &CustomStructure,
     ) -> Result<(), Box<dyn std::error::Error>> {
-        let mut rdr = csv::Reader::from_path(format!("{}.csv", structure.name))?;
-        let mut new_rows = Vec::new();
-
-        for result in rdr.records() {
-            let record = result?;
-            let mut row_data = HashMap::new();
-
-            for (idx, field) in structure.fields.iter().enumerate() {
-                let value = record.get(idx).unwrap_or_default().to_string();
-                row_data.insert(
-                    field.name.clone(),
-                    FieldValue {
-                        value,
-                        field_type: field.field_type.clone(),
-                    },
-                );
-            }
-
-            new_rows.push(row_data);
-        }
-
-        self.erp_data.data.insert(structure.name.clone(), new_rows);
-        self.save_erp_data();
+        let new_rows = csv_import::read_csv_rows(structure, &self.csv_path(structure))?;
+        self.erp_data.data.insert(structure.id.clone(), new_rows);
+        self.save_structure_rows(&structure.id);
         Ok(())
     }
     fn render_top_menu(&mut self, ui: &mut Ui) {
@@ -1043,10 +1874,10 @@ impl ERPApp {
                                     let selected = self
                                         .selected_structure
                                         .as_ref()
-                                        .map_or(false, |s| s == &structure.name);
+                                        .map_or(false, |s| s == &structure.id);
 
                                     if ui.selectable_label(selected, &structure.name).clicked() {
-                                        structure_selection = Some(structure.name.clone());
+                                        structure_selection = Some(structure.id.clone());
                                     }
                                 }
                             });
@@ -1059,12 +1890,12 @@ impl ERPApp {
                     self.expanded_subcategories.insert(key, value);
                 }
 
-                if let Some(structure_name) = structure_selection {
-                    self.selected_structure = Some(structure_name.clone());
+                if let Some(structure_id) = structure_selection {
+                    self.selected_structure = Some(structure_id.clone());
                     self.show_setting_panel = false;
 
-                    if !self.erp_data.data.contains_key(&structure_name) {
-                        self.load_structure_data(&structure_name);
+                    if !self.erp_data.data.contains_key(&structure_id) {
+                        self.load_structure_data(&structure_id);
                     }
                 }
             });
@@ -1122,6 +1953,11 @@ impl eframe::App for ERPApp {
 
         ctx.set_fonts(fonts);
 
+        if self.workspace.is_none() {
+            self.render_workspace_picker(ctx);
+            return;
+        }
+
         // egui::TopBottomPanel::top("top_panel").show(ctx, |ui| {
         //     self.render_top_menu(ui);
         // });
@@ -1140,12 +1976,22 @@ impl eframe::App for ERPApp {
             }
         });
         self.render_category_selector_popup(ctx);
+        self.render_migration_preview(ctx);
+        self.render_recovery_prompt(ctx);
+        self.render_reconcile_dialog(ctx);
+        self.render_csv_import(ctx);
+        self.render_csv_export(ctx);
+        self.render_excel_import(ctx);
+        self.render_category_import(ctx);
+        self.render_structure_source_import(ctx);
+        self.render_line_editor(ctx);
+        self.refresh_related();
+        self.flush_backups(ctx);
     }
 }
 
 fn main() {
-    let mut app: ERPApp = ERPApp::default();
-    app.load_custom_structures();
+    let app: ERPApp = ERPApp::new(workspace::workspace_arg());
 
     let options = eframe::NativeOptions {
         viewport: egui::ViewportBuilder::default().with_inner_size([980.0, 900.0]),
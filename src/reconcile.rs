@@ -0,0 +1,238 @@
+// 시작 시 주 저장소와 구조체별 CSV 백업 비교
+use crate::csv_import::read_csv_rows;
+use crate::{CustomStructure, ERPApp, Row};
+use chrono::{DateTime, Local};
+use eframe::egui;
+use egui::{Context, ScrollArea};
+use std::collections::hash_map::DefaultHasher;
+use std::fs;
+use std::hash::{Hash, Hasher};
+use std::path::Path;
+use std::time::{Duration, Instant, SystemTime};
+
+// 표에서 고친 뒤 CSV 백업을 다시 쓰기까지 기다리는 시간
+const BACKUP_DELAY: Duration = Duration::from_secs(2);
+
+// 저장소와 CSV 백업의 내용이 다른 구조체
+#[derive(Clone, Debug)]
+pub(crate) struct BackupMismatch {
+    pub(crate) structure_id: String,
+    pub(crate) structure_name: String,
+    pub(crate) store_rows: usize,
+    pub(crate) csv_rows: usize,
+    pub(crate) store_modified: Option<SystemTime>,
+    pub(crate) csv_modified: Option<SystemTime>,
+    pub(crate) store_hash: u64,
+    pub(crate) csv_hash: u64,
+}
+
+// 예전 백업에는 행 ID가 없으므로 필드 순서대로 늘어놓은 값만 해시
+fn content_hash(structure: &CustomStructure, rows: &[Row]) -> u64 {
+    let mut hasher = DefaultHasher::new();
+    for row in rows {
+        for field in &structure.fields {
+            row.values
+                .get(&field.id)
+                .map(|v| v.value.as_str())
+                .unwrap_or_default()
+                .hash(&mut hasher);
+        }
+    }
+    hasher.finish()
+}
+
+fn modified(path: &Path) -> Option<SystemTime> {
+    fs::metadata(path).and_then(|m| m.modified()).ok()
+}
+
+fn format_time(time: Option<SystemTime>) -> String {
+    match time {
+        Some(time) => DateTime::<Local>::from(time)
+            .format("%Y-%m-%d %H:%M:%S")
+            .to_string(),
+        None => "알 수 없음".to_string(),
+    }
+}
+
+impl ERPApp {
+    // 저장소에서 읽은 데이터를 CSV 백업과 비교.
+    // 저장소에 없는 구조체는 CSV에서 가져오고, 양쪽이 다르면 사용자에게 묻는다
+    pub(crate) fn reconcile_backups(&mut self) {
+        self.backup_mismatches.clear();
+        let store_modified = self.storage.modified();
+
+        let structures: Vec<CustomStructure> = self
+            .custom_structures
+            .iter()
+            .flat_map(|c| c.subcategories.iter())
+            .flat_map(|s| s.structures.iter())
+            .cloned()
+            .collect();
+
+        for structure in structures {
+            let path = self.csv_path(&structure);
+            if !path.is_file() {
+                // 백업이 없으면 저장소 내용으로 새로 만든다
+                if self.erp_data.data.contains_key(&structure.id) {
+                    self.save_to_csv(&structure.id);
+                }
+                continue;
+            }
+
+            let csv_rows = match read_csv_rows(&structure, &path) {
+                Ok(rows) => rows,
+                Err(e) => {
+                    eprintln!("CSV 백업을 읽을 수 없습니다 ({}): {}", path.display(), e);
+                    continue;
+                }
+            };
+
+            let Some(store_rows) = self.erp_data.data.get(&structure.id) else {
+                // 이전 버전은 구조체를 열 때 CSV에서 읽었으므로 저장소에 없으면 CSV가 원본
+                println!("CSV 백업에서 가져옴: {}", structure.name);
+                self.erp_data.data.insert(structure.id.clone(), csv_rows);
+                self.save_structure_rows(&structure.id);
+                continue;
+            };
+
+            let store_hash = content_hash(&structure, store_rows);
+            let csv_hash = content_hash(&structure, &csv_rows);
+            if store_hash != csv_hash {
+                self.backup_mismatches.push(BackupMismatch {
+                    structure_id: structure.id.clone(),
+                    structure_name: structure.name.clone(),
+                    store_rows: store_rows.len(),
+                    csv_rows: csv_rows.len(),
+                    store_modified,
+                    csv_modified: modified(&path),
+                    store_hash,
+                    csv_hash,
+                });
+            }
+        }
+    }
+
+    // 확인을 기다리는 구조체의 CSV는 덮어쓰지 않는다
+    pub(crate) fn backup_unresolved(&self, structure_id: &str) -> bool {
+        self.backup_mismatches
+            .iter()
+            .any(|m| m.structure_id == structure_id)
+    }
+
+    // 저장소 내용을 유지하고 CSV 백업을 다시 작성
+    fn keep_store(&mut self, structure_id: &str) {
+        self.backup_mismatches
+            .retain(|m| m.structure_id != structure_id);
+        self.save_to_csv(structure_id);
+    }
+
+    // 표에서 고친 행을 CSV 백업에 반영. 입력할 때마다 파일을 다시 쓰지 않도록 잠시 기다리고,
+    // 창을 닫을 때는 바로 쓴다 (다음 시작 때 불일치로 묻지 않도록)
+    pub(crate) fn flush_backups(&mut self, ctx: &Context) {
+        if self.backup_dirty.is_empty() {
+            return;
+        }
+        let closing = ctx.input(|i| i.viewport().close_requested());
+        let now = Instant::now();
+        let due: Vec<String> = self
+            .backup_dirty
+            .iter()
+            .filter(|(_, edited)| closing || now.duration_since(**edited) >= BACKUP_DELAY)
+            .map(|(structure_id, _)| structure_id.clone())
+            .collect();
+        for structure_id in due {
+            self.backup_dirty.remove(&structure_id);
+            self.save_to_csv(&structure_id);
+        }
+        if !self.backup_dirty.is_empty() {
+            ctx.request_repaint_after(BACKUP_DELAY);
+        }
+    }
+
+    // CSV 백업 내용으로 저장소 덮어쓰기
+    fn restore_from_csv(&mut self, structure_id: &str) {
+        let Some(structure) = self.find_structure(structure_id) else {
+            return;
+        };
+        match read_csv_rows(&structure, &self.csv_path(&structure)) {
+            Ok(rows) => {
+                self.backup_mismatches
+                    .retain(|m| m.structure_id != structure_id);
+                self.erp_data.data.insert(structure.id.clone(), rows);
+                self.save_structure_rows(&structure.id);
+                println!("CSV 백업으로 복원했습니다: {}", structure.name);
+            }
+            Err(e) => eprintln!("CSV 백업을 읽을 수 없습니다: {}", e),
+        }
+    }
+
+    pub(crate) fn render_reconcile_dialog(&mut self, ctx: &Context) {
+        if self.backup_mismatches.is_empty() || !self.recovery.is_empty() {
+            return;
+        }
+
+        let mut keep = None;
+        let mut restore = None;
+
+        egui::Window::new("저장소와 CSV 백업이 다릅니다")
+            .collapsible(false)
+            .default_size([520.0, 360.0])
+            .show(ctx, |ui| {
+                ui.label(
+                    "어느 쪽을 남길지 선택하세요. 선택하기 전에는 CSV 백업을 덮어쓰지 않습니다.",
+                );
+                ui.separator();
+                ScrollArea::vertical()
+                    .id_salt("backup_mismatches")
+                    .max_height(300.0)
+                    .show(ui, |ui| {
+                        for mismatch in &self.backup_mismatches {
+                            let store_newer = mismatch.store_modified > mismatch.csv_modified;
+                            ui.group(|ui| {
+                                ui.strong(&mismatch.structure_name);
+                                egui::Grid::new(("mismatch", &mismatch.structure_id))
+                                    .num_columns(4)
+                                    .show(ui, |ui| {
+                                        ui.label("");
+                                        ui.label("행 수");
+                                        ui.label("수정 시각");
+                                        ui.label("내용 해시");
+                                        ui.end_row();
+
+                                        ui.label(if store_newer {
+                                            "저장소 (최신)"
+                                        } else {
+                                            "저장소"
+                                        });
+                                        ui.label(mismatch.store_rows.to_string());
+                                        ui.label(format_time(mismatch.store_modified));
+                                        ui.label(format!("{:016x}", mismatch.store_hash));
+                                        ui.end_row();
+
+                                        ui.label(if store_newer { "CSV" } else { "CSV (최신)" });
+                                        ui.label(mismatch.csv_rows.to_string());
+                                        ui.label(format_time(mismatch.csv_modified));
+                                        ui.label(format!("{:016x}", mismatch.csv_hash));
+                                        ui.end_row();
+                                    });
+                                ui.horizontal(|ui| {
+                                    if ui.button("저장소 유지 (CSV 다시 쓰기)").clicked() {
+                                        keep = Some(mismatch.structure_id.clone());
+                                    }
+                                    if ui.button("CSV로 복원").clicked() {
+                                        restore = Some(mismatch.structure_id.clone());
+                                    }
+                                });
+                            });
+                        }
+                    });
+            });
+
+        if let Some(structure_id) = keep {
+            self.keep_store(&structure_id);
+        }
+        if let Some(structure_id) = restore {
+            self.restore_from_csv(&structure_id);
+        }
+    }
+}
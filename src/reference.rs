@@ -0,0 +1,394 @@
+// 참조 필드: 다른 구조체의 행을 가리킨다. 값은 참조하는 행의 ID
+use crate::csv_import::ImportError;
+use crate::{CustomStructure, ERPApp, Field, FieldType, Row};
+use eframe::egui;
+use egui::Response;
+use serde::{Deserialize, Serialize};
+use std::collections::{HashMap, HashSet};
+
+// 선택 목록에 한 번에 보여 줄 최대 행 수
+const MAX_PICKER_ROWS: usize = 50;
+
+// 참조된 행을 지울 때의 규칙
+#[derive(Clone, Copy, Default, Serialize, Deserialize, PartialEq, Debug)]
+pub(crate) enum OnDelete {
+    #[default]
+    Restrict, // 참조하는 행이 있으면 삭제 막기
+    Cascade, // 참조하는 행도 함께 삭제
+    SetNull, // 참조하는 행의 값을 비우기
+}
+
+impl OnDelete {
+    fn label(&self) -> &'static str {
+        match self {
+            OnDelete::Restrict => "삭제 막기",
+            OnDelete::Cascade => "함께 삭제",
+            OnDelete::SetNull => "값 비우기",
+        }
+    }
+}
+
+#[derive(Clone, Default, Serialize, Deserialize, PartialEq, Debug)]
+pub(crate) struct ReferenceTarget {
+    pub(crate) structure_id: String,
+    #[serde(default)]
+    pub(crate) display_field_id: String, // 행 대신 보여 줄 필드
+    #[serde(default)]
+    pub(crate) on_delete: OnDelete,
+}
+
+fn field_value<'a>(row: &'a Row, field_id: &str) -> &'a str {
+    row.values
+        .get(field_id)
+        .map(|v| v.value.as_str())
+        .unwrap_or_default()
+}
+
+// 표시 필드 값, 비어 있으면 처음으로 값이 있는 필드
+fn row_label(structure: &CustomStructure, row: &Row, display_field_id: &str) -> String {
+    let label = field_value(row, display_field_id);
+    if !label.is_empty() {
+        return label.to_string();
+    }
+    structure
+        .fields
+        .iter()
+        .map(|f| field_value(row, &f.id))
+        .find(|v| !v.is_empty())
+        .unwrap_or("(빈 행)")
+        .to_string()
+}
+
+fn label_rows(
+    structure: &CustomStructure,
+    rows: &[Row],
+    display_field_id: &str,
+) -> Vec<(String, String)> {
+    rows.iter()
+        .map(|row| (row.id.clone(), row_label(structure, row, display_field_id)))
+        .collect()
+}
+
+// 참조 필드 ID -> 선택 목록 (행 ID, 표시 값). 가져오기 미리보기에서 한 번 만들어 둔다
+pub(crate) type ReferenceOptions = HashMap<String, Vec<(String, String)>>;
+
+// 가져온 값이 행 ID가 아니면 표시 값으로 행을 찾아 ID로 바꾼다. 못 찾은 값은 비우고 오류로
+pub(crate) fn resolve_with(
+    structure: &CustomStructure,
+    references: &ReferenceOptions,
+    rows: &mut [Row],
+) -> Vec<ImportError> {
+    let mut errors = Vec::new();
+    for field in &structure.fields {
+        if field.field_type != FieldType::Reference {
+            continue;
+        }
+        let options = references
+            .get(&field.id)
+            .map(Vec::as_slice)
+            .unwrap_or_default();
+        for (idx, row) in rows.iter_mut().enumerate() {
+            let Some(field_value) = row.values.get_mut(&field.id) else {
+                continue;
+            };
+            let value = field_value.value.trim().to_string();
+            if value.is_empty() || options.iter().any(|(id, _)| *id == value) {
+                continue;
+            }
+            match options.iter().find(|(_, label)| *label == value) {
+                Some((id, _)) => field_value.value = id.clone(),
+                None => {
+                    errors.push(ImportError {
+                        row: idx + 1,
+                        column: field.name.clone(),
+                        value: value.clone(),
+                        error: "참조할 행을 찾을 수 없습니다".to_string(),
+                    });
+                    field_value.value = String::new();
+                }
+            }
+        }
+    }
+    errors
+}
+
+// 구조체 편집기의 참조 대상, 표시 필드, 삭제 규칙 선택
+pub(crate) fn reference_editor(
+    ui: &mut egui::Ui,
+    idx: usize,
+    field: &mut Field,
+    structures: &[CustomStructure],
+) {
+    let target = field.reference.get_or_insert_with(ReferenceTarget::default);
+    let target_structure = structures.iter().find(|s| s.id == target.structure_id);
+    ui.horizontal(|ui| {
+        ui.label("참조 대상:");
+        egui::ComboBox::from_id_salt(("reference_structure", idx))
+            .selected_text(target_structure.map_or("선택 안 됨", |s| s.name.as_str()))
+            .show_ui(ui, |ui| {
+                for structure in structures {
+                    if ui
+                        .selectable_label(target.structure_id == structure.id, &structure.name)
+                        .clicked()
+                    {
+                        target.structure_id = structure.id.clone();
+                        target.display_field_id = structure
+                            .fields
+                            .first()
+                            .map(|f| f.id.clone())
+                            .unwrap_or_default();
+                    }
+                }
+            });
+
+        if let Some(structure) = target_structure {
+            ui.label("표시 필드:");
+            let display = structure
+                .fields
+                .iter()
+                .find(|f| f.id == target.display_field_id)
+                .map_or("선택 안 됨", |f| f.name.as_str());
+            egui::ComboBox::from_id_salt(("reference_display", idx))
+                .selected_text(display)
+                .show_ui(ui, |ui| {
+                    for f in &structure.fields {
+                        ui.selectable_value(&mut target.display_field_id, f.id.clone(), &f.name);
+                    }
+                });
+        }
+    });
+    ui.horizontal(|ui| {
+        ui.label("참조된 행 삭제 시:");
+        for rule in [OnDelete::Restrict, OnDelete::Cascade, OnDelete::SetNull] {
+            ui.radio_value(&mut target.on_delete, rule, rule.label());
+        }
+    });
+}
+
+// 표의 참조 셀: 검색할 수 있는 선택 목록과 참조한 행으로 이동하는 버튼.
+// 이동 버튼을 누르면 true
+pub(crate) fn reference_cell(
+    ui: &mut egui::Ui,
+    id: impl std::hash::Hash,
+    options: &[(String, String)],
+    value: &mut String,
+) -> (Response, bool) {
+    let id = ui.make_persistent_id(id);
+    let before = value.clone();
+    let mut navigate = false;
+    let selected = match options.iter().find(|(row_id, _)| row_id == value) {
+        Some((_, label)) => egui::RichText::new(label),
+        None if value.is_empty() => egui::RichText::new(""),
+        None => egui::RichText::new("(없는 행)").color(egui::Color32::RED),
+    };
+
+    let mut response = ui
+        .horizontal(|ui| {
+            egui::ComboBox::from_id_salt(id)
+                .selected_text(selected)
+                .show_ui(ui, |ui| {
+                    let mut search: String =
+                        ui.data_mut(|d| d.get_temp(id.with("search")).unwrap_or_default());
+                    ui.add(egui::TextEdit::singleline(&mut search).hint_text("🔍 검색"));
+                    let query = search.to_lowercase();
+                    ui.selectable_value(value, String::new(), "(없음)");
+                    for (row_id, label) in options
+                        .iter()
+                        .filter(|(_, label)| label.to_lowercase().contains(&query))
+                        .take(MAX_PICKER_ROWS)
+                    {
+                        ui.selectable_value(value, row_id.clone(), label);
+                    }
+                    ui.data_mut(|d| d.insert_temp(id.with("search"), search));
+                });
+            if !value.is_empty()
+                && ui
+                    .small_button("↗")
+                    .on_hover_text("참조한 행으로 이동")
+                    .clicked()
+            {
+                navigate = true;
+            }
+        })
+        .response;
+    if *value != before {
+        response.mark_changed();
+    }
+    (response, navigate)
+}
+
+impl ERPApp {
+    // 참조 필드의 선택 목록: (행 ID, 표시 값)
+    pub(crate) fn reference_options(&self, field: &Field) -> Vec<(String, String)> {
+        self.pending_reference_options(field, &[])
+    }
+
+    // 아직 저장하지 않은 구조체와 행(통합 문서의 다른 시트)이 있으면 그 행에서 찾는다
+    fn pending_reference_options(
+        &self,
+        field: &Field,
+        pending: &[(&CustomStructure, &[Row])],
+    ) -> Vec<(String, String)> {
+        let Some(target) = &field.reference else {
+            return Vec::new();
+        };
+        if let Some((structure, rows)) = pending.iter().find(|(s, _)| s.id == target.structure_id) {
+            return label_rows(structure, rows, &target.display_field_id);
+        }
+        let Some(structure) = self.find_structure(&target.structure_id) else {
+            return Vec::new();
+        };
+        self.erp_data
+            .data
+            .get(&structure.id)
+            .map(|rows| label_rows(&structure, rows, &target.display_field_id))
+            .unwrap_or_default()
+    }
+
+    // 구조체의 참조 필드마다 선택 목록
+    pub(crate) fn reference_lookup(
+        &self,
+        structure: &CustomStructure,
+        pending: &[(&CustomStructure, &[Row])],
+    ) -> ReferenceOptions {
+        structure
+            .fields
+            .iter()
+            .filter(|f| f.field_type == FieldType::Reference)
+            .map(|f| (f.id.clone(), self.pending_reference_options(f, pending)))
+            .collect()
+    }
+
+    // structure_id를 가리키는 모든 참조 필드: (참조하는 구조체, 필드)
+    fn referencing_fields(&self, structure_id: &str) -> Vec<(CustomStructure, Field)> {
+        self.custom_structures
+            .iter()
+            .flat_map(|c| c.subcategories.iter())
+            .flat_map(|s| s.structures.iter())
+            .flat_map(|s| {
+                s.fields
+                    .iter()
+                    .filter(|f| {
+                        f.field_type == FieldType::Reference
+                            && f.reference
+                                .as_ref()
+                                .is_some_and(|r| r.structure_id == structure_id)
+                    })
+                    .map(move |f| (s.clone(), f.clone()))
+            })
+            .collect()
+    }
+
+    // 내보내기용: 참조 값을 행 ID 대신 표시 값으로
+    pub(crate) fn display_rows(&self, structure: &CustomStructure, rows: &[Row]) -> Vec<Row> {
+        let mut rows = rows.to_vec();
+        for field in &structure.fields {
+            if field.field_type != FieldType::Reference {
+                continue;
+            }
+            let options = self.reference_options(field);
+            for row in &mut rows {
+                if let Some(field_value) = row.values.get_mut(&field.id) {
+                    if let Some((_, label)) =
+                        options.iter().find(|(id, _)| *id == field_value.value)
+                    {
+                        field_value.value = label.clone();
+                    }
+                }
+            }
+        }
+        rows
+    }
+
+    // 참조 규칙을 따라 행 삭제. 삭제 막기 규칙에 걸리면 아무것도 지우지 않고 Err
+    pub(crate) fn delete_row_with_references(
+        &mut self,
+        structure_id: &str,
+        row_id: &str,
+    ) -> Result<(), String> {
+        let mut to_delete = vec![(structure_id.to_string(), row_id.to_string())];
+        let mut to_clear: Vec<(String, String, String)> = Vec::new(); // 구조체, 행, 필드
+        let mut queue = to_delete.clone();
+
+        while let Some((target_structure, target_row)) = queue.pop() {
+            for (structure, field) in self.referencing_fields(&target_structure) {
+                let rule = field
+                    .reference
+                    .as_ref()
+                    .map(|r| r.on_delete)
+                    .unwrap_or_default();
+                let rows = self
+                    .erp_data
+                    .data
+                    .get(&structure.id)
+                    .cloned()
+                    .unwrap_or_default();
+                for row in rows
+                    .iter()
+                    .filter(|r| field_value(r, &field.id) == target_row)
+                {
+                    let key = (structure.id.clone(), row.id.clone());
+                    if to_delete.contains(&key) {
+                        continue;
+                    }
+                    match rule {
+                        OnDelete::Restrict => {
+                            let first = structure.fields.first().map(|f| f.id.as_str());
+                            return Err(format!(
+                                "{}의 '{}' 행이 {} 필드로 참조하고 있어 삭제할 수 없습니다",
+                                structure.name,
+                                row_label(&structure, row, first.unwrap_or_default()),
+                                field.name
+                            ));
+                        }
+                        OnDelete::Cascade => {
+                            to_delete.push(key.clone());
+                            queue.push(key);
+                        }
+                        OnDelete::SetNull => {
+                            to_clear.push((structure.id.clone(), row.id.clone(), field.id.clone()))
+                        }
+                    }
+                }
+            }
+        }
+
+        let mut touched = HashSet::new();
+        for (structure_id, row_id, field_id) in to_clear {
+            if to_delete.contains(&(structure_id.clone(), row_id.clone())) {
+                continue;
+            }
+            let Some(structure) = self.find_structure(&structure_id) else {
+                continue;
+            };
+            let Some(row) = self
+                .erp_data
+                .data
+                .get_mut(&structure_id)
+                .and_then(|rows| rows.iter_mut().find(|r| r.id == row_id))
+            else {
+                continue;
+            };
+            if let Some(value) = row.values.get_mut(&field_id) {
+                value.value.clear();
+            }
+            let row = row.clone();
+            self.save_row(&structure, &row);
+            touched.insert(structure_id);
+        }
+        for (structure_id, row_id) in to_delete {
+            let Some(structure) = self.find_structure(&structure_id) else {
+                continue;
+            };
+            if let Some(rows) = self.erp_data.data.get_mut(&structure_id) {
+                rows.retain(|r| r.id != row_id);
+            }
+            self.remove_row(&structure, &row_id);
+            touched.insert(structure_id);
+        }
+        for structure_id in touched {
+            self.save_to_csv(&structure_id);
+        }
+        Ok(())
+    }
+}
@@ -0,0 +1,546 @@
+// 집계(롤업)와 조회 필드: 다른 구조체의 관련 행을 모아 계산하거나 참조한 행의 값을 가져온다.
+// 행이 바뀐 구조체를 표시해 두었다가 그 구조체에 의존하는 필드만 다시 계산한다
+use crate::currency::{self, parse_decimal};
+use crate::dates;
+use crate::exchange::{base_amount, base_field, ExchangeRates, BASE_CURRENCY, RATES_STRUCTURE_ID};
+use crate::line_items::format_total;
+use crate::schema::convert_value;
+use crate::{CustomStructure, ERPApp, Field, FieldType, Row};
+use eframe::egui;
+use rust_decimal::Decimal;
+use serde::{Deserialize, Serialize};
+use std::collections::{HashMap, HashSet};
+
+// 계산 결과가 다시 다른 구조체를 바꿀 때 한 번에 따라가는 최대 단계 (순환 설정 대비)
+const MAX_PASSES: usize = 5;
+
+#[derive(Clone, Copy, Default, Serialize, Deserialize, PartialEq, Debug)]
+pub(crate) enum RollupFunction {
+    #[default]
+    Sum,
+    Count,
+    Avg,
+    Min,
+    Max,
+    Latest, // 날짜 필드는 가장 늦은 날짜, 그 밖에는 마지막에 추가된 행의 값
+}
+
+impl RollupFunction {
+    const ALL: [RollupFunction; 6] = [
+        RollupFunction::Sum,
+        RollupFunction::Count,
+        RollupFunction::Avg,
+        RollupFunction::Min,
+        RollupFunction::Max,
+        RollupFunction::Latest,
+    ];
+
+    fn label(&self) -> &'static str {
+        match self {
+            RollupFunction::Sum => "합계 (SUM)",
+            RollupFunction::Count => "개수 (COUNT)",
+            RollupFunction::Avg => "평균 (AVG)",
+            RollupFunction::Min => "최소 (MIN)",
+            RollupFunction::Max => "최대 (MAX)",
+            RollupFunction::Latest => "최근 (LATEST)",
+        }
+    }
+}
+
+// 원본 구조체에서 link 필드 값이 이 행의 기준 값(기본은 행 ID)과 같은 행을 모아 집계
+#[derive(Clone, Default, Serialize, Deserialize, PartialEq, Debug)]
+pub(crate) struct Rollup {
+    pub(crate) structure_id: String,
+    pub(crate) link_field_id: String,
+    #[serde(default, skip_serializing_if = "String::is_empty")]
+    pub(crate) key_field_id: String, // 비어 있으면 행 ID (참조 필드로 연결)
+    #[serde(default)]
+    pub(crate) function: RollupFunction,
+    #[serde(default)]
+    pub(crate) value_field_id: String, // 개수에서는 쓰지 않음
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub(crate) filter: Option<RollupFilter>,
+    #[serde(default, skip_serializing_if = "std::ops::Not::not")]
+    pub(crate) to_base: bool, // 거래 통화 금액을 거래일 환율로 원화 환산해서 모은다
+}
+
+// 원본 행 조건: 필드 값이 같은 행만 (대소문자 무시)
+#[derive(Clone, Default, Serialize, Deserialize, PartialEq, Debug)]
+pub(crate) struct RollupFilter {
+    pub(crate) field_id: String,
+    pub(crate) value: String,
+}
+
+// 이 구조체의 참조 필드가 가리키는 행에서 값 하나를 가져온다
+#[derive(Clone, Default, Serialize, Deserialize, PartialEq, Debug)]
+pub(crate) struct Lookup {
+    pub(crate) reference_field_id: String,
+    pub(crate) value_field_id: String,
+}
+
+fn field_value<'a>(row: &'a Row, field_id: &str) -> &'a str {
+    row.values
+        .get(field_id)
+        .map(|v| v.value.as_str())
+        .unwrap_or_default()
+}
+
+// 모은 행들의 값 집계. 값이 하나도 없으면 합계와 개수는 0, 나머지는 빈 값
+pub(crate) fn aggregate(
+    function: RollupFunction,
+    value_field: Option<&Field>,
+    rows: &[&Row],
+) -> String {
+    if function == RollupFunction::Count {
+        return rows.len().to_string();
+    }
+    let Some(field) = value_field else {
+        return String::new();
+    };
+    let values: Vec<&str> = rows
+        .iter()
+        .map(|row| field_value(row, &field.id).trim())
+        .filter(|v| !v.is_empty())
+        .collect();
+
+    // 날짜/시간 필드의 최소/최대/최근은 시간 순서로 비교
+    if dates::is_temporal(&field.field_type)
+        && matches!(
+            function,
+            RollupFunction::Min | RollupFunction::Max | RollupFunction::Latest
+        )
+    {
+        let mut keyed: Vec<(i64, &str)> = values
+            .iter()
+            .filter_map(|v| Some((dates::temporal_key(&field.field_type, v)?, *v)))
+            .collect();
+        keyed.sort_by_key(|(key, _)| *key);
+        let picked = match function {
+            RollupFunction::Min => keyed.first(),
+            _ => keyed.last(),
+        };
+        return picked
+            .and_then(|(_, v)| convert_value(v, &field.field_type).ok())
+            .unwrap_or_default();
+    }
+    if function == RollupFunction::Latest {
+        return values.last().map(|v| v.to_string()).unwrap_or_default();
+    }
+
+    let numbers: Vec<Decimal> = values.iter().filter_map(|v| parse_decimal(v)).collect();
+    let total = match function {
+        RollupFunction::Sum => numbers.iter().sum(),
+        _ if numbers.is_empty() => return String::new(),
+        RollupFunction::Avg => numbers.iter().sum::<Decimal>() / Decimal::from(numbers.len()),
+        RollupFunction::Min => numbers.iter().copied().min().unwrap_or_default(),
+        _ => numbers.iter().copied().max().unwrap_or_default(),
+    };
+    // 통화 필드를 모으면 그 통화의 소수 자릿수로
+    if field.field_type == FieldType::Currency {
+        return currency::normalize_field(field, &total.to_string()).unwrap_or_default();
+    }
+    format_total(total)
+}
+
+// 원화로 환산한 원본 행. 환율이 없는 금액은 빼고 모은다
+fn base_rows(
+    rates: &ExchangeRates,
+    source: &CustomStructure,
+    field: &Field,
+    rows: &[Row],
+) -> Vec<Row> {
+    rows.iter()
+        .map(|row| {
+            let amount = base_amount(rates, source, field, row);
+            let mut row = row.clone();
+            if let Some(field_value) = row.values.get_mut(&field.id) {
+                field_value.value = amount.map(|a| a.to_string()).unwrap_or_default();
+            }
+            row
+        })
+        .collect()
+}
+
+// 집계 한 필드의 값: 이 구조체의 행 ID -> 값
+fn rollup_values(
+    rollup: &Rollup,
+    rows: &[Row],
+    source: &CustomStructure,
+    source_rows: &[Row],
+    rates: &ExchangeRates,
+) -> HashMap<String, String> {
+    let mut value_field = source
+        .fields
+        .iter()
+        .find(|f| f.id == rollup.value_field_id)
+        .cloned();
+    let converted;
+    let source_rows = match &mut value_field {
+        Some(field) if rollup.to_base && field.field_type == FieldType::Currency => {
+            converted = base_rows(rates, source, field, source_rows);
+            *field = base_field(field);
+            converted.as_slice()
+        }
+        _ => source_rows,
+    };
+    let mut groups: HashMap<String, Vec<&Row>> = HashMap::new();
+    for source_row in source_rows {
+        if let Some(filter) = &rollup.filter {
+            let value = field_value(source_row, &filter.field_id).trim();
+            if value.to_lowercase() != filter.value.trim().to_lowercase() {
+                continue;
+            }
+        }
+        let key = field_value(source_row, &rollup.link_field_id).trim();
+        if !key.is_empty() {
+            groups.entry(key.to_string()).or_default().push(source_row);
+        }
+    }
+    rows.iter()
+        .map(|row| {
+            let key = if rollup.key_field_id.is_empty() {
+                row.id.as_str()
+            } else {
+                field_value(row, &rollup.key_field_id).trim()
+            };
+            let matched = groups.get(key).map(Vec::as_slice).unwrap_or_default();
+            (
+                row.id.clone(),
+                aggregate(rollup.function, value_field.as_ref(), matched),
+            )
+        })
+        .collect()
+}
+
+fn structure_combo(
+    ui: &mut egui::Ui,
+    id: impl std::hash::Hash,
+    selected: &mut String,
+    structures: &[CustomStructure],
+) -> bool {
+    let before = selected.clone();
+    let text = structures
+        .iter()
+        .find(|s| s.id == *selected)
+        .map_or("선택 안 됨", |s| s.name.as_str());
+    egui::ComboBox::from_id_salt(id)
+        .selected_text(text)
+        .show_ui(ui, |ui| {
+            for structure in structures {
+                ui.selectable_value(selected, structure.id.clone(), &structure.name);
+            }
+        });
+    *selected != before
+}
+
+// empty_label이 있으면 빈 값(선택 안 함)도 고를 수 있다
+fn field_combo(
+    ui: &mut egui::Ui,
+    id: impl std::hash::Hash,
+    selected: &mut String,
+    fields: &[Field],
+    empty_label: Option<&str>,
+) {
+    let text = fields
+        .iter()
+        .find(|f| f.id == *selected)
+        .map(|f| f.name.as_str())
+        .or(empty_label.filter(|_| selected.is_empty()))
+        .unwrap_or("선택 안 됨");
+    egui::ComboBox::from_id_salt(id)
+        .selected_text(text)
+        .show_ui(ui, |ui| {
+            if let Some(label) = empty_label {
+                ui.selectable_value(selected, String::new(), label);
+            }
+            for field in fields {
+                ui.selectable_value(selected, field.id.clone(), &field.name);
+            }
+        });
+}
+
+// 구조체 편집기의 집계 설정. structures의 마지막은 편집 중인 구조체
+pub(crate) fn rollup_editor(
+    ui: &mut egui::Ui,
+    idx: usize,
+    field: &mut Field,
+    current: &CustomStructure,
+    structures: &[CustomStructure],
+) {
+    let rollup = field.rollup.get_or_insert_with(Rollup::default);
+    ui.horizontal(|ui| {
+        ui.label("원본 구조체:");
+        if structure_combo(
+            ui,
+            ("rollup_structure", idx),
+            &mut rollup.structure_id,
+            structures,
+        ) {
+            // 이 구조체를 가리키는 참조 필드가 있으면 연결 필드로
+            let source = structures.iter().find(|s| s.id == rollup.structure_id);
+            rollup.link_field_id = source
+                .and_then(|s| {
+                    s.fields.iter().find(|f| {
+                        f.reference
+                            .as_ref()
+                            .is_some_and(|r| r.structure_id == current.id)
+                    })
+                })
+                .map(|f| f.id.clone())
+                .unwrap_or_default();
+            rollup.key_field_id.clear();
+            rollup.value_field_id.clear();
+            rollup.filter = None;
+        }
+    });
+    let Some(source) = structures.iter().find(|s| s.id == rollup.structure_id) else {
+        return;
+    };
+    ui.horizontal(|ui| {
+        ui.label("연결:");
+        field_combo(
+            ui,
+            ("rollup_link", idx),
+            &mut rollup.link_field_id,
+            &source.fields,
+            None,
+        );
+        ui.label("=");
+        field_combo(
+            ui,
+            ("rollup_key", idx),
+            &mut rollup.key_field_id,
+            &current.fields,
+            Some("이 행 (참조)"),
+        );
+    });
+    ui.horizontal(|ui| {
+        ui.label("함수:");
+        egui::ComboBox::from_id_salt(("rollup_function", idx))
+            .selected_text(rollup.function.label())
+            .show_ui(ui, |ui| {
+                for function in RollupFunction::ALL {
+                    ui.selectable_value(&mut rollup.function, function, function.label());
+                }
+            });
+        if rollup.function != RollupFunction::Count {
+            ui.label("값 필드:");
+            field_combo(
+                ui,
+                ("rollup_value", idx),
+                &mut rollup.value_field_id,
+                &source.fields,
+                None,
+            );
+            let value_field = source.fields.iter().find(|f| f.id == rollup.value_field_id);
+            if value_field.is_some_and(|f| f.field_type == FieldType::Currency) {
+                ui.checkbox(&mut rollup.to_base, format!("{}로 환산", BASE_CURRENCY))
+                    .on_hover_text("거래 통화 금액을 거래일의 환율표 환율로 바꿔서 모읍니다");
+            }
+        }
+    });
+    ui.horizontal(|ui| {
+        let mut enabled = rollup.filter.is_some();
+        if ui.checkbox(&mut enabled, "조건:").changed() {
+            rollup.filter = enabled.then(RollupFilter::default);
+        }
+        if let Some(filter) = &mut rollup.filter {
+            field_combo(
+                ui,
+                ("rollup_filter", idx),
+                &mut filter.field_id,
+                &source.fields,
+                None,
+            );
+            ui.label("=");
+            ui.add(egui::TextEdit::singleline(&mut filter.value).desired_width(120.0));
+        }
+    });
+}
+
+// 구조체 편집기의 조회 설정
+pub(crate) fn lookup_editor(
+    ui: &mut egui::Ui,
+    idx: usize,
+    field: &mut Field,
+    current: &CustomStructure,
+    structures: &[CustomStructure],
+) {
+    let lookup = field.lookup.get_or_insert_with(Lookup::default);
+    let references: Vec<Field> = current
+        .fields
+        .iter()
+        .filter(|f| f.field_type == FieldType::Reference)
+        .cloned()
+        .collect();
+    if references.is_empty() {
+        ui.label("조회하려면 먼저 참조 필드를 추가해주세요.");
+        return;
+    }
+    ui.horizontal(|ui| {
+        ui.label("참조 필드:");
+        field_combo(
+            ui,
+            ("lookup_reference", idx),
+            &mut lookup.reference_field_id,
+            &references,
+            None,
+        );
+        let target = references
+            .iter()
+            .find(|f| f.id == lookup.reference_field_id)
+            .and_then(|f| f.reference.as_ref())
+            .and_then(|r| structures.iter().find(|s| s.id == r.structure_id));
+        if let Some(target) = target {
+            ui.label("가져올 필드:");
+            field_combo(
+                ui,
+                ("lookup_value", idx),
+                &mut lookup.value_field_id,
+                &target.fields,
+                None,
+            );
+        }
+    });
+}
+
+impl ERPApp {
+    // structure의 집계/조회 필드가 dirty 구조체의 행에 의존하는지
+    fn depends_on(&self, structure: &CustomStructure, dirty: &HashSet<String>) -> bool {
+        structure.fields.iter().any(|field| match field.field_type {
+            FieldType::Rollup => {
+                dirty.contains(&structure.id)
+                    || field.rollup.as_ref().is_some_and(|r| {
+                        dirty.contains(&r.structure_id)
+                            || (r.to_base && dirty.contains(RATES_STRUCTURE_ID))
+                    })
+            }
+            FieldType::Lookup => {
+                dirty.contains(&structure.id)
+                    || field
+                        .lookup
+                        .as_ref()
+                        .and_then(|l| {
+                            structure
+                                .fields
+                                .iter()
+                                .find(|f| f.id == l.reference_field_id)
+                        })
+                        .and_then(|f| f.reference.as_ref())
+                        .is_some_and(|r| dirty.contains(&r.structure_id))
+            }
+            _ => false,
+        })
+    }
+
+    // 구조체의 집계/조회 필드를 다시 계산하고 값이 바뀐 행만 저장
+    fn recompute_related(&mut self, structure: &CustomStructure) {
+        let Some(mut rows) = self.erp_data.data.get(&structure.id).cloned() else {
+            return;
+        };
+        for field in &structure.fields {
+            let values = match (field.field_type.clone(), &field.rollup, &field.lookup) {
+                (FieldType::Rollup, Some(rollup), _) => {
+                    let Some(source) = self.find_structure(&rollup.structure_id) else {
+                        continue;
+                    };
+                    let source_rows = self
+                        .erp_data
+                        .data
+                        .get(&source.id)
+                        .map(Vec::as_slice)
+                        .unwrap_or_default();
+                    rollup_values(rollup, &rows, &source, source_rows, &self.exchange_rates())
+                }
+                (FieldType::Lookup, _, Some(lookup)) => {
+                    let target_id = structure
+                        .fields
+                        .iter()
+                        .find(|f| f.id == lookup.reference_field_id)
+                        .and_then(|f| f.reference.as_ref())
+                        .map(|r| r.structure_id.clone())
+                        .unwrap_or_default();
+                    let targets: HashMap<&str, &str> = self
+                        .erp_data
+                        .data
+                        .get(&target_id)
+                        .map(Vec::as_slice)
+                        .unwrap_or_default()
+                        .iter()
+                        .map(|r| (r.id.as_str(), field_value(r, &lookup.value_field_id)))
+                        .collect();
+                    rows.iter()
+                        .map(|row| {
+                            let target = field_value(row, &lookup.reference_field_id);
+                            let value = targets.get(target).copied().unwrap_or_default();
+                            (row.id.clone(), value.to_string())
+                        })
+                        .collect()
+                }
+                _ => continue,
+            };
+            for row in &mut rows {
+                let value = values.get(&row.id).cloned().unwrap_or_default();
+                let field_value = row.values.entry(field.id.clone()).or_default();
+                field_value.value = value;
+                field_value.field_type = field.field_type.clone();
+            }
+        }
+
+        let stored = self
+            .erp_data
+            .data
+            .get(&structure.id)
+            .cloned()
+            .unwrap_or_default();
+        let changed: Vec<Row> = rows
+            .iter()
+            .filter(|row| !stored.contains(row))
+            .cloned()
+            .collect();
+        if changed.is_empty() {
+            return;
+        }
+        for row in &changed {
+            self.save_row(structure, row);
+        }
+        self.erp_data.data.insert(structure.id.clone(), rows);
+        self.save_to_csv(&structure.id);
+    }
+
+    // 행이 바뀐 구조체에 의존하는 집계/조회 필드를 갱신 (화면을 그린 뒤 한 번)
+    pub(crate) fn refresh_related(&mut self) {
+        for _ in 0..MAX_PASSES {
+            if self.related_dirty.is_empty() {
+                return;
+            }
+            let dirty = std::mem::take(&mut self.related_dirty);
+            let structures: Vec<CustomStructure> = self
+                .custom_structures
+                .iter()
+                .flat_map(|c| c.subcategories.iter())
+                .flat_map(|s| s.structures.iter())
+                .cloned()
+                .collect();
+            for structure in &structures {
+                if self.depends_on(structure, &dirty) {
+                    self.recompute_related(structure);
+                }
+            }
+        }
+        // 서로를 집계하는 설정이면 여기서 멈춘다
+        self.related_dirty.clear();
+    }
+
+    // 구조체 정의가 바뀌거나 데이터를 다시 읽은 뒤에는 모두 다시 계산
+    pub(crate) fn mark_all_related(&mut self) {
+        let ids: Vec<String> = self
+            .custom_structures
+            .iter()
+            .flat_map(|c| c.subcategories.iter())
+            .flat_map(|s| s.structures.iter())
+            .map(|s| s.id.clone())
+            .collect();
+        self.related_dirty.extend(ids);
+    }
+}
@@ -0,0 +1,211 @@
+// 표 보기: 열 머리글을 눌러 정렬하고 필터 입력으로 행을 거른다 (저장된 행 순서는 바꾸지 않는다)
+use crate::attachments;
+use crate::currency;
+use crate::dates;
+use crate::korean_id;
+use crate::{CustomStructure, Field, FieldType, Row};
+use rust_decimal::Decimal;
+use std::cmp::Ordering;
+use std::collections::HashMap;
+
+#[derive(Clone, Default)]
+pub(crate) struct GridView {
+    pub(crate) sort: Option<(String, bool)>, // 필드 ID, 내림차순 여부
+    pub(crate) filter: String,
+}
+
+impl GridView {
+    // 오름차순 -> 내림차순 -> 정렬 없음
+    pub(crate) fn toggle_sort(&mut self, field_id: &str) {
+        self.sort = match &self.sort {
+            Some((id, false)) if id == field_id => Some((id.clone(), true)),
+            Some((id, true)) if id == field_id => None,
+            _ => Some((field_id.to_string(), false)),
+        };
+    }
+
+    pub(crate) fn sort_marker(&self, field_id: &str) -> &'static str {
+        match &self.sort {
+            Some((id, false)) if id == field_id => " ▲",
+            Some((id, true)) if id == field_id => " ▼",
+            _ => "",
+        }
+    }
+}
+
+fn field_value<'a>(row: &'a Row, field_id: &str) -> &'a str {
+    row.values
+        .get(field_id)
+        .map(|v| v.value.as_str())
+        .unwrap_or_default()
+}
+
+// 화면에 보이는 값: 참조는 표시 값, 날짜/시간은 표시 형식, 첨부 파일은 파일 이름, 식별 번호는 가린 값
+fn display_value(
+    field: &Field,
+    value: &str,
+    reference_options: &HashMap<String, Vec<(String, String)>>,
+) -> String {
+    if field.field_type == FieldType::Reference {
+        if let Some((_, label)) = reference_options
+            .get(&field.id)
+            .and_then(|options| options.iter().find(|(id, _)| id == value))
+        {
+            return label.clone();
+        }
+    }
+    if dates::is_temporal(&field.field_type) {
+        return dates::display(field, value);
+    }
+    if field.field_type == FieldType::Currency {
+        return currency::display_field(field, value);
+    }
+    if korean_id::is_identifier(&field.field_type) {
+        return korean_id::display(field, value);
+    }
+    if field.field_type == FieldType::Attachment {
+        if let Ok(attachments) = attachments::parse_attachments(value) {
+            let names: Vec<&str> = attachments.iter().map(|a| a.name.as_str()).collect();
+            return names.join(", ");
+        }
+    }
+    value.to_string()
+}
+
+// 정렬 키: 날짜/시간은 시간 순서, 숫자는 크기, 나머지는 보이는 글자 순서.
+// 읽을 수 없는 값이 섞여 있으면 읽을 수 있는 값 뒤에 글자 순서로 (열마다 한 가지 순서가 되도록)
+#[derive(PartialEq, Eq, PartialOrd, Ord, Debug)]
+enum SortKey {
+    Temporal(i64),
+    Number(Decimal),
+    Text(String),
+}
+
+fn sort_key(
+    field: &Field,
+    value: &str,
+    reference_options: &HashMap<String, Vec<(String, String)>>,
+) -> SortKey {
+    if let Some(key) = dates::temporal_key(&field.field_type, value) {
+        return SortKey::Temporal(key);
+    }
+    if matches!(
+        field.field_type,
+        FieldType::Number | FieldType::Currency | FieldType::Formula | FieldType::Rollup
+    ) {
+        if let Some(number) = currency::parse_decimal(value) {
+            return SortKey::Number(number);
+        }
+    }
+    SortKey::Text(display_value(field, value, reference_options).to_lowercase())
+}
+
+// "필드 이름:조건"이면 그 필드에서만, 아니면 모든 필드에서 찾는다.
+// 날짜/시간 필드는 기간과 비교 조건으로, 나머지는 보이는 값에 포함되는지로 본다
+fn row_matches(
+    structure: &CustomStructure,
+    row: &Row,
+    query: &str,
+    reference_options: &HashMap<String, Vec<(String, String)>>,
+) -> bool {
+    let query = query.trim();
+    if query.is_empty() {
+        return true;
+    }
+    let scoped = query.split_once(':').and_then(|(name, condition)| {
+        structure
+            .fields
+            .iter()
+            .find(|f| f.name.eq_ignore_ascii_case(name.trim()))
+            .map(|field| (vec![field], condition.trim()))
+    });
+    let (fields, condition) = scoped.unwrap_or_else(|| (structure.fields.iter().collect(), query));
+    let lower = condition.to_lowercase();
+    fields.iter().any(|field| {
+        let value = field_value(row, &field.id);
+        match dates::matches_filter(&field.field_type, value, condition) {
+            Some(matched) => matched,
+            None => display_value(field, value, reference_options)
+                .to_lowercase()
+                .contains(&lower),
+        }
+    })
+}
+
+// 필터를 통과한 행의 순번을 정렬 순서대로. 빈 값은 정렬 방향과 상관없이 뒤로
+pub(crate) fn visible_order(
+    structure: &CustomStructure,
+    rows: &[Row],
+    view: &GridView,
+    reference_options: &HashMap<String, Vec<(String, String)>>,
+) -> Vec<usize> {
+    let mut order: Vec<usize> = (0..rows.len())
+        .filter(|&i| row_matches(structure, &rows[i], &view.filter, reference_options))
+        .collect();
+    let Some((field_id, descending)) = &view.sort else {
+        return order;
+    };
+    let Some(field) = structure.fields.iter().find(|f| &f.id == field_id) else {
+        return order;
+    };
+    // 빈 값은 None
+    let keys: Vec<Option<SortKey>> = rows
+        .iter()
+        .map(|row| {
+            let value = field_value(row, &field.id);
+            (!value.trim().is_empty()).then(|| sort_key(field, value, reference_options))
+        })
+        .collect();
+    order.sort_by(|&a, &b| match (&keys[a], &keys[b]) {
+        (None, None) => Ordering::Equal,
+        (None, Some(_)) => Ordering::Greater,
+        (Some(_), None) => Ordering::Less,
+        (Some(x), Some(y)) if *descending => y.cmp(x),
+        (Some(x), Some(y)) => x.cmp(y),
+    });
+    order
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::FieldValue;
+
+    #[test]
+    fn mixed_column_sorts_numbers_before_text() {
+        let mut field = Field::new();
+        field.field_type = FieldType::Number;
+        let structure = CustomStructure {
+            fields: vec![field.clone()],
+            ..CustomStructure::new()
+        };
+        let rows: Vec<Row> = ["가1", "10", "", "2", "B"]
+            .iter()
+            .map(|value| {
+                let mut row = Row::new();
+                row.values.insert(
+                    field.id.clone(),
+                    FieldValue {
+                        value: value.to_string(),
+                        field_type: FieldType::Number,
+                    },
+                );
+                row
+            })
+            .collect();
+        let mut view = GridView {
+            sort: Some((field.id.clone(), false)),
+            filter: String::new(),
+        };
+        let options = HashMap::new();
+        assert_eq!(
+            visible_order(&structure, &rows, &view, &options),
+            [3, 1, 4, 0, 2]
+        );
+        view.sort = Some((field.id.clone(), true));
+        assert_eq!(
+            visible_order(&structure, &rows, &view, &options),
+            [0, 4, 1, 3, 2]
+        );
+    }
+}
@@ -0,0 +1,145 @@
+// 충돌/정전에도 파일이 깨지지 않도록 임시 파일에 쓰고 교체
+use std::error::Error;
+use std::fmt;
+use std::fs::{self, File};
+use std::io::{self, Write};
+use std::path::{Path, PathBuf};
+
+// path 뒤에 접미사를 붙인 같은 폴더의 파일 (erp_data.json -> erp_data.json.bak)
+pub(crate) fn sibling(path: &Path, suffix: &str) -> PathBuf {
+    let mut name = path.file_name().unwrap_or_default().to_os_string();
+    name.push(suffix);
+    path.with_file_name(name)
+}
+
+pub(crate) fn backup_path(path: &Path) -> PathBuf {
+    sibling(path, ".bak")
+}
+
+pub(crate) fn corrupt_path(path: &Path) -> PathBuf {
+    sibling(path, ".corrupt")
+}
+
+// 임시 파일에 쓰고 fsync 한 뒤 rename. 기존 파일은 .bak 으로 남겨 마지막 정상본으로 사용
+pub(crate) fn atomic_write(path: &Path, contents: &[u8]) -> io::Result<()> {
+    if path.exists() {
+        let backup = backup_path(path);
+        let _ = fs::remove_file(&backup);
+        // 하드 링크가 안 되는 파일 시스템이면 복사
+        if fs::hard_link(path, &backup).is_err() {
+            fs::copy(path, &backup)?;
+        }
+    }
+    write_and_rename(path, contents)
+}
+
+fn write_and_rename(path: &Path, contents: &[u8]) -> io::Result<()> {
+    let tmp = sibling(path, ".tmp");
+    {
+        let mut file = File::create(&tmp)?;
+        file.write_all(contents)?;
+        file.sync_all()?;
+    }
+    fs::rename(&tmp, path)?;
+    sync_parent_dir(path)
+}
+
+#[cfg(unix)]
+fn sync_parent_dir(path: &Path) -> io::Result<()> {
+    let dir = match path.parent() {
+        Some(dir) if !dir.as_os_str().is_empty() => dir,
+        _ => Path::new("."),
+    };
+    File::open(dir)?.sync_all()
+}
+
+#[cfg(not(unix))]
+fn sync_parent_dir(_path: &Path) -> io::Result<()> {
+    Ok(())
+}
+
+// 읽을 수 없는 파일을 .corrupt 로 복사해 보관
+pub(crate) fn quarantine(path: &Path) -> io::Result<PathBuf> {
+    let corrupt = corrupt_path(path);
+    fs::copy(path, &corrupt)?;
+    Ok(corrupt)
+}
+
+// .bak 내용으로 파일 복구 (손상된 파일이 .bak 을 덮어쓰지 않도록 백업 교체 없이 기록)
+pub(crate) fn restore_backup(path: &Path) -> io::Result<()> {
+    let contents = fs::read(backup_path(path))?;
+    write_and_rename(path, &contents)
+}
+
+// 손상된 파일을 빈 내용으로 바꾸고 시작 (.bak 은 남겨 둠)
+pub(crate) fn reset(path: &Path, empty_contents: &str) -> io::Result<()> {
+    write_and_rename(path, empty_contents.as_bytes())
+}
+
+// 파일을 읽어 parse 로 해석. 실패하면 .corrupt 로 보관하고 .bak 을 쓸 수 있는지 확인.
+// empty_contents 는 사용자가 빈 상태로 시작할 때 기록할 내용
+pub(crate) fn read_checked<T, E: fmt::Display>(
+    path: &Path,
+    empty_contents: &str,
+    parse: impl Fn(&str) -> Result<T, E>,
+) -> Result<Option<T>, Box<dyn Error>> {
+    let bytes = match fs::read(path) {
+        Ok(bytes) => bytes,
+        Err(e) if e.kind() == io::ErrorKind::NotFound => {
+            // 파일은 없지만 백업이 남아 있으면 조용히 비우지 말고 복구를 제안
+            return if backup_is_valid(path, &parse) {
+                Err(Box::new(CorruptFile {
+                    path: path.to_path_buf(),
+                    error: "파일이 없습니다".to_string(),
+                    has_backup: true,
+                    empty_contents: empty_contents.to_string(),
+                }))
+            } else {
+                Ok(None)
+            };
+        }
+        Err(e) => return Err(e.into()),
+    };
+
+    let parsed = match String::from_utf8(bytes) {
+        Ok(data) => parse(&data).map_err(|e| e.to_string()),
+        Err(e) => Err(e.to_string()),
+    };
+    match parsed {
+        Ok(value) => Ok(Some(value)),
+        Err(e) => {
+            if let Err(copy_err) = quarantine(path) {
+                eprintln!("손상된 파일 보관 실패: {}", copy_err);
+            }
+            Err(Box::new(CorruptFile {
+                path: path.to_path_buf(),
+                error: e,
+                has_backup: backup_is_valid(path, &parse),
+                empty_contents: empty_contents.to_string(),
+            }))
+        }
+    }
+}
+
+fn backup_is_valid<T, E>(path: &Path, parse: &impl Fn(&str) -> Result<T, E>) -> bool {
+    fs::read_to_string(backup_path(path))
+        .map(|data| parse(&data).is_ok())
+        .unwrap_or(false)
+}
+
+// 시작 시 읽지 못한 파일. 사용자가 복구 여부를 정할 때까지 저장을 막는다
+#[derive(Debug, Clone)]
+pub(crate) struct CorruptFile {
+    pub(crate) path: PathBuf,
+    pub(crate) error: String,
+    pub(crate) has_backup: bool,
+    pub(crate) empty_contents: String,
+}
+
+impl fmt::Display for CorruptFile {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{} 손상: {}", self.path.display(), self.error)
+    }
+}
+
+impl Error for CorruptFile {}
@@ -0,0 +1,674 @@
+// 구조체 필드 변경 시 기존 데이터 마이그레이션
+use crate::attachments;
+use crate::currency;
+use crate::dates;
+use crate::defaults;
+use crate::formula;
+use crate::korean_id;
+use crate::line_items;
+use crate::select;
+use crate::{CustomStructure, ERPApp, Field, FieldType, FieldValue, Row};
+use eframe::egui;
+use egui::{Context, ScrollArea};
+
+#[derive(Clone, Debug, PartialEq)]
+pub(crate) enum FieldChange {
+    Added {
+        name: String,
+    },
+    Removed {
+        name: String,
+    },
+    Renamed {
+        from: String,
+        to: String,
+    },
+    Retyped {
+        name: String,
+        from: FieldType,
+        to: FieldType,
+    },
+    // 통화 코드, 소수 자릿수, 행마다 거래 통화 설정이 바뀌어 저장된 금액을 다시 맞춰야 할 때
+    CurrencyChanged {
+        name: String,
+    },
+    // 하위 표 합계 설정이 바뀌어 합계를 다시 계산해야 할 때
+    SubtotalChanged {
+        name: String,
+    },
+    Reordered,
+}
+
+impl FieldChange {
+    pub(crate) fn describe(&self) -> String {
+        match self {
+            FieldChange::Added { name } => format!("필드 추가: {}", name),
+            FieldChange::Removed { name } => format!("필드 삭제: {}", name),
+            FieldChange::Renamed { from, to } => format!("이름 변경: {} → {}", from, to),
+            FieldChange::Retyped { name, from, to } => {
+                format!("타입 변경: {} ({:?} → {:?})", name, from, to)
+            }
+            FieldChange::CurrencyChanged { name } => format!("통화 설정 변경: {}", name),
+            FieldChange::SubtotalChanged { name } => format!("합계 설정 변경: {}", name),
+            FieldChange::Reordered => "필드 순서 변경".to_string(),
+        }
+    }
+}
+
+// 구조체 버전 간 차이
+#[derive(Clone, Debug, Default)]
+pub(crate) struct SchemaDiff {
+    pub(crate) from_version: u32,
+    pub(crate) to_version: u32,
+    pub(crate) changes: Vec<FieldChange>,
+}
+
+impl SchemaDiff {
+    pub(crate) fn is_empty(&self) -> bool {
+        self.changes.is_empty()
+    }
+}
+
+pub(crate) fn diff_structures(old: &CustomStructure, new: &CustomStructure) -> SchemaDiff {
+    let mut changes = Vec::new();
+
+    for field in &new.fields {
+        match old.fields.iter().find(|f| f.id == field.id) {
+            None => changes.push(FieldChange::Added {
+                name: field.name.clone(),
+            }),
+            Some(old_field) => {
+                if old_field.name != field.name {
+                    changes.push(FieldChange::Renamed {
+                        from: old_field.name.clone(),
+                        to: field.name.clone(),
+                    });
+                }
+                if old_field.field_type != field.field_type {
+                    changes.push(FieldChange::Retyped {
+                        name: field.name.clone(),
+                        from: old_field.field_type.clone(),
+                        to: field.field_type.clone(),
+                    });
+                } else if field.field_type == FieldType::Currency {
+                    // 환산 기준일 필드는 저장된 값과 상관없다
+                    let (old_currency, new_currency) = (
+                        currency::currency_of(old_field),
+                        currency::currency_of(field),
+                    );
+                    if (&old_currency.code, old_currency.scale, old_currency.per_row)
+                        != (&new_currency.code, new_currency.scale, new_currency.per_row)
+                    {
+                        changes.push(FieldChange::CurrencyChanged {
+                            name: field.name.clone(),
+                        });
+                    }
+                }
+                if old_field.subtotal != field.subtotal {
+                    changes.push(FieldChange::SubtotalChanged {
+                        name: field.name.clone(),
+                    });
+                }
+            }
+        }
+    }
+
+    for old_field in &old.fields {
+        if !new.fields.iter().any(|f| f.id == old_field.id) {
+            changes.push(FieldChange::Removed {
+                name: old_field.name.clone(),
+            });
+        }
+    }
+
+    // 양쪽에 모두 있는 필드의 상대 순서 비교
+    let old_order: Vec<&str> = old
+        .fields
+        .iter()
+        .filter(|f| new.fields.iter().any(|n| n.id == f.id))
+        .map(|f| f.id.as_str())
+        .collect();
+    let new_order: Vec<&str> = new
+        .fields
+        .iter()
+        .filter(|f| old.fields.iter().any(|o| o.id == f.id))
+        .map(|f| f.id.as_str())
+        .collect();
+    if old_order != new_order {
+        changes.push(FieldChange::Reordered);
+    }
+
+    SchemaDiff {
+        from_version: old.version,
+        to_version: old.version + 1,
+        changes,
+    }
+}
+
+// 값을 새 필드 타입으로 변환. 변환할 수 없으면 Err
+pub(crate) fn convert_value(value: &str, to: &FieldType) -> Result<String, String> {
+    let trimmed = value.trim();
+    if trimmed.is_empty() {
+        return Ok(String::new());
+    }
+    match to {
+        FieldType::Text => Ok(value.to_string()),
+        FieldType::Number => trimmed
+            .replace(',', "")
+            .parse::<f64>()
+            .map(|n| n.to_string())
+            .map_err(|_| format!("숫자가 아닙니다: {}", value)),
+        FieldType::Boolean => match trimmed.to_lowercase().as_str() {
+            "true" | "1" | "y" | "yes" | "예" | "참" | "✓" => Ok("true".to_string()),
+            "false" | "0" | "n" | "no" | "아니오" | "거짓" | "✗" => Ok("false".to_string()),
+            _ => Err(format!("참/거짓 값이 아닙니다: {}", value)),
+        },
+        FieldType::Currency => currency::parse_decimal(trimmed)
+            .map(|n| n.normalize().to_string())
+            .ok_or(format!("금액이 아닙니다: {}", value)),
+        FieldType::Date => {
+            dates::normalize_date(trimmed).ok_or(format!("날짜가 아닙니다: {}", value))
+        }
+        FieldType::DateTime => {
+            dates::normalize_datetime(trimmed).ok_or(format!("날짜와 시간이 아닙니다: {}", value))
+        }
+        FieldType::Time => {
+            dates::normalize_time(trimmed).ok_or(format!("시간이 아닙니다: {}", value))
+        }
+        FieldType::Select => Ok(trimmed.to_string()),
+        FieldType::Reference => Ok(trimmed.to_string()),
+        FieldType::Table => line_items::parse_lines(trimmed).map(|_| trimmed.to_string()),
+        FieldType::Attachment => {
+            attachments::parse_attachments(trimmed).map(|_| trimmed.to_string())
+        }
+        FieldType::ResidentNumber | FieldType::BusinessNumber | FieldType::Phone => {
+            korean_id::normalize(to, trimmed)
+        }
+        FieldType::Formula | FieldType::Rollup | FieldType::Lookup => Ok(trimmed.to_string()),
+        FieldType::MultiSelect => Ok(select::split_multi(trimmed)
+            .collect::<Vec<_>>()
+            .join(select::MULTI_SEPARATOR)),
+    }
+}
+
+// 필드 설정까지 반영한 변환. 선택 필드는 항목 목록에 있는 값만 허용
+pub(crate) fn convert_field_value(value: &str, field: &Field) -> Result<String, String> {
+    let converted = convert_value(value, &field.field_type)?;
+    if select::is_select(&field.field_type) {
+        select::normalize(field, &converted)
+    } else if field.field_type == FieldType::Currency {
+        // 거래 통화 코드가 남도록 변환 전 값으로
+        currency::normalize_field(field, value)
+    } else {
+        Ok(converted)
+    }
+}
+
+#[derive(Clone, Debug)]
+pub(crate) struct ConversionFailure {
+    pub(crate) row_index: usize,
+    pub(crate) field_name: String,
+    pub(crate) value: String,
+    pub(crate) error: String,
+}
+
+#[derive(Clone, Debug, Default)]
+pub(crate) struct MigrationPreview {
+    pub(crate) diff: SchemaDiff,
+    pub(crate) total_rows: usize,
+    pub(crate) affected_rows: usize,
+    pub(crate) failures: Vec<ConversionFailure>,
+}
+
+// 변경 적용 전 영향 받는 행 수와 변환 실패 목록 계산
+pub(crate) fn preview_migration(
+    old: &CustomStructure,
+    new: &CustomStructure,
+    rows: &[Row],
+    user_name: &str,
+) -> MigrationPreview {
+    let diff = diff_structures(old, new);
+    let mut preview = MigrationPreview {
+        total_rows: rows.len(),
+        ..Default::default()
+    };
+
+    let formulas = formula::Formulas::compile(new);
+    for (row_index, row) in rows.iter().enumerate() {
+        let mut migrated = row.clone();
+        let failures = migrate_row(new, &formulas, &mut migrated, user_name);
+        if migrated != *row {
+            preview.affected_rows += 1;
+        }
+        preview
+            .failures
+            .extend(
+                failures
+                    .into_iter()
+                    .map(|(field_name, value, error)| ConversionFailure {
+                        row_index,
+                        field_name,
+                        value,
+                        error,
+                    }),
+            );
+    }
+
+    preview.diff = diff;
+    preview
+}
+
+// 삭제된 필드 제거, 타입 변환, 새 필드 기본값 채우기.
+// 새 필드에는 행마다 같은 기본값만 넣고 일련번호는 비워 둔다.
+// 변환 실패한 값은 비워지며 (필드 이름, 원래 값, 오류) 목록으로 반환
+fn migrate_row(
+    structure: &CustomStructure,
+    formulas: &formula::Formulas,
+    row: &mut Row,
+    user_name: &str,
+) -> Vec<(String, String, String)> {
+    let mut failures = Vec::new();
+    row.values
+        .retain(|field_id, _| structure.fields.iter().any(|f| &f.id == field_id));
+
+    for field in &structure.fields {
+        let field_value = row
+            .values
+            .entry(field.id.clone())
+            .or_insert_with(|| FieldValue {
+                value: defaults::static_default(field, user_name).unwrap_or_default(),
+                field_type: field.field_type.clone(),
+            });
+
+        if field_value.field_type != field.field_type {
+            match convert_value(&field_value.value, &field.field_type) {
+                Ok(converted) => field_value.value = converted,
+                Err(e) => {
+                    failures.push((field.name.clone(), field_value.value.clone(), e));
+                    field_value.value = String::new();
+                }
+            }
+            field_value.field_type = field.field_type.clone();
+        }
+        // 통화나 소수 자릿수를 바꾸면 저장된 금액도 맞춘다
+        if field.field_type == FieldType::Currency {
+            match currency::normalize_field(field, &field_value.value) {
+                Ok(value) => field_value.value = value,
+                // 거래 통화 선택을 끄면 다른 통화 금액은 옮길 수 없다
+                Err(e) if currency::foreign_code(field, &field_value.value).is_some() => {
+                    failures.push((field.name.clone(), field_value.value.clone(), e));
+                    field_value.value = String::new();
+                }
+                Err(_) => {}
+            }
+        }
+    }
+    // 새로 설정한 하위 표 합계와 수식도 채운다
+    line_items::apply_subtotals(structure, row);
+    formulas.apply(row);
+    failures
+}
+
+pub(crate) fn apply_migration(structure: &CustomStructure, rows: &mut [Row], user_name: &str) {
+    let formulas = formula::Formulas::compile(structure);
+    for row in rows.iter_mut() {
+        migrate_row(structure, &formulas, row, user_name);
+    }
+}
+
+// 확인 대기 중인 구조체 저장
+pub(crate) struct PendingMigration {
+    pub(crate) structure: CustomStructure,
+    pub(crate) category: String,
+    pub(crate) subcategory: String,
+    pub(crate) preview: MigrationPreview,
+}
+
+impl ERPApp {
+    pub(crate) fn render_migration_preview(&mut self, ctx: &Context) {
+        let Some(pending) = &self.pending_migration else {
+            return;
+        };
+
+        let mut apply = false;
+        let mut cancel = false;
+        let preview = &pending.preview;
+
+        egui::Window::new("구조체 변경 미리보기")
+            .collapsible(false)
+            .default_size([420.0, 360.0])
+            .show(ctx, |ui| {
+                ui.label(format!(
+                    "{} (버전 {} → {})",
+                    pending.structure.name, preview.diff.from_version, preview.diff.to_version
+                ));
+                ui.separator();
+                for change in &preview.diff.changes {
+                    ui.label(change.describe());
+                }
+                ui.separator();
+                ui.label(format!(
+                    "영향 받는 행: {} / {}",
+                    preview.affected_rows, preview.total_rows
+                ));
+
+                if !preview.failures.is_empty() {
+                    ui.colored_label(
+                        egui::Color32::RED,
+                        format!(
+                            "변환 실패 {}건 (적용 시 빈 값으로 바뀝니다)",
+                            preview.failures.len()
+                        ),
+                    );
+                    ScrollArea::vertical()
+                        .id_salt("migration_failures")
+                        .max_height(180.0)
+                        .show(ui, |ui| {
+                            for failure in &preview.failures {
+                                ui.label(format!(
+                                    "{}행 {}: \"{}\" - {}",
+                                    failure.row_index + 1,
+                                    failure.field_name,
+                                    failure.value,
+                                    failure.error
+                                ));
+                            }
+                        });
+                }
+
+                ui.separator();
+                ui.horizontal(|ui| {
+                    if ui.button("✔ 적용").clicked() {
+                        apply = true;
+                    }
+                    if ui.button("❌ 취소").clicked() {
+                        cancel = true;
+                    }
+                });
+            });
+
+        if apply {
+            if let Some(pending) = self.pending_migration.take() {
+                self.commit_structure(
+                    &pending.category,
+                    &pending.subcategory,
+                    pending.structure,
+                    true,
+                );
+            }
+        } else if cancel {
+            self.pending_migration = None;
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::currency::Currency;
+
+    fn field(name: &str, field_type: FieldType) -> Field {
+        Field {
+            name: name.to_string(),
+            field_type,
+            ..Field::new()
+        }
+    }
+
+    fn row(values: &[(&Field, &str)]) -> Row {
+        let mut row = Row::new();
+        for (field, value) in values {
+            row.values.insert(
+                field.id.clone(),
+                FieldValue {
+                    value: value.to_string(),
+                    field_type: field.field_type.clone(),
+                },
+            );
+        }
+        row
+    }
+
+    fn structure(fields: &[&Field]) -> CustomStructure {
+        CustomStructure {
+            fields: fields.iter().map(|f| (*f).clone()).collect(),
+            ..CustomStructure::new()
+        }
+    }
+
+    #[test]
+    fn convert_value_by_type() {
+        assert_eq!(
+            convert_value("1,234.50", &FieldType::Number).unwrap(),
+            "1234.5"
+        );
+        assert!(convert_value("열두 개", &FieldType::Number).is_err());
+        assert_eq!(convert_value("예", &FieldType::Boolean).unwrap(), "true");
+        assert_eq!(convert_value("N", &FieldType::Boolean).unwrap(), "false");
+        assert!(convert_value("아마도", &FieldType::Boolean).is_err());
+        assert_eq!(
+            convert_value("2024.3.4", &FieldType::Date).unwrap(),
+            "2024-03-04"
+        );
+        assert!(convert_value("어제", &FieldType::Date).is_err());
+        // 빈 값은 어떤 타입으로도 그대로
+        assert_eq!(convert_value("  ", &FieldType::Date).unwrap(), "");
+        assert_eq!(
+            convert_value(" 그대로 ", &FieldType::Text).unwrap(),
+            " 그대로 "
+        );
+    }
+
+    #[test]
+    fn diff_detects_each_change() {
+        let name = field("이름", FieldType::Text);
+        let quantity = field("수량", FieldType::Text);
+        let memo = field("메모", FieldType::Text);
+        let old = structure(&[&name, &quantity, &memo]);
+
+        let added = field("비고", FieldType::Text);
+        let mut renamed = name.clone();
+        renamed.name = "성명".to_string();
+        let mut retyped = quantity.clone();
+        retyped.field_type = FieldType::Number;
+        let new = structure(&[&retyped, &renamed, &added]);
+
+        let diff = diff_structures(&old, &new);
+        assert_eq!(
+            diff.changes,
+            vec![
+                FieldChange::Retyped {
+                    name: "수량".to_string(),
+                    from: FieldType::Text,
+                    to: FieldType::Number,
+                },
+                FieldChange::Renamed {
+                    from: "이름".to_string(),
+                    to: "성명".to_string(),
+                },
+                FieldChange::Added {
+                    name: "비고".to_string()
+                },
+                FieldChange::Removed {
+                    name: "메모".to_string()
+                },
+                FieldChange::Reordered,
+            ]
+        );
+        assert_eq!(
+            (diff.from_version, diff.to_version),
+            (old.version, old.version + 1)
+        );
+        assert!(diff_structures(&old, &old).is_empty());
+    }
+
+    #[test]
+    fn retype_converts_values_and_lists_failures() {
+        let quantity = field("수량", FieldType::Text);
+        let paid = field("결제", FieldType::Text);
+        let due = field("기한", FieldType::Text);
+        let memo = field("메모", FieldType::Text);
+        let old = structure(&[&quantity, &paid, &due, &memo]);
+        let mut new = structure(&[&quantity, &paid, &due]);
+        new.fields[0].field_type = FieldType::Number;
+        new.fields[1].field_type = FieldType::Boolean;
+        new.fields[2].field_type = FieldType::Date;
+
+        let mut rows = vec![
+            row(&[
+                (&quantity, "1,000"),
+                (&paid, "예"),
+                (&due, "2024.3.4"),
+                (&memo, "a"),
+            ]),
+            row(&[(&quantity, "많음"), (&paid, "0"), (&due, ""), (&memo, "b")]),
+        ];
+        let preview = preview_migration(&old, &new, &rows, "");
+        assert_eq!(preview.total_rows, 2);
+        assert_eq!(preview.affected_rows, 2);
+        assert_eq!(preview.failures.len(), 1);
+        let failure = &preview.failures[0];
+        assert_eq!(
+            (
+                failure.row_index,
+                failure.field_name.as_str(),
+                failure.value.as_str()
+            ),
+            (1, "수량", "많음")
+        );
+        // 미리보기는 행을 바꾸지 않는다
+        assert_eq!(rows[0].values[&quantity.id].value, "1,000");
+
+        apply_migration(&new, &mut rows, "");
+        let values = |row: &Row| {
+            [&quantity, &paid, &due]
+                .iter()
+                .map(|f| row.values[&f.id].value.clone())
+                .collect::<Vec<_>>()
+        };
+        assert_eq!(values(&rows[0]), ["1000", "true", "2024-03-04"]);
+        // 변환에 실패한 값은 비운다
+        assert_eq!(values(&rows[1]), ["", "false", ""]);
+        assert!(rows.iter().all(|r| !r.values.contains_key(&memo.id)));
+        assert_eq!(rows[0].values[&quantity.id].field_type, FieldType::Number);
+    }
+
+    #[test]
+    fn added_fields_get_static_defaults() {
+        let name = field("이름", FieldType::Text);
+        let old = structure(&[&name]);
+        let mut status = field("상태", FieldType::Text);
+        status.default = defaults::DefaultValue::Literal("접수".to_string());
+        let mut owner = field("담당자", FieldType::Text);
+        owner.default = defaults::DefaultValue::CurrentUser;
+        let mut created = field("등록일", FieldType::Date);
+        created.default = defaults::DefaultValue::Today;
+        let mut number = field("번호", FieldType::Text);
+        number.default = defaults::DefaultValue::Sequence("{0000}".to_string());
+        let new = structure(&[&name, &status, &owner, &created, &number]);
+
+        let mut rows = vec![row(&[(&name, "가")])];
+        assert_eq!(
+            preview_migration(&old, &new, &rows, "홍길동").affected_rows,
+            1
+        );
+        apply_migration(&new, &mut rows, "홍길동");
+        let values = &rows[0].values;
+        assert_eq!(values[&status.id].value, "접수");
+        assert_eq!(values[&owner.id].value, "홍길동");
+        assert_eq!(
+            values[&created.id].value,
+            dates::now_value(&FieldType::Date)
+        );
+        // 일련번호는 행마다 달라야 하므로 채우지 않는다
+        assert_eq!(values[&number.id].value, "");
+
+        // 이미 있던 필드의 빈 값은 그대로 둔다
+        let mut rows = vec![row(&[(&name, "나"), (&status, "")])];
+        apply_migration(&new, &mut rows, "홍길동");
+        assert_eq!(rows[0].values[&status.id].value, "");
+    }
+
+    #[test]
+    fn currency_scale_change_rescales_amounts() {
+        let mut amount = field("금액", FieldType::Currency);
+        amount.currency = Some(Currency {
+            code: "USD".to_string(),
+            scale: 2,
+            ..Currency::default()
+        });
+        let old = CustomStructure {
+            fields: vec![amount.clone()],
+            ..CustomStructure::new()
+        };
+        let mut new = old.clone();
+        new.fields[0].currency = Some(Currency::default());
+
+        let mut rows = vec![
+            row(&[(&amount, "1234.56")]),
+            row(&[(&amount, "10.00")]),
+            row(&[(&amount, "")]),
+        ];
+        let preview = preview_migration(&old, &new, &rows, "");
+        assert_eq!(
+            preview.diff.changes,
+            vec![FieldChange::CurrencyChanged {
+                name: "금액".to_string()
+            }]
+        );
+        assert_eq!(preview.affected_rows, 2);
+        assert!(preview.failures.is_empty());
+
+        apply_migration(&new, &mut rows, "");
+        let values: Vec<&str> = rows
+            .iter()
+            .map(|r| r.values[&amount.id].value.as_str())
+            .collect();
+        assert_eq!(values, ["1235", "10", ""]);
+    }
+
+    #[test]
+    fn subtotal_change_recomputes_totals() {
+        let quantity = field("수량", FieldType::Number);
+        let mut lines = field("품목", FieldType::Table);
+        lines.columns = vec![quantity.clone()];
+        let total = field("합계", FieldType::Number);
+        let old = CustomStructure {
+            fields: vec![lines.clone(), total.clone()],
+            ..CustomStructure::new()
+        };
+        let mut new = old.clone();
+        new.fields[1].subtotal = Some(line_items::Subtotal {
+            table_field_id: lines.id.clone(),
+            column_id: quantity.id.clone(),
+        });
+
+        let items =
+            serde_json::to_string(&vec![row(&[(&quantity, "2")]), row(&[(&quantity, "3")])])
+                .unwrap();
+        let mut rows = vec![row(&[(&lines, &items), (&total, "")])];
+        let preview = preview_migration(&old, &new, &rows, "");
+        assert_eq!(
+            preview.diff.changes,
+            vec![FieldChange::SubtotalChanged {
+                name: "합계".to_string()
+            }]
+        );
+        assert_eq!(preview.affected_rows, 1);
+        apply_migration(&new, &mut rows, "");
+        assert_eq!(rows[0].values[&total.id].value, "5");
+    }
+
+    #[test]
+    fn currency_date_field_is_not_a_change() {
+        let mut amount = field("금액", FieldType::Currency);
+        amount.currency = Some(Currency::default());
+        let old = CustomStructure {
+            fields: vec![amount],
+            ..CustomStructure::new()
+        };
+        let mut new = old.clone();
+        new.fields[0].currency.as_mut().unwrap().date_field_id = "d".to_string();
+        assert!(diff_structures(&old, &new).is_empty());
+    }
+}
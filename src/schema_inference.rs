@@ -0,0 +1,305 @@
+// 파일에서 새 구조체 만들기: 머리글 행으로 필드를 만들고 데이터를 훑어 타입 추정
+use crate::csv_import::{empty_row, read_csv_table, render_import_errors, ImportError};
+use crate::dates;
+use crate::excel_import::{cell_to_string, read_sheet};
+use crate::korean_id;
+use crate::reference::resolve_with;
+use crate::schema::{convert_field_value, convert_value};
+use crate::validation::{accept_rows, rejected_to_errors, render_violations};
+use crate::{CustomStructure, ERPApp, Field, FieldType, Row};
+use calamine::{open_workbook, Reader, Xlsx};
+use eframe::egui;
+use egui::Context;
+use rfd::FileDialog;
+use std::error::Error;
+use std::path::Path;
+
+// 타입 추정에 쓰는 최대 행 수
+const SAMPLE_ROWS: usize = 200;
+
+// 1/0은 숫자로 보고, 글자로 된 참/거짓만 Boolean
+fn is_boolean(value: &str) -> bool {
+    matches!(
+        value.to_lowercase().as_str(),
+        "true" | "false" | "y" | "n" | "yes" | "no" | "예" | "아니오" | "참" | "거짓" | "✓" | "✗"
+    )
+}
+
+// 0으로 시작하는 코드(우편번호, 사번 등)는 숫자로 바꾸면 0이 사라지므로 제외
+fn is_number(value: &str) -> bool {
+    let digits = value.trim_start_matches(['-', '+']);
+    let leading_zero = digits.len() > 1 && digits.starts_with('0') && !digits.starts_with("0.");
+    !leading_zero && convert_value(value, &FieldType::Number).is_ok()
+}
+
+// 비어 있지 않은 값이 모두 같은 타입으로 읽히면 그 타입, 아니면 Text.
+// 식별 번호는 1999-12-31 같은 날짜와 겹치지 않도록 날짜 다음에 본다
+pub(crate) fn infer_field_type<'a>(samples: impl Iterator<Item = &'a str>) -> FieldType {
+    let values: Vec<&str> = samples
+        .map(str::trim)
+        .filter(|v| !v.is_empty())
+        .take(SAMPLE_ROWS)
+        .collect();
+    if values.is_empty() {
+        FieldType::Text
+    } else if values.iter().all(|v| is_boolean(v)) {
+        FieldType::Boolean
+    } else if values.iter().all(|v| is_number(v)) {
+        FieldType::Number
+    } else if values.iter().all(|v| dates::parse_date(v).is_some()) {
+        FieldType::Date
+    } else if values.iter().all(|v| dates::parse_time(v).is_some()) {
+        FieldType::Time
+    } else if values.iter().all(|v| dates::parse_datetime(v).is_some()) {
+        FieldType::DateTime
+    } else {
+        korean_id::infer(&values).unwrap_or(FieldType::Text)
+    }
+}
+
+// 빈 머리글은 "열 N", 겹치는 이름은 번호를 붙인다
+fn field_names(headers: &[String]) -> Vec<String> {
+    let mut names: Vec<String> = Vec::new();
+    for (idx, header) in headers.iter().enumerate() {
+        let base = match header.trim() {
+            "" => format!("열 {}", idx + 1),
+            name => name.to_string(),
+        };
+        let mut name = base.clone();
+        let mut n = 2;
+        while names.contains(&name) {
+            name = format!("{} ({})", base, n);
+            n += 1;
+        }
+        names.push(name);
+    }
+    names
+}
+
+// 새 구조체의 원본 파일. 구조체를 저장하면 행을 한 번에 가져온다
+pub(crate) struct StructureSource {
+    pub(crate) structure_id: String,
+    pub(crate) file_name: String,
+    pub(crate) field_ids: Vec<String>, // 열 -> 처음 만든 필드 ID
+    pub(crate) records: Vec<Vec<String>>,
+    pub(crate) import_rows: bool,
+    pub(crate) preview: Option<SourcePreview>,
+}
+
+// 저장한 구조체의 타입으로 변환한 행. 변환 실패나 제약 조건 위반이 있으면 가져오기 전에 보여 준다
+pub(crate) struct SourcePreview {
+    pub(crate) rows: Vec<Row>,
+    pub(crate) errors: Vec<ImportError>,
+    pub(crate) violations: Vec<ImportError>, // 제약 조건 때문에 가져오지 않을 행
+}
+
+// 머리글과 문자열로 바꾼 데이터 행
+type Table = (Vec<String>, Vec<Vec<String>>);
+
+// CSV는 인코딩/구분자를 감지해서, Excel은 첫 시트를 읽는다
+fn read_table(path: &Path) -> Result<Table, Box<dyn Error>> {
+    let is_excel = path
+        .extension()
+        .is_some_and(|ext| ext.eq_ignore_ascii_case("xlsx"));
+    if !is_excel {
+        let table = read_csv_table(path)?;
+        return Ok((table.headers, table.records));
+    }
+
+    let workbook: Xlsx<_> = open_workbook(path)?;
+    let Some(sheet) = workbook.sheet_names().first().cloned() else {
+        return Err("시트가 비어있습니다".into());
+    };
+    let (headers, cells) = read_sheet(path, &sheet)?;
+    let records = cells
+        .iter()
+        .map(|row| {
+            row.iter()
+                .map(|cell| cell_to_string(cell, None).unwrap_or_default())
+                .collect()
+        })
+        .collect();
+    Ok((headers, records))
+}
+
+impl ERPApp {
+    // 파일을 골라 필드와 타입을 채운 구조체를 편집기에 연다
+    pub(crate) fn start_structure_from_file(&mut self, category: String, subcategory: String) {
+        let Some(path) = FileDialog::new()
+            .add_filter("CSV / Excel", &["csv", "tsv", "txt", "xlsx"])
+            .set_title("파일에서 새 구조체")
+            .pick_file()
+        else {
+            return;
+        };
+        let (headers, records) = match read_table(&path) {
+            Ok(table) => table,
+            Err(e) => {
+                eprintln!("파일을 읽을 수 없습니다: {}", e);
+                return;
+            }
+        };
+
+        let mut structure = CustomStructure::new();
+        structure.name = path
+            .file_stem()
+            .map(|s| s.to_string_lossy().to_string())
+            .unwrap_or_default();
+        for (col, name) in field_names(&headers).into_iter().enumerate() {
+            let samples = records
+                .iter()
+                .map(|r| r.get(col).map(String::as_str).unwrap_or_default());
+            structure.fields.push(Field {
+                name,
+                field_type: infer_field_type(samples),
+                ..Field::new()
+            });
+        }
+
+        self.structure_source = Some(StructureSource {
+            structure_id: structure.id.clone(),
+            file_name: path
+                .file_name()
+                .map(|n| n.to_string_lossy().to_string())
+                .unwrap_or_default(),
+            field_ids: structure.fields.iter().map(|f| f.id.clone()).collect(),
+            records,
+            import_rows: true,
+            preview: None,
+        });
+        self.current_structure = structure;
+        self.selected_category = Some(category);
+        self.current_subcategory = Some(subcategory);
+        self.show_structure_editor = true;
+    }
+
+    // 구조체가 저장된 뒤 원본 파일의 행을 편집기에서 정한 타입으로 변환.
+    // 문제가 없으면 바로 가져오고, 있으면 확인 창을 띄운다
+    pub(crate) fn import_structure_source(&mut self) {
+        let Some(mut source) = self.structure_source.take() else {
+            return;
+        };
+        let Some(structure) = self.find_structure(&source.structure_id) else {
+            // 아직 저장되지 않음 (마이그레이션 확인 대기 등)
+            self.structure_source = Some(source);
+            return;
+        };
+        if !source.import_rows {
+            return;
+        }
+
+        let mut errors = Vec::new();
+        let mut rows: Vec<_> = source
+            .records
+            .iter()
+            .enumerate()
+            .map(|(row_idx, record)| {
+                let mut row = empty_row(&structure);
+                for (col, field_id) in source.field_ids.iter().enumerate() {
+                    // 편집기에서 지운 필드의 열은 건너뜀
+                    let Some(field) = structure.fields.iter().find(|f| &f.id == field_id) else {
+                        continue;
+                    };
+                    let value = record.get(col).map(String::as_str).unwrap_or_default();
+                    let value = convert_field_value(value, field).unwrap_or_else(|error| {
+                        errors.push(ImportError {
+                            row: row_idx + 1,
+                            column: field.name.clone(),
+                            value: value.to_string(),
+                            error,
+                        });
+                        String::new()
+                    });
+                    if let Some(field_value) = row.values.get_mut(field_id) {
+                        field_value.value = value;
+                    }
+                }
+                row
+            })
+            .collect();
+        errors.extend(resolve_with(
+            &structure,
+            &self.reference_lookup(&structure, &[]),
+            &mut rows,
+        ));
+
+        let existing = self
+            .erp_data
+            .data
+            .get(&structure.id)
+            .cloned()
+            .unwrap_or_default();
+        let (_, rejected) = accept_rows(&structure, &existing, rows.clone());
+        let preview = SourcePreview {
+            rows,
+            errors,
+            violations: rejected_to_errors(&rejected),
+        };
+        if preview.errors.is_empty() && preview.violations.is_empty() {
+            self.insert_source_rows(&structure, &source.file_name, preview.rows);
+        } else {
+            source.preview = Some(preview);
+            self.structure_source = Some(source);
+        }
+    }
+
+    fn insert_source_rows(&mut self, structure: &CustomStructure, file_name: &str, rows: Vec<Row>) {
+        let count = rows.len();
+        let rejected = self.insert_rows(structure, rows);
+        println!(
+            "{}에서 {}행을 가져왔습니다 (제약 조건 위반으로 제외 {}행)",
+            file_name,
+            count - rejected.len(),
+            rejected.len()
+        );
+    }
+
+    pub(crate) fn render_structure_source_import(&mut self, ctx: &Context) {
+        let Some(source) = &self.structure_source else {
+            return;
+        };
+        let Some(preview) = &source.preview else {
+            return;
+        };
+
+        let mut apply = false;
+        let mut cancel = false;
+        egui::Window::new("파일에서 행 가져오기")
+            .collapsible(false)
+            .default_size([520.0, 400.0])
+            .show(ctx, |ui| {
+                ui.label(format!(
+                    "{}의 {}행을 가져옵니다",
+                    source.file_name,
+                    preview.rows.len()
+                ));
+                ui.separator();
+                render_import_errors(ui, "source_import_errors", &preview.errors);
+                render_violations(ui, "source_import_violations", &preview.violations);
+
+                ui.separator();
+                ui.horizontal(|ui| {
+                    if ui.button("✔ 가져오기").clicked() {
+                        apply = true;
+                    }
+                    if ui.button("❌ 가져오지 않기").clicked() {
+                        cancel = true;
+                    }
+                });
+            });
+
+        if apply {
+            let Some(source) = self.structure_source.take() else {
+                return;
+            };
+            let (Some(structure), Some(preview)) =
+                (self.find_structure(&source.structure_id), source.preview)
+            else {
+                return;
+            };
+            self.insert_source_rows(&structure, &source.file_name, preview.rows);
+        } else if cancel {
+            self.structure_source = None;
+        }
+    }
+}
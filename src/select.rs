@@ -0,0 +1,199 @@
+// 선택/다중 선택 필드: 구조체 편집기에서 관리하는 항목 목록 (색상, 순서)
+use crate::{Field, FieldType};
+use eframe::egui;
+use egui::{Color32, Response};
+use serde::{Deserialize, Serialize};
+
+// 다중 선택 값은 항목을 ", "로 이어 저장
+pub(crate) const MULTI_SEPARATOR: &str = ", ";
+
+// 새 항목에 차례로 쓰는 색
+const PALETTE: [[u8; 3]; 8] = [
+    [66, 133, 244],
+    [52, 168, 83],
+    [251, 188, 5],
+    [234, 67, 53],
+    [156, 39, 176],
+    [0, 172, 193],
+    [255, 112, 67],
+    [120, 144, 156],
+];
+
+#[derive(Clone, Default, Serialize, Deserialize, PartialEq, Debug)]
+pub(crate) struct SelectOption {
+    pub(crate) value: String,
+    #[serde(default)]
+    pub(crate) color: [u8; 3],
+}
+
+pub(crate) fn is_select(field_type: &FieldType) -> bool {
+    matches!(field_type, FieldType::Select | FieldType::MultiSelect)
+}
+
+pub(crate) fn split_multi(value: &str) -> impl Iterator<Item = &str> {
+    value.split(',').map(str::trim).filter(|v| !v.is_empty())
+}
+
+fn find_option<'a>(field: &'a Field, value: &str) -> Option<&'a SelectOption> {
+    field.options.iter().find(|o| o.value == value).or_else(|| {
+        let lower = value.to_lowercase();
+        field
+            .options
+            .iter()
+            .find(|o| o.value.to_lowercase() == lower)
+    })
+}
+
+// 목록의 항목 이름으로 맞춘 값 (대소문자 차이는 허용). 목록에 없으면 Err
+pub(crate) fn normalize(field: &Field, value: &str) -> Result<String, String> {
+    let value = value.trim();
+    if value.is_empty() {
+        return Ok(String::new());
+    }
+    let lookup = |v: &str| {
+        find_option(field, v)
+            .map(|o| o.value.clone())
+            .ok_or_else(|| format!("목록에 없는 값입니다: {}", v))
+    };
+    match field.field_type {
+        FieldType::Select => lookup(value),
+        FieldType::MultiSelect => {
+            let mut values: Vec<String> = Vec::new();
+            for v in split_multi(value) {
+                let v = lookup(v)?;
+                if !values.contains(&v) {
+                    values.push(v);
+                }
+            }
+            Ok(values.join(MULTI_SEPARATOR))
+        }
+        _ => Ok(value.to_string()),
+    }
+}
+
+fn option_color(option: &SelectOption) -> Color32 {
+    let [r, g, b] = option.color;
+    Color32::from_rgb(r, g, b)
+}
+
+fn colored_text(field: &Field, value: &str) -> egui::RichText {
+    match find_option(field, value) {
+        Some(option) => egui::RichText::new(value).color(option_color(option)),
+        None => egui::RichText::new(value),
+    }
+}
+
+// 표의 선택 셀
+pub(crate) fn select_cell(
+    ui: &mut egui::Ui,
+    id: impl std::hash::Hash,
+    field: &Field,
+    value: &mut String,
+) -> Response {
+    let before = value.clone();
+    let mut response = egui::ComboBox::from_id_salt(id)
+        .selected_text(colored_text(field, value))
+        .show_ui(ui, |ui| {
+            ui.selectable_value(value, String::new(), "(없음)");
+            for option in &field.options {
+                ui.selectable_value(
+                    value,
+                    option.value.clone(),
+                    egui::RichText::new(&option.value).color(option_color(option)),
+                );
+            }
+        })
+        .response;
+    if *value != before {
+        response.mark_changed();
+    }
+    response
+}
+
+// 표의 다중 선택 셀: 체크한 항목을 목록 순서대로 저장
+pub(crate) fn multi_select_cell(
+    ui: &mut egui::Ui,
+    id: impl std::hash::Hash,
+    field: &Field,
+    value: &mut String,
+) -> Response {
+    let before = value.clone();
+    let selected: Vec<String> = split_multi(value).map(str::to_string).collect();
+    let mut response = egui::ComboBox::from_id_salt(id)
+        .selected_text(value.clone())
+        .show_ui(ui, |ui| {
+            let mut checked: Vec<String> = Vec::new();
+            for option in &field.options {
+                let mut on = selected.contains(&option.value);
+                ui.checkbox(
+                    &mut on,
+                    egui::RichText::new(&option.value).color(option_color(option)),
+                );
+                if on {
+                    checked.push(option.value.clone());
+                }
+            }
+            // 목록에 없는 기존 값은 그대로 둔다
+            checked.extend(
+                selected
+                    .iter()
+                    .filter(|v| !field.options.iter().any(|o| &o.value == *v))
+                    .cloned(),
+            );
+            *value = checked.join(MULTI_SEPARATOR);
+        })
+        .response;
+    if *value != before {
+        response.mark_changed();
+    }
+    response
+}
+
+// 구조체 편집기의 항목 목록 편집
+pub(crate) fn options_editor(ui: &mut egui::Ui, idx: usize, field: &mut Field) {
+    egui::CollapsingHeader::new(format!("항목 목록 ({})", field.options.len()))
+        .id_salt(("field_options", idx))
+        .default_open(field.options.is_empty())
+        .show(ui, |ui| {
+            let mut move_up = None;
+            let mut remove = None;
+            let count = field.options.len();
+            for (i, option) in field.options.iter_mut().enumerate() {
+                ui.horizontal(|ui| {
+                    ui.color_edit_button_srgb(&mut option.color);
+                    let response =
+                        ui.add(egui::TextEdit::singleline(&mut option.value).desired_width(150.0));
+                    // 쉼표는 다중 선택 구분자라 항목 이름에 쓰지 않는다
+                    if response.changed() && option.value.contains(',') {
+                        option.value = option.value.replace(',', "");
+                    }
+                    if ui.button("⬆").clicked() && i > 0 {
+                        move_up = Some(i);
+                    }
+                    if ui.button("⬇").clicked() && i + 1 < count {
+                        move_up = Some(i + 1);
+                    }
+                    if ui.button("🗑️").clicked() {
+                        remove = Some(i);
+                    }
+                });
+            }
+            if let Some(i) = move_up {
+                field.options.swap(i - 1, i);
+            }
+            if let Some(i) = remove {
+                field.options.remove(i);
+            }
+            if ui.button("➕ 항목 추가").clicked() {
+                field.options.push(SelectOption {
+                    value: format!("항목 {}", count + 1),
+                    color: PALETTE[count % PALETTE.len()],
+                });
+            }
+            let mut names: Vec<&str> = field.options.iter().map(|o| o.value.as_str()).collect();
+            names.sort_unstable();
+            if names.windows(2).any(|w| w[0] == w[1]) {
+                ui.colored_label(Color32::RED, "같은 이름의 항목이 있습니다");
+            }
+        });
+}
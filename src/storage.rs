@@ -0,0 +1,473 @@
+// 구조체 정의와 행 데이터 저장소
+use crate::csv_format::CsvFormat;
+use crate::excel_export::ExcelExportOptions;
+use crate::safe_file::{atomic_write, read_checked, sibling};
+use crate::workspace::Workspace;
+use crate::{legacy, CustomCategory, CustomStructure, ERPData, FieldValue, Row, ERP_DATA_VERSION};
+use rusqlite::{params, Connection, OptionalExtension};
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+use std::error::Error;
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::time::SystemTime;
+
+pub(crate) trait Storage {
+    fn kind(&self) -> StorageKind;
+
+    // 데이터 파일의 마지막 수정 시각 (CSV 백업과 비교용)
+    fn modified(&self) -> Option<SystemTime>;
+
+    fn load_structures(&mut self) -> Result<Vec<CustomCategory>, Box<dyn Error>>;
+    fn save_structures(&mut self, structures: &[CustomCategory]) -> Result<(), Box<dyn Error>>;
+
+    // 전체 데이터 읽기/쓰기
+    fn load_data(&mut self, structures: &[CustomCategory]) -> Result<ERPData, Box<dyn Error>>;
+    fn save_data(
+        &mut self,
+        structures: &[CustomCategory],
+        data: &ERPData,
+    ) -> Result<(), Box<dyn Error>>;
+
+    // 구조체 하나의 행 전체 교체 (가져오기, 마이그레이션 등)
+    fn replace_rows(
+        &mut self,
+        structure: &CustomStructure,
+        rows: &[Row],
+    ) -> Result<(), Box<dyn Error>>;
+
+    // 행 단위 저장 (셀 편집, 행 추가/삭제)
+    fn upsert_row(&mut self, structure: &CustomStructure, row: &Row) -> Result<(), Box<dyn Error>>;
+    fn delete_row(
+        &mut self,
+        structure: &CustomStructure,
+        row_id: &str,
+    ) -> Result<(), Box<dyn Error>>;
+}
+
+#[derive(Clone, Copy, Default, Serialize, Deserialize, PartialEq, Debug)]
+pub(crate) enum StorageKind {
+    #[default]
+    Json,
+    Sqlite,
+}
+
+impl StorageKind {
+    pub(crate) fn label(&self) -> &'static str {
+        match self {
+            StorageKind::Json => "JSON + CSV",
+            StorageKind::Sqlite => "SQLite",
+        }
+    }
+
+    pub(crate) fn open(&self, workspace: &Workspace) -> Result<Box<dyn Storage>, Box<dyn Error>> {
+        Ok(match self {
+            StorageKind::Json => Box::new(JsonStorage::new(workspace)),
+            StorageKind::Sqlite => Box::new(SqliteStorage::open(&workspace.sqlite_path())?),
+        })
+    }
+}
+
+impl Default for Box<dyn Storage> {
+    fn default() -> Self {
+        Box::new(JsonStorage::new(&Workspace::default()))
+    }
+}
+
+// 저장소 선택 등 워크스페이스 설정 (erp_settings.json)
+#[derive(Clone, Default, Serialize, Deserialize)]
+pub(crate) struct AppSettings {
+    #[serde(default)]
+    pub(crate) storage: StorageKind,
+    #[serde(default)]
+    pub(crate) csv_backup: CsvFormat, // 구조체별 CSV 백업 형식
+    #[serde(default)]
+    pub(crate) csv_export: CsvFormat, // 마지막으로 고른 CSV 내보내기 형식
+    #[serde(default)]
+    pub(crate) excel_export: ExcelExportOptions,
+    #[serde(default)]
+    pub(crate) user_name: String, // "현재 사용자" 기본값, 비어 있으면 OS 사용자 이름
+}
+
+impl AppSettings {
+    pub(crate) fn load(workspace: &Workspace) -> Self {
+        fs::read_to_string(workspace.settings_path())
+            .ok()
+            .and_then(|data| serde_json::from_str(&data).ok())
+            .unwrap_or_default()
+    }
+
+    pub(crate) fn save(&self, workspace: &Workspace) -> Result<(), Box<dyn Error>> {
+        atomic_write(
+            &workspace.settings_path(),
+            serde_json::to_string_pretty(self)?.as_bytes(),
+        )?;
+        Ok(())
+    }
+}
+
+// 기존 방식: custom_structures.json + erp_data.json (CSV 백업은 앱에서 별도로 작성)
+pub(crate) struct JsonStorage {
+    structures_path: PathBuf,
+    data_path: PathBuf,
+    data: ERPData, // erp_data.json 내용, 행 단위 저장 시 전체 파일을 다시 쓰기 위해 보관
+}
+
+impl JsonStorage {
+    pub(crate) fn new(workspace: &Workspace) -> Self {
+        JsonStorage {
+            structures_path: workspace.structures_path(),
+            data_path: workspace.data_path(),
+            data: ERPData::default(),
+        }
+    }
+
+    fn write_data(&self) -> Result<(), Box<dyn Error>> {
+        let mut data = self.data.clone();
+        data.version = ERP_DATA_VERSION;
+        atomic_write(
+            &self.data_path,
+            serde_json::to_string_pretty(&data)?.as_bytes(),
+        )?;
+        Ok(())
+    }
+}
+
+impl Storage for JsonStorage {
+    fn kind(&self) -> StorageKind {
+        StorageKind::Json
+    }
+
+    fn modified(&self) -> Option<SystemTime> {
+        fs::metadata(&self.data_path)
+            .and_then(|m| m.modified())
+            .ok()
+    }
+
+    fn load_structures(&mut self) -> Result<Vec<CustomCategory>, Box<dyn Error>> {
+        let structures = read_checked(&self.structures_path, "[]", |data| {
+            serde_json::from_str::<Vec<CustomCategory>>(data)
+        })?;
+        Ok(structures.unwrap_or_default())
+    }
+
+    fn save_structures(&mut self, structures: &[CustomCategory]) -> Result<(), Box<dyn Error>> {
+        atomic_write(
+            &self.structures_path,
+            serde_json::to_string_pretty(structures)?.as_bytes(),
+        )?;
+        Ok(())
+    }
+
+    fn load_data(&mut self, structures: &[CustomCategory]) -> Result<ERPData, Box<dyn Error>> {
+        let empty = serde_json::to_string_pretty(&ERPData {
+            version: ERP_DATA_VERSION,
+            ..Default::default()
+        })?;
+        match read_checked(&self.data_path, &empty, |data| {
+            legacy::parse_erp_data(data, structures)
+        })? {
+            Some((loaded, upgraded)) => {
+                self.data = loaded;
+                if upgraded {
+                    // 이름 기반 레거시 파일을 ID 기반으로 변환하여 다시 저장
+                    println!("erp_data.json을 ID 기반 형식으로 변환했습니다");
+                    self.write_data()?;
+                }
+            }
+            None => {
+                // 파일이 없는 경우 빈 데이터 파일 생성
+                self.data = ERPData::default();
+                self.write_data()?;
+            }
+        }
+        Ok(self.data.clone())
+    }
+
+    fn save_data(
+        &mut self,
+        _structures: &[CustomCategory],
+        data: &ERPData,
+    ) -> Result<(), Box<dyn Error>> {
+        self.data = data.clone();
+        self.write_data()
+    }
+
+    fn replace_rows(
+        &mut self,
+        structure: &CustomStructure,
+        rows: &[Row],
+    ) -> Result<(), Box<dyn Error>> {
+        self.data.data.insert(structure.id.clone(), rows.to_vec());
+        self.write_data()
+    }
+
+    fn upsert_row(&mut self, structure: &CustomStructure, row: &Row) -> Result<(), Box<dyn Error>> {
+        let rows = self.data.data.entry(structure.id.clone()).or_default();
+        match rows.iter_mut().find(|r| r.id == row.id) {
+            Some(existing) => *existing = row.clone(),
+            None => rows.push(row.clone()),
+        }
+        self.write_data()
+    }
+
+    fn delete_row(
+        &mut self,
+        structure: &CustomStructure,
+        row_id: &str,
+    ) -> Result<(), Box<dyn Error>> {
+        if let Some(rows) = self.data.data.get_mut(&structure.id) {
+            rows.retain(|r| r.id != row_id);
+        }
+        self.write_data()
+    }
+}
+
+// SQLite: 구조체마다 테이블 하나 (row_id + 필드 ID별 컬럼), 구조체 정의는 meta 테이블
+pub(crate) struct SqliteStorage {
+    path: PathBuf,
+    conn: Connection,
+}
+
+impl SqliteStorage {
+    pub(crate) fn open(path: &Path) -> Result<Self, Box<dyn Error>> {
+        let conn = Connection::open(path)?;
+        conn.execute_batch(
+            "PRAGMA journal_mode = WAL;
+             CREATE TABLE IF NOT EXISTS meta (key TEXT PRIMARY KEY, value TEXT NOT NULL);",
+        )?;
+        Ok(SqliteStorage {
+            path: path.to_path_buf(),
+            conn,
+        })
+    }
+
+    fn table_name(structure_id: &str) -> String {
+        quote_ident(&format!("rows_{}", structure_id))
+    }
+
+    // 테이블이 없으면 만들고, 새로 추가된 필드는 컬럼 추가
+    fn ensure_table(&self, structure: &CustomStructure) -> Result<(), Box<dyn Error>> {
+        let table = Self::table_name(&structure.id);
+        self.conn.execute(
+            &format!(
+                "CREATE TABLE IF NOT EXISTS {} (row_id TEXT PRIMARY KEY)",
+                table
+            ),
+            [],
+        )?;
+
+        let mut stmt = self
+            .conn
+            .prepare(&format!("PRAGMA table_info({})", table))?;
+        let columns: Vec<String> = stmt
+            .query_map([], |r| r.get::<_, String>(1))?
+            .collect::<Result<_, _>>()?;
+        for field in &structure.fields {
+            if !columns.contains(&field.id) {
+                self.conn.execute(
+                    &format!(
+                        "ALTER TABLE {} ADD COLUMN {} TEXT",
+                        table,
+                        quote_ident(&field.id)
+                    ),
+                    [],
+                )?;
+            }
+        }
+        Ok(())
+    }
+
+    fn write_row(&self, structure: &CustomStructure, row: &Row) -> Result<(), Box<dyn Error>> {
+        let mut columns = vec!["row_id".to_string()];
+        let mut values = vec![row.id.clone()];
+        for field in &structure.fields {
+            columns.push(quote_ident(&field.id));
+            values.push(
+                row.values
+                    .get(&field.id)
+                    .map(|v| v.value.clone())
+                    .unwrap_or_default(),
+            );
+        }
+        let placeholders: Vec<String> = (1..=values.len()).map(|i| format!("?{}", i)).collect();
+        let updates: Vec<String> = columns[1..]
+            .iter()
+            .map(|c| format!("{} = excluded.{}", c, c))
+            .collect();
+        let sql = if updates.is_empty() {
+            format!(
+                "INSERT INTO {} (row_id) VALUES (?1) ON CONFLICT(row_id) DO NOTHING",
+                Self::table_name(&structure.id)
+            )
+        } else {
+            format!(
+                "INSERT INTO {} ({}) VALUES ({}) ON CONFLICT(row_id) DO UPDATE SET {}",
+                Self::table_name(&structure.id),
+                columns.join(", "),
+                placeholders.join(", "),
+                updates.join(", ")
+            )
+        };
+        self.conn
+            .execute(&sql, rusqlite::params_from_iter(values.iter()))?;
+        Ok(())
+    }
+
+    fn read_rows(&self, structure: &CustomStructure) -> Result<Vec<Row>, Box<dyn Error>> {
+        self.ensure_table(structure)?;
+        let mut columns = vec!["row_id".to_string()];
+        columns.extend(structure.fields.iter().map(|f| quote_ident(&f.id)));
+        let mut stmt = self.conn.prepare(&format!(
+            "SELECT {} FROM {} ORDER BY rowid",
+            columns.join(", "),
+            Self::table_name(&structure.id)
+        ))?;
+
+        let rows = stmt
+            .query_map([], |r| {
+                let mut row = Row {
+                    id: r.get(0)?,
+                    values: HashMap::new(),
+                };
+                for (idx, field) in structure.fields.iter().enumerate() {
+                    let value: Option<String> = r.get(idx + 1)?;
+                    row.values.insert(
+                        field.id.clone(),
+                        FieldValue {
+                            value: value.unwrap_or_default(),
+                            field_type: field.field_type.clone(),
+                        },
+                    );
+                }
+                Ok(row)
+            })?
+            .collect::<Result<_, _>>()?;
+        Ok(rows)
+    }
+}
+
+fn quote_ident(name: &str) -> String {
+    format!("\"{}\"", name.replace('"', "\"\""))
+}
+
+fn all_structures(structures: &[CustomCategory]) -> impl Iterator<Item = &CustomStructure> {
+    structures
+        .iter()
+        .flat_map(|c| c.subcategories.iter())
+        .flat_map(|s| s.structures.iter())
+}
+
+impl Storage for SqliteStorage {
+    fn kind(&self) -> StorageKind {
+        StorageKind::Sqlite
+    }
+
+    // WAL 모드에서는 최근 변경이 -wal 파일에 먼저 기록된다
+    fn modified(&self) -> Option<SystemTime> {
+        [self.path.clone(), sibling(&self.path, "-wal")]
+            .iter()
+            .filter_map(|p| fs::metadata(p).and_then(|m| m.modified()).ok())
+            .max()
+    }
+
+    fn load_structures(&mut self) -> Result<Vec<CustomCategory>, Box<dyn Error>> {
+        let json: Option<String> = self
+            .conn
+            .query_row("SELECT value FROM meta WHERE key = 'structures'", [], |r| {
+                r.get(0)
+            })
+            .optional()?;
+        match json {
+            Some(json) => Ok(serde_json::from_str(&json)?),
+            None => Ok(Vec::new()),
+        }
+    }
+
+    fn save_structures(&mut self, structures: &[CustomCategory]) -> Result<(), Box<dyn Error>> {
+        self.conn.execute(
+            "INSERT INTO meta (key, value) VALUES ('structures', ?1)
+             ON CONFLICT(key) DO UPDATE SET value = excluded.value",
+            params![serde_json::to_string(structures)?],
+        )?;
+        for structure in all_structures(structures) {
+            self.ensure_table(structure)?;
+        }
+        Ok(())
+    }
+
+    fn load_data(&mut self, structures: &[CustomCategory]) -> Result<ERPData, Box<dyn Error>> {
+        let mut data = ERPData {
+            version: ERP_DATA_VERSION,
+            ..Default::default()
+        };
+        for structure in all_structures(structures) {
+            data.data
+                .insert(structure.id.clone(), self.read_rows(structure)?);
+        }
+        Ok(data)
+    }
+
+    fn save_data(
+        &mut self,
+        structures: &[CustomCategory],
+        data: &ERPData,
+    ) -> Result<(), Box<dyn Error>> {
+        for structure in all_structures(structures) {
+            if let Some(rows) = data.data.get(&structure.id) {
+                self.replace_rows(structure, rows)?;
+            }
+        }
+        Ok(())
+    }
+
+    fn replace_rows(
+        &mut self,
+        structure: &CustomStructure,
+        rows: &[Row],
+    ) -> Result<(), Box<dyn Error>> {
+        self.ensure_table(structure)?;
+        self.conn.execute_batch("BEGIN")?;
+        let result = (|| -> Result<(), Box<dyn Error>> {
+            self.conn.execute(
+                &format!("DELETE FROM {}", Self::table_name(&structure.id)),
+                [],
+            )?;
+            for row in rows {
+                self.write_row(structure, row)?;
+            }
+            Ok(())
+        })();
+        match result {
+            Ok(()) => {
+                self.conn.execute_batch("COMMIT")?;
+                Ok(())
+            }
+            Err(e) => {
+                self.conn.execute_batch("ROLLBACK")?;
+                Err(e)
+            }
+        }
+    }
+
+    fn upsert_row(&mut self, structure: &CustomStructure, row: &Row) -> Result<(), Box<dyn Error>> {
+        self.ensure_table(structure)?;
+        self.write_row(structure, row)
+    }
+
+    fn delete_row(
+        &mut self,
+        structure: &CustomStructure,
+        row_id: &str,
+    ) -> Result<(), Box<dyn Error>> {
+        self.ensure_table(structure)?;
+        self.conn.execute(
+            &format!(
+                "DELETE FROM {} WHERE row_id = ?1",
+                Self::table_name(&structure.id)
+            ),
+            params![row_id],
+        )?;
+        Ok(())
+    }
+}
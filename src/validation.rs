@@ -0,0 +1,556 @@
+// 필드 제약 조건: 필수, 구조체 안에서 고유, 숫자 최소/최대, 글자 수, 정규식
+use crate::csv_import::ImportError;
+use crate::currency;
+use crate::dates;
+use crate::formula::Formulas;
+use crate::korean_id;
+use crate::line_items::apply_subtotals;
+use crate::schema::convert_value;
+use crate::select;
+use crate::{CustomStructure, ERPApp, Field, FieldType, Row};
+use eframe::egui;
+use egui::ScrollArea;
+use regex::Regex;
+use rust_decimal::prelude::ToPrimitive;
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+use std::rc::Rc;
+
+#[derive(Clone, Default, Serialize, Deserialize, PartialEq, Debug)]
+pub(crate) struct FieldConstraints {
+    #[serde(default)]
+    pub(crate) required: bool,
+    #[serde(default)]
+    pub(crate) unique: bool,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub(crate) min: Option<f64>, // 숫자 필드만
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub(crate) max: Option<f64>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub(crate) min_length: Option<usize>, // 글자 수
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub(crate) max_length: Option<usize>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub(crate) pattern: Option<String>, // 값 전체가 맞아야 하는 정규식
+}
+
+impl FieldConstraints {
+    pub(crate) fn is_empty(&self) -> bool {
+        *self == FieldConstraints::default()
+    }
+}
+
+// 값 전체와 맞춰 보도록 앵커를 붙여 컴파일
+fn compile_pattern(pattern: &str) -> Result<Regex, String> {
+    Regex::new(&format!("^(?:{})$", pattern)).map_err(|e| format!("잘못된 정규식: {}", e))
+}
+
+// 고유성 외의 조건 검사. 빈 값은 필수 조건만 본다
+fn check_value(
+    field: &Field,
+    pattern: Option<&Result<Regex, String>>,
+    value: &str,
+) -> Option<String> {
+    let constraints = &field.constraints;
+    if value.trim().is_empty() {
+        return constraints.required.then(|| "필수 값입니다".to_string());
+    }
+
+    if matches!(field.field_type, FieldType::Number | FieldType::Currency) {
+        if let Some(number) = currency::parse_decimal(value).and_then(|n| n.to_f64()) {
+            if let Some(min) = constraints.min.filter(|min| number < *min) {
+                return Some(format!("{} 이상이어야 합니다", min));
+            }
+            if let Some(max) = constraints.max.filter(|max| number > *max) {
+                return Some(format!("{} 이하여야 합니다", max));
+            }
+        }
+    }
+
+    if select::is_select(&field.field_type) {
+        if let Err(e) = select::normalize(field, value) {
+            return Some(e);
+        }
+    }
+
+    // 예전에 입력한 읽을 수 없는 날짜/시간, 금액
+    if dates::is_temporal(&field.field_type) {
+        if let Err(e) = convert_value(value, &field.field_type) {
+            return Some(e);
+        }
+    }
+    if field.field_type == FieldType::Currency {
+        if let Err(e) = currency::normalize_field(field, value) {
+            return Some(e);
+        }
+    }
+    if korean_id::is_identifier(&field.field_type) {
+        if let Err(e) = korean_id::normalize(&field.field_type, value) {
+            return Some(e);
+        }
+    }
+
+    let length = value.chars().count();
+    if let Some(min) = constraints.min_length.filter(|min| length < *min) {
+        return Some(format!("{}자 이상이어야 합니다", min));
+    }
+    if let Some(max) = constraints.max_length.filter(|max| length > *max) {
+        return Some(format!("{}자 이하여야 합니다", max));
+    }
+
+    match pattern {
+        Some(Ok(regex)) if !regex.is_match(value) => Some(format!(
+            "형식이 맞지 않습니다 ({})",
+            constraints.pattern.as_deref().unwrap_or_default()
+        )),
+        Some(Err(e)) => Some(e.clone()),
+        _ => None,
+    }
+}
+
+fn field_value<'a>(row: &'a Row, field_id: &str) -> &'a str {
+    row.values
+        .get(field_id)
+        .map(|v| v.value.as_str())
+        .unwrap_or_default()
+}
+
+#[derive(Clone, Debug)]
+pub(crate) struct Violation {
+    pub(crate) field_id: String,
+    pub(crate) field_name: String,
+    pub(crate) value: String,
+    pub(crate) message: String,
+}
+
+// 구조체의 필드 목록과 한 번만 컴파일한 정규식. 화면에서는 구조체별로 캐시해 둔다
+pub(crate) struct CompiledConstraints {
+    fields: Vec<Field>,
+    patterns: HashMap<String, Result<Regex, String>>,
+}
+
+impl CompiledConstraints {
+    pub(crate) fn compile(structure: &CustomStructure) -> Self {
+        let patterns = structure
+            .fields
+            .iter()
+            .filter_map(|f| {
+                let pattern = f.constraints.pattern.as_deref()?;
+                Some((f.id.clone(), compile_pattern(pattern)))
+            })
+            .collect();
+        CompiledConstraints {
+            fields: structure.fields.clone(),
+            patterns,
+        }
+    }
+}
+
+// 컴파일한 제약 조건에 고유 필드의 값 개수를 세어 두는 검사기
+pub(crate) struct RowValidator {
+    constraints: Rc<CompiledConstraints>,
+    unique: HashMap<String, HashMap<String, usize>>, // 필드 ID -> 값 -> 행 수
+}
+
+impl RowValidator {
+    pub(crate) fn new(structure: &CustomStructure, rows: &[Row]) -> Self {
+        Self::with_constraints(Rc::new(CompiledConstraints::compile(structure)), rows)
+    }
+
+    pub(crate) fn with_constraints(constraints: Rc<CompiledConstraints>, rows: &[Row]) -> Self {
+        let unique = constraints
+            .fields
+            .iter()
+            .filter(|f| f.constraints.unique)
+            .map(|f| (f.id.clone(), HashMap::new()))
+            .collect();
+        let mut validator = RowValidator {
+            constraints,
+            unique,
+        };
+        for row in rows {
+            validator.add(row);
+        }
+        validator
+    }
+
+    pub(crate) fn add(&mut self, row: &Row) {
+        for (field_id, counts) in &mut self.unique {
+            let value = field_value(row, field_id).trim();
+            if !value.is_empty() {
+                *counts.entry(value.to_string()).or_default() += 1;
+            }
+        }
+    }
+
+    pub(crate) fn remove(&mut self, row: &Row) {
+        for (field_id, counts) in &mut self.unique {
+            if let Some(count) = counts.get_mut(field_value(row, field_id).trim()) {
+                *count = count.saturating_sub(1);
+            }
+        }
+    }
+
+    // 행의 위반 목록. others는 고유성 검사에서 이미 있는 것으로 볼 같은 값의 행 수
+    fn violations(&self, row: &Row, others: usize) -> Vec<Violation> {
+        let mut violations = Vec::new();
+        for field in &self.constraints.fields {
+            let value = field_value(row, &field.id);
+            let mut message = check_value(field, self.constraints.patterns.get(&field.id), value);
+            if message.is_none() && field.constraints.unique && !value.trim().is_empty() {
+                let count = self
+                    .unique
+                    .get(&field.id)
+                    .and_then(|counts| counts.get(value.trim()))
+                    .copied()
+                    .unwrap_or(0);
+                if count > others {
+                    message = Some("이미 같은 값이 있습니다".to_string());
+                }
+            }
+            if let Some(message) = message {
+                violations.push(Violation {
+                    field_id: field.id.clone(),
+                    field_name: field.name.clone(),
+                    value: value.to_string(),
+                    message,
+                });
+            }
+        }
+        violations
+    }
+
+    // 아직 추가하지 않은 행을 검사
+    pub(crate) fn check(&self, row: &Row) -> Vec<Violation> {
+        self.violations(row, 0)
+    }
+}
+
+// 화면 표시용: (행 ID, 필드 ID) -> 위반 내용. 겹치는 값은 해당 행 모두 표시.
+// 매 프레임 불리므로 정규식은 캐시해 둔 것을 쓰고 고유 값 개수만 다시 센다
+pub(crate) fn validate_rows(
+    constraints: &Rc<CompiledConstraints>,
+    rows: &[Row],
+) -> HashMap<(String, String), String> {
+    let validator = RowValidator::with_constraints(constraints.clone(), rows);
+    let mut result = HashMap::new();
+    for row in rows {
+        for violation in validator.violations(row, 1) {
+            result.insert((row.id.clone(), violation.field_id), violation.message);
+        }
+    }
+    result
+}
+
+// base 뒤에 candidates를 하나씩 붙이며 검사해 위반 없는 행만 받아들인다.
+// 거부한 행은 (candidates 안의 순번, 위반 목록)
+pub(crate) fn accept_rows(
+    structure: &CustomStructure,
+    base: &[Row],
+    candidates: Vec<Row>,
+) -> (Vec<Row>, Vec<(usize, Vec<Violation>)>) {
+    let mut validator = RowValidator::new(structure, base);
+    let mut accepted = Vec::new();
+    let mut rejected = Vec::new();
+    for (idx, row) in candidates.into_iter().enumerate() {
+        let violations = validator.check(&row);
+        if violations.is_empty() {
+            validator.add(&row);
+            accepted.push(row);
+        } else {
+            rejected.push((idx, violations));
+        }
+    }
+    (accepted, rejected)
+}
+
+// 가져오기 창에 보여 줄 형태로 (행 번호는 1부터)
+pub(crate) fn rejected_to_errors(rejected: &[(usize, Vec<Violation>)]) -> Vec<ImportError> {
+    rejected
+        .iter()
+        .flat_map(|(idx, violations)| {
+            violations.iter().map(move |v| ImportError {
+                row: idx + 1,
+                column: v.field_name.clone(),
+                value: v.value.clone(),
+                error: v.message.clone(),
+            })
+        })
+        .collect()
+}
+
+// 가져오기 창 아래의 제약 조건 위반 목록
+pub(crate) fn render_violations(ui: &mut egui::Ui, id: &str, violations: &[ImportError]) {
+    if violations.is_empty() {
+        return;
+    }
+    let mut rows: Vec<usize> = violations.iter().map(|v| v.row).collect();
+    rows.dedup();
+    ui.colored_label(
+        egui::Color32::RED,
+        format!(
+            "제약 조건 위반 {}행 (이 행들은 가져오지 않습니다)",
+            rows.len()
+        ),
+    );
+    ScrollArea::vertical()
+        .id_salt(id)
+        .max_height(140.0)
+        .show(ui, |ui| {
+            for violation in violations {
+                ui.label(format!(
+                    "{}행 {}: \"{}\" - {}",
+                    violation.row, violation.column, violation.value, violation.error
+                ));
+            }
+        });
+}
+
+fn optional_number(ui: &mut egui::Ui, label: &str, value: &mut Option<f64>) {
+    let mut enabled = value.is_some();
+    if ui.checkbox(&mut enabled, label).changed() {
+        *value = enabled.then_some(0.0);
+    }
+    if let Some(number) = value {
+        ui.add(egui::DragValue::new(number));
+    }
+}
+
+fn optional_length(ui: &mut egui::Ui, label: &str, value: &mut Option<usize>) {
+    let mut enabled = value.is_some();
+    if ui.checkbox(&mut enabled, label).changed() {
+        *value = enabled.then_some(1);
+    }
+    if let Some(length) = value {
+        ui.add(egui::DragValue::new(length).range(0..=10_000));
+    }
+}
+
+// 구조체 편집기의 필드별 제약 조건 편집
+pub(crate) fn constraints_editor(ui: &mut egui::Ui, idx: usize, field: &mut Field) {
+    let title = if field.constraints.is_empty() {
+        "제약 조건".to_string()
+    } else {
+        "제약 조건 ✔".to_string()
+    };
+    egui::CollapsingHeader::new(title)
+        .id_salt(("field_constraints", idx))
+        .show(ui, |ui| {
+            let constraints = &mut field.constraints;
+            ui.horizontal(|ui| {
+                ui.checkbox(&mut constraints.required, "필수");
+                ui.checkbox(&mut constraints.unique, "고유");
+            });
+            if matches!(field.field_type, FieldType::Number | FieldType::Currency) {
+                ui.horizontal(|ui| {
+                    optional_number(ui, "최소", &mut constraints.min);
+                    optional_number(ui, "최대", &mut constraints.max);
+                });
+            }
+            // 식별 번호는 형식이 정해져 있다
+            if !matches!(
+                field.field_type,
+                FieldType::Boolean
+                    | FieldType::Table
+                    | FieldType::Attachment
+                    | FieldType::ResidentNumber
+                    | FieldType::BusinessNumber
+                    | FieldType::Phone
+            ) {
+                ui.horizontal(|ui| {
+                    optional_length(ui, "최소 글자 수", &mut constraints.min_length);
+                    optional_length(ui, "최대 글자 수", &mut constraints.max_length);
+                });
+                ui.horizontal(|ui| {
+                    let mut pattern = constraints.pattern.clone().unwrap_or_default();
+                    ui.label("정규식:");
+                    if ui.text_edit_singleline(&mut pattern).changed() {
+                        constraints.pattern = (!pattern.is_empty()).then_some(pattern.clone());
+                    }
+                    if let Some(Err(e)) = constraints.pattern.as_deref().map(compile_pattern) {
+                        ui.colored_label(egui::Color32::RED, e);
+                    }
+                });
+            }
+        });
+}
+
+impl ERPApp {
+    // 제약 조건을 통과한 행만 기존 데이터 뒤에 붙이고 저장. 거부한 행을 돌려준다.
+    // 참조 값은 미리보기에서 resolve_with로 행 ID로 바꿔 둔 상태여야 한다
+    pub(crate) fn insert_rows(
+        &mut self,
+        structure: &CustomStructure,
+        mut rows: Vec<Row>,
+    ) -> Vec<(usize, Vec<Violation>)> {
+        let formulas = Formulas::compile(structure);
+        for row in &mut rows {
+            apply_subtotals(structure, row);
+            formulas.apply(row);
+        }
+        let existing = self
+            .erp_data
+            .data
+            .get(&structure.id)
+            .cloned()
+            .unwrap_or_default();
+        let (accepted, rejected) = accept_rows(structure, &existing, rows);
+        self.erp_data
+            .data
+            .entry(structure.id.clone())
+            .or_default()
+            .extend(accepted);
+        self.save_structure_rows(&structure.id);
+        self.save_to_csv(&structure.id);
+        rejected
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::FieldValue;
+
+    fn field(name: &str, field_type: FieldType) -> Field {
+        Field {
+            name: name.to_string(),
+            field_type,
+            ..Field::new()
+        }
+    }
+
+    fn row(values: &[(&Field, &str)]) -> Row {
+        let mut row = Row::new();
+        for (field, value) in values {
+            row.values.insert(
+                field.id.clone(),
+                FieldValue {
+                    value: value.to_string(),
+                    field_type: field.field_type.clone(),
+                },
+            );
+        }
+        row
+    }
+
+    fn structure(fields: &[&Field]) -> CustomStructure {
+        CustomStructure {
+            fields: fields.iter().map(|f| (*f).clone()).collect(),
+            ..CustomStructure::new()
+        }
+    }
+
+    fn messages(field: &Field, values: &[&str]) -> Vec<Option<String>> {
+        let validator = RowValidator::new(&structure(&[field]), &[]);
+        values
+            .iter()
+            .map(|value| {
+                validator
+                    .check(&row(&[(field, value)]))
+                    .pop()
+                    .map(|v| v.message)
+            })
+            .collect()
+    }
+
+    #[test]
+    fn pattern_must_match_whole_value() {
+        let mut code = field("코드", FieldType::Text);
+        code.constraints.pattern = Some("[A-Z]{3}".to_string());
+        let result = messages(&code, &["USD", "USDX", "xUSD", ""]);
+        assert_eq!(result[0], None);
+        assert!(result[1]
+            .as_deref()
+            .unwrap()
+            .starts_with("형식이 맞지 않습니다"));
+        assert!(result[2].is_some());
+        // 빈 값은 필수가 아니면 통과
+        assert_eq!(result[3], None);
+
+        // 대안(|)도 값 전체에 묶인다
+        code.constraints.pattern = Some("A|B".to_string());
+        assert!(messages(&code, &["A", "AB"])[1].is_some());
+    }
+
+    #[test]
+    fn invalid_pattern_is_reported() {
+        let mut code = field("코드", FieldType::Text);
+        code.constraints.pattern = Some("[".to_string());
+        let result = messages(&code, &["A"]);
+        assert!(result[0].as_deref().unwrap().starts_with("잘못된 정규식"));
+    }
+
+    #[test]
+    fn number_min_max() {
+        let mut qty = field("수량", FieldType::Number);
+        qty.constraints.min = Some(0.0);
+        qty.constraints.max = Some(10.0);
+        assert_eq!(
+            messages(&qty, &["0", "10", "-1", "10.5"]),
+            vec![
+                None,
+                None,
+                Some("0 이상이어야 합니다".to_string()),
+                Some("10 이하여야 합니다".to_string()),
+            ]
+        );
+    }
+
+    #[test]
+    fn length_counts_characters() {
+        let mut name = field("이름", FieldType::Text);
+        name.constraints.required = true;
+        name.constraints.min_length = Some(2);
+        name.constraints.max_length = Some(3);
+        assert_eq!(
+            messages(&name, &["홍길동", "홍", "홍길동전", " "]),
+            vec![
+                None,
+                Some("2자 이상이어야 합니다".to_string()),
+                Some("3자 이하여야 합니다".to_string()),
+                Some("필수 값입니다".to_string()),
+            ]
+        );
+    }
+
+    #[test]
+    fn unique_marks_every_duplicate_row() {
+        let mut code = field("코드", FieldType::Text);
+        code.constraints.unique = true;
+        let rows = vec![
+            row(&[(&code, "A-1")]),
+            row(&[(&code, " A-1 ")]),
+            row(&[(&code, "A-2")]),
+            row(&[(&code, "")]),
+            row(&[(&code, "")]),
+        ];
+        let constraints = Rc::new(CompiledConstraints::compile(&structure(&[&code])));
+        let violations = validate_rows(&constraints, &rows);
+        assert_eq!(violations.len(), 2);
+        for row in &rows[..2] {
+            assert_eq!(
+                violations[&(row.id.clone(), code.id.clone())],
+                "이미 같은 값이 있습니다"
+            );
+        }
+    }
+
+    #[test]
+    fn accept_rows_rejects_later_duplicates() {
+        let mut code = field("코드", FieldType::Text);
+        code.constraints.unique = true;
+        let structure = structure(&[&code]);
+        let base = vec![row(&[(&code, "A-1")])];
+        let candidates = vec![
+            row(&[(&code, "A-1")]),
+            row(&[(&code, "A-2")]),
+            row(&[(&code, "A-2")]),
+        ];
+        let (accepted, rejected) = accept_rows(&structure, &base, candidates);
+        assert_eq!(accepted.len(), 1);
+        assert_eq!(
+            rejected.iter().map(|(idx, _)| *idx).collect::<Vec<_>>(),
+            vec![0, 2]
+        );
+    }
+}
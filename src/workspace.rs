@@ -0,0 +1,336 @@
+// 워크스페이스: 구조체 정의, 데이터, 백업, 첨부 파일을 담는 폴더
+use crate::safe_file::atomic_write;
+use crate::{CustomCategory, CustomStructure, ERPApp};
+use eframe::egui;
+use egui::{Context, ScrollArea};
+use rfd::FileDialog;
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+use std::error::Error;
+use std::fs;
+use std::io;
+use std::path::{Path, PathBuf};
+
+const BACKUPS_DIR: &str = "backups";
+const ATTACHMENTS_DIR: &str = "attachments";
+const MAX_RECENT: usize = 10;
+const CSV_FILES: &str = "csv_files.json";
+
+#[derive(Clone, Debug, Default)]
+pub(crate) struct Workspace {
+    root: PathBuf,
+    csv_files: HashMap<String, String>, // 구조체 ID -> backups 폴더 안의 CSV 파일 이름
+}
+
+impl Workspace {
+    // 폴더가 없으면 만들고 하위 폴더(backups, attachments)도 준비
+    pub(crate) fn open(root: &Path) -> io::Result<Self> {
+        fs::create_dir_all(root)?;
+        let root = root.canonicalize()?;
+        fs::create_dir_all(root.join(BACKUPS_DIR))?;
+        fs::create_dir_all(root.join(ATTACHMENTS_DIR))?;
+        let csv_files = fs::read_to_string(root.join(CSV_FILES))
+            .ok()
+            .and_then(|data| serde_json::from_str(&data).ok())
+            .unwrap_or_default();
+        Ok(Workspace { root, csv_files })
+    }
+
+    pub(crate) fn root(&self) -> &Path {
+        &self.root
+    }
+
+    pub(crate) fn structures_path(&self) -> PathBuf {
+        self.root.join("custom_structures.json")
+    }
+
+    pub(crate) fn data_path(&self) -> PathBuf {
+        self.root.join("erp_data.json")
+    }
+
+    pub(crate) fn sqlite_path(&self) -> PathBuf {
+        self.root.join("erp_data.sqlite")
+    }
+
+    pub(crate) fn settings_path(&self) -> PathBuf {
+        self.root.join("erp_settings.json")
+    }
+
+    // 일련번호 기본값의 마지막 번호
+    pub(crate) fn sequences_path(&self) -> PathBuf {
+        self.root.join("sequences.json")
+    }
+
+    pub(crate) fn backups_dir(&self) -> PathBuf {
+        self.root.join(BACKUPS_DIR)
+    }
+
+    // 첨부 파일 저장소 (내용 해시 이름)
+    pub(crate) fn attachments_dir(&self) -> PathBuf {
+        self.root.join(ATTACHMENTS_DIR)
+    }
+
+    // 구조체 CSV 백업 경로. 파일 이름은 구조체 이름이 아니라 ID로 찾는다
+    pub(crate) fn csv_path(&self, structure: &CustomStructure) -> PathBuf {
+        let file_name = match self.csv_files.get(&structure.id) {
+            Some(file_name) => file_name.clone(),
+            None => format!("{}.csv", sanitize_file_name(&structure.name)),
+        };
+        self.backups_dir().join(file_name)
+    }
+
+    // 파일 이름이 없는 구조체에 안전하고 겹치지 않는 이름을 정해 csv_files.json에 기록.
+    // 이름 기반으로 저장되던 이전 CSV 파일은 새 이름으로 옮긴다
+    pub(crate) fn assign_csv_files(
+        &mut self,
+        structures: &[CustomCategory],
+    ) -> Result<(), Box<dyn Error>> {
+        let mut changed = false;
+        let all = structures
+            .iter()
+            .flat_map(|c| c.subcategories.iter())
+            .flat_map(|s| s.structures.iter());
+        for structure in all {
+            if self.csv_files.contains_key(&structure.id) {
+                continue;
+            }
+            let legacy = self.legacy_csv_path(&structure.name);
+            let file_name = self.unused_file_name(&sanitize_file_name(&structure.name), &legacy);
+            let path = self.backups_dir().join(&file_name);
+            if let Some(legacy) = legacy {
+                if legacy != path {
+                    if fs::rename(&legacy, &path).is_err() {
+                        fs::copy(&legacy, &path)?;
+                        fs::remove_file(&legacy)?;
+                    }
+                    println!("CSV 파일 이동: {} -> {}", legacy.display(), path.display());
+                }
+            }
+            self.csv_files.insert(structure.id.clone(), file_name);
+            changed = true;
+        }
+
+        if changed {
+            atomic_write(
+                &self.root.join(CSV_FILES),
+                serde_json::to_string_pretty(&self.csv_files)?.as_bytes(),
+            )?;
+        }
+        Ok(())
+    }
+
+    // 이전 버전이 `<이름>.csv`로 저장한 파일 (워크스페이스 루트 또는 backups 폴더).
+    // 경로 구분자가 들어간 이름은 워크스페이스 밖을 가리킬 수 있으므로 무시
+    fn legacy_csv_path(&self, structure_name: &str) -> Option<PathBuf> {
+        let file_name = format!("{}.csv", structure_name);
+        let mut components = Path::new(&file_name).components();
+        let single = matches!(
+            (components.next(), components.next()),
+            (Some(std::path::Component::Normal(_)), None)
+        );
+        if !single || structure_name.contains(['/', '\\']) {
+            return None;
+        }
+        // backups 폴더의 파일이 이미 다른 구조체에 배정되었으면 이전 파일이 아님
+        let assigned = self.csv_files.values().any(|f| *f == file_name);
+        let mut candidates = vec![self.root.join(&file_name)];
+        if !assigned {
+            candidates.insert(0, self.backups_dir().join(&file_name));
+        }
+        candidates.into_iter().find(|path| path.is_file())
+    }
+
+    // 다른 구조체가 쓰는 이름이나 이미 있는 파일과 겹치지 않는 이름 (대소문자 무시)
+    fn unused_file_name(&self, base: &str, legacy: &Option<PathBuf>) -> String {
+        let mut n = 1;
+        loop {
+            let file_name = if n == 1 {
+                format!("{}.csv", base)
+            } else {
+                format!("{} ({}).csv", base, n)
+            };
+            let lower = file_name.to_lowercase();
+            let taken = self.csv_files.values().any(|f| f.to_lowercase() == lower);
+            let path = self.backups_dir().join(&file_name);
+            let occupied = path.exists() && legacy.as_ref() != Some(&path);
+            if !taken && !occupied {
+                return file_name;
+            }
+            n += 1;
+        }
+    }
+}
+
+// 파일 이름으로 쓸 수 없는 문자와 Windows 예약 이름을 피한 이름
+pub(crate) fn sanitize_file_name(name: &str) -> String {
+    let mut sanitized: String = name
+        .chars()
+        .map(|c| match c {
+            '/' | '\\' | ':' | '*' | '?' | '"' | '<' | '>' | '|' => '_',
+            c if c.is_control() => '_',
+            c => c,
+        })
+        .take(100)
+        .collect();
+    // Windows는 끝의 점과 공백을 지운다
+    sanitized = sanitized.trim().trim_end_matches('.').to_string();
+    if sanitized.is_empty() || sanitized.chars().all(|c| c == '.' || c == '_') {
+        sanitized = format!("structure{}", sanitized);
+    }
+
+    const RESERVED: [&str; 4] = ["CON", "PRN", "AUX", "NUL"];
+    let stem = sanitized
+        .split('.')
+        .next()
+        .unwrap_or_default()
+        .to_uppercase();
+    let numbered = (stem.starts_with("COM") || stem.starts_with("LPT"))
+        && stem.len() == 4
+        && stem[3..].chars().all(|c| c.is_ascii_digit());
+    if RESERVED.contains(&stem.as_str()) || numbered {
+        sanitized.insert(0, '_');
+    }
+    sanitized
+}
+
+// 최근 연 워크스페이스 목록 (사용자 설정 폴더에 저장)
+#[derive(Clone, Debug, Default, Serialize, Deserialize)]
+pub(crate) struct RecentWorkspaces {
+    #[serde(default)]
+    pub(crate) paths: Vec<PathBuf>,
+}
+
+impl RecentWorkspaces {
+    fn file_path() -> Option<PathBuf> {
+        dirs::config_dir().map(|dir| dir.join("erp_rs").join("recent_workspaces.json"))
+    }
+
+    pub(crate) fn load() -> Self {
+        Self::file_path()
+            .and_then(|path| fs::read_to_string(path).ok())
+            .and_then(|data| serde_json::from_str(&data).ok())
+            .unwrap_or_default()
+    }
+
+    pub(crate) fn save(&self) -> Result<(), Box<dyn Error>> {
+        let path = Self::file_path().ok_or("사용자 설정 폴더를 찾을 수 없습니다")?;
+        if let Some(dir) = path.parent() {
+            fs::create_dir_all(dir)?;
+        }
+        atomic_write(&path, serde_json::to_string_pretty(self)?.as_bytes())?;
+        Ok(())
+    }
+
+    // 가장 최근 항목을 맨 앞에
+    pub(crate) fn add(&mut self, path: &Path) {
+        self.paths.retain(|p| p != path);
+        self.paths.insert(0, path.to_path_buf());
+        self.paths.truncate(MAX_RECENT);
+    }
+
+    pub(crate) fn remove(&mut self, path: &Path) {
+        self.paths.retain(|p| p != path);
+    }
+}
+
+// --workspace <폴더> 또는 --workspace=<폴더>
+pub(crate) fn workspace_arg() -> Option<PathBuf> {
+    let mut args = std::env::args().skip(1);
+    while let Some(arg) = args.next() {
+        if arg == "--workspace" {
+            return args.next().map(PathBuf::from);
+        }
+        if let Some(path) = arg.strip_prefix("--workspace=") {
+            return Some(PathBuf::from(path));
+        }
+    }
+    None
+}
+
+impl ERPApp {
+    // 워크스페이스를 열고 이전 워크스페이스 상태는 모두 비움
+    pub(crate) fn open_workspace(&mut self, path: &Path) {
+        let workspace = match Workspace::open(path) {
+            Ok(workspace) => workspace,
+            Err(e) => {
+                eprintln!("워크스페이스를 열 수 없습니다 ({}): {}", path.display(), e);
+                return;
+            }
+        };
+
+        let mut recent = std::mem::take(&mut self.recent_workspaces);
+        recent.add(workspace.root());
+        if let Err(e) = recent.save() {
+            eprintln!("최근 워크스페이스 목록 저장 실패: {}", e);
+        }
+
+        *self = Self::default();
+        self.recent_workspaces = recent;
+        println!("워크스페이스: {}", workspace.root().display());
+        self.workspace = Some(workspace);
+        self.open_storage();
+        self.load_all();
+    }
+
+    pub(crate) fn render_workspace_picker(&mut self, ctx: &Context) {
+        let mut open = None;
+        let mut forget = None;
+
+        egui::CentralPanel::default().show(ctx, |ui| {
+            ui.vertical_centered(|ui| {
+                ui.add_space(60.0);
+                ui.heading("워크스페이스 선택");
+                ui.label("구조체 정의, 데이터, 백업, 첨부 파일을 저장할 폴더를 선택하세요");
+                ui.add_space(20.0);
+
+                ui.horizontal(|ui| {
+                    if ui.button("📁 폴더 선택...").clicked() {
+                        if let Some(path) = FileDialog::new()
+                            .set_title("워크스페이스 폴더 선택")
+                            .pick_folder()
+                        {
+                            open = Some(path);
+                        }
+                    }
+                    if ui.button("현재 폴더 사용").clicked() {
+                        match std::env::current_dir() {
+                            Ok(path) => open = Some(path),
+                            Err(e) => eprintln!("현재 폴더를 알 수 없습니다: {}", e),
+                        }
+                    }
+                });
+
+                if !self.recent_workspaces.paths.is_empty() {
+                    ui.add_space(20.0);
+                    ui.label("최근 워크스페이스");
+                    ui.separator();
+                    ScrollArea::vertical()
+                        .id_salt("recent_workspaces")
+                        .max_height(300.0)
+                        .show(ui, |ui| {
+                            for path in &self.recent_workspaces.paths {
+                                ui.horizontal(|ui| {
+                                    if ui.button(path.display().to_string()).clicked() {
+                                        open = Some(path.clone());
+                                    }
+                                    if ui.small_button("✖").clicked() {
+                                        forget = Some(path.clone());
+                                    }
+                                });
+                            }
+                        });
+                }
+            });
+        });
+
+        if let Some(path) = forget {
+            self.recent_workspaces.remove(&path);
+            if let Err(e) = self.recent_workspaces.save() {
+                eprintln!("최근 워크스페이스 목록 저장 실패: {}", e);
+            }
+        }
+        if let Some(path) = open {
+            self.open_workspace(&path);
+        }
+    }
+}